@@ -3,6 +3,7 @@
 use egui::*;
 
 use crate::{
+    bone_panel,
     shared::{Shared, Vec2},
     ui as ui_mod,
 };
@@ -17,29 +18,33 @@ pub fn draw(egui_ctx: &Context, shared: &mut Shared) {
         .show(egui_ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.heading("Armature");
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui_mod::button("New", ui).clicked() {
+                        shared.new_armature();
+                        shared.ui.original_name = "".to_string();
+                        shared.ui.rename_id =
+                            "armature ".to_owned() + &shared.armature_idx.to_string();
+                    }
+                });
             });
 
             ui.separator();
 
+            armatures(ui, shared);
+
+            ui.separator();
+
             ui.horizontal(|ui| {
                 if ui_mod::button("New Bone", ui).clicked() {
                     let idx: usize;
-                    let bone: Bone;
                     if shared.selected_bone() == None {
-                        (bone, idx) = new_bone(shared, -1);
+                        (_, idx) = new_bone(shared, -1);
                     } else {
-                        (bone, idx) = new_bone(shared, shared.selected_bone().unwrap().id);
+                        (_, idx) = new_bone(shared, shared.selected_bone().unwrap().id);
                     }
 
                     // immediately select new bone upon creating it
                     shared.select_bone(idx);
-
-                    shared.undo_actions.push(Action {
-                        action: ActionEnum::Bone,
-                        action_type: ActionType::Created,
-                        id: bone.id,
-                        ..Default::default()
-                    });
                 }
                 let drag_name = if shared.ui.dragging { "Edit" } else { "Drag" };
                 if shared.armature.bones.len() > 1 && ui_mod::button(drag_name, ui).clicked() {
@@ -60,8 +65,9 @@ pub fn draw(egui_ctx: &Context, shared: &mut Shared) {
             ui.dnd_drop_zone::<i32, _>(frame, |ui| {
                 ui.set_min_width(ui.available_width());
                 let mut idx = 0;
+                let mut rows: Vec<egui::Rect> = vec![];
                 for s in shared.armature.bones.clone() {
-                    ui.horizontal(|ui| {
+                    let row = ui.horizontal(|ui| {
                         // add space to the left if this is a child
                         let mut nb: &Bone = &s;
                         while nb.parent_id != -1 {
@@ -91,18 +97,31 @@ pub fn draw(egui_ctx: &Context, shared: &mut Shared) {
 
                             if ui_mod::selection_button(
                                 &s.name.to_string(),
-                                idx as usize == shared.selected_bone_idx,
+                                shared.is_selected(s.id),
                                 ui,
                             )
                             .clicked()
                             {
-                                shared.select_bone(idx as usize);
+                                let modifiers = ui.input(|i| i.modifiers);
+                                if modifiers.command {
+                                    shared.toggle_selection(idx as usize);
+                                } else if modifiers.shift {
+                                    shared.add_to_selection(idx as usize);
+                                } else {
+                                    shared.select_bone(idx as usize);
+                                }
                             };
                         }
                         idx += 1;
                     });
+                    rows.push(row.response.rect);
                 }
+                box_select(ui, shared, &rows);
             });
+
+            ui.add_space(3.);
+            ui.separator();
+            draw_order(ui, shared);
         })
         .response;
     if response.hovered() {
@@ -110,6 +129,129 @@ pub fn draw(egui_ctx: &Context, shared: &mut Shared) {
     }
 }
 
+/// Dragging over the hierarchy while holding shift selects the bones of the rows it
+/// covers, or unselects them while holding ctrl.
+fn box_select(ui: &mut egui::Ui, shared: &mut Shared, rows: &[egui::Rect]) {
+    // only senses drags, so the buttons underneath can still be clicked
+    let id = ui.id().with("box_select");
+    let area = ui.interact(ui.min_rect(), id, egui::Sense::drag());
+    let (modifiers, pointer) = ui.input(|i| (i.modifiers, i.pointer.latest_pos()));
+    if area.drag_started() {
+        ui.data_mut(|d| d.insert_temp(id, pointer));
+    }
+    if !modifiers.shift && !modifiers.command {
+        return;
+    }
+    let Some(Some(start)) = ui.data(|d| d.get_temp::<Option<egui::Pos2>>(id)) else {
+        return;
+    };
+    let Some(pointer) = pointer else {
+        return;
+    };
+    let rect = egui::Rect::from_two_pos(start, pointer);
+
+    if area.dragged() {
+        shared.ui.draw_rect(rect, ui);
+    } else if area.drag_stopped() {
+        ui.data_mut(|d| d.remove::<Option<egui::Pos2>>(id));
+        for (idx, row) in rows.iter().enumerate() {
+            if !rect.intersects(*row) {
+                continue;
+            }
+            if modifiers.command {
+                shared.unselect_bone(idx);
+            } else {
+                shared.add_to_selection(idx);
+            }
+        }
+    }
+}
+
+/// Slots from front to back. Reordering them while animating keys the draw order on
+/// the selected frame.
+fn draw_order(ui: &mut egui::Ui, shared: &mut Shared) {
+    if shared.armature.slots.is_empty() {
+        return;
+    }
+
+    ui.heading("Draw Order")
+        .on_hover_text("Slots at the top are drawn in front of the ones below");
+
+    let mut order = shared.draw_order();
+    let mut moved = None;
+    for pos in (0..order.len()).rev() {
+        let idx = order[pos];
+        let name = shared.armature.slots[idx].name.clone();
+        let bone_id = shared.armature.slots[idx].bone_id;
+        ui.push_id(("draw_order", idx), |ui| {
+            ui.horizontal(|ui| {
+                let selected = shared.selected_slot_idx == idx && shared.selected_slot().is_some();
+                if ui_mod::selection_button(&name, selected, ui).clicked() {
+                    if let Some(bone_idx) =
+                        shared.armature.bones.iter().position(|b| b.id == bone_id)
+                    {
+                        shared.select_bone(bone_idx);
+                        shared.selected_slot_idx = idx;
+                    }
+                }
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if pos > 0 && ui_mod::button("v", ui).clicked() {
+                        moved = Some((pos, pos - 1));
+                    }
+                    if pos + 1 < order.len() && ui_mod::button("^", ui).clicked() {
+                        moved = Some((pos, pos + 1));
+                    }
+                });
+            });
+        });
+    }
+
+    if let Some((from, to)) = moved {
+        order.swap(from, to);
+        shared.set_draw_order(order);
+    }
+}
+
+/// List of the project's armatures, and where the selected one is placed.
+fn armatures(ui: &mut egui::Ui, shared: &mut Shared) {
+    for i in 0..shared.armatures.len() {
+        // initialize renaming
+        let rename_id = "armature ".to_owned() + &i.to_string();
+        let mut name = shared.project_armature(i).name.clone();
+        if shared.ui.check_renaming(&rename_id, &mut name, ui, |_| {}) {
+            shared.project_armature_mut(i).name = name;
+            continue;
+        }
+
+        if ui_mod::selection_button(&name, i == shared.armature_idx, ui).clicked() {
+            if shared.armature_idx != i {
+                shared.switch_armature(i);
+            } else {
+                shared.ui.rename_id = rename_id;
+            }
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Place:");
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            let mut pos = shared.armature.pos;
+            let id = "armature_y".to_string();
+            (_, pos.y) = bone_panel::float_input(id, shared, ui, pos.y, 1.);
+            ui.label("Y");
+            let id = "armature_x".to_string();
+            (_, pos.x) = bone_panel::float_input(id, shared, ui, pos.x, 1.);
+            ui.label("X");
+            shared.armature.pos = pos;
+        });
+    });
+
+    if shared.armatures.len() > 1 && ui_mod::button("Delete Armature", ui).clicked() {
+        shared.ui.polar_id = "delete_armature".to_string();
+        shared.ui.polar_headline = "Are you sure to delete this armature?".to_string();
+    }
+}
+
 pub fn new_bone(shared: &mut Shared, id: i32) -> (Bone, usize) {
     let mut parent_id = -1;
     if shared.find_bone(id) != None {
@@ -120,9 +262,7 @@ pub fn new_bone(shared: &mut Shared, id: i32) -> (Bone, usize) {
         parent_id,
         id: generate_id(&shared.armature.bones),
         scale: Vec2 { x: 1., y: 1. },
-        tex_idx: -1,
         pivot: Vec2::new(0.5, 0.5),
-        zindex: shared.armature.bones.len() as f32,
         ..Default::default()
     };
     if id == -1 {
@@ -182,14 +322,6 @@ fn check_bone_dragging(shared: &mut Shared, ui: &mut egui::Ui, drag: Response, i
                 }
             }
 
-            shared.undo_actions.push(Action{
-                action: ActionEnum::Bone,
-                action_type: ActionType::Edited,
-                id: shared.armature.bones[*dragged_payload as usize].id,
-                bone: shared.armature.bones[*dragged_payload as usize].clone(),
-                ..Default::default()
-            });
-
             if move_type == 0 {
                 // set dragged bone's parent as target
                 shared.armature.bones[*dragged_payload as usize].parent_id = shared.armature.bones[idx as usize].parent_id;
@@ -0,0 +1,273 @@
+//! Packing textures into atlas pages (MaxRects, best short side fit).
+
+use crate::runtime::{Texture, Vec2};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct AtlasConfig {
+    /// Largest width and height of a page. Textures that don't fit get a page of their own.
+    #[serde(default = "default_max_size")]
+    pub max_size: u32,
+    /// Empty pixels between textures.
+    #[serde(default)]
+    pub padding: u32,
+    /// How many times the edge pixels of each texture are repeated around it,
+    /// so that filtering doesn't bleed neighbours in.
+    #[serde(default)]
+    pub extrude: u32,
+}
+
+fn default_max_size() -> u32 {
+    2048
+}
+
+impl Default for AtlasConfig {
+    fn default() -> Self {
+        AtlasConfig {
+            max_size: default_max_size(),
+            padding: 2,
+            extrude: 1,
+        }
+    }
+}
+
+/// Where a rect ended up.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Placement {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Rect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+impl Rect {
+    fn right(&self) -> u32 {
+        self.x + self.w
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y + self.h
+    }
+
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+}
+
+/// A single page, tracking the (possibly overlapping) free rects left in it.
+struct Page {
+    free: Vec<Rect>,
+    used: Rect,
+}
+
+impl Page {
+    fn new(width: u32, height: u32) -> Page {
+        Page {
+            free: vec![Rect {
+                x: 0,
+                y: 0,
+                w: width,
+                h: height,
+            }],
+            used: Rect {
+                x: 0,
+                y: 0,
+                w: 0,
+                h: 0,
+            },
+        }
+    }
+
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rect> {
+        let best = self
+            .free
+            .iter()
+            .filter(|f| f.w >= w && f.h >= h)
+            .min_by_key(|f| {
+                let (dw, dh) = (f.w - w, f.h - h);
+                (dw.min(dh), dw.max(dh))
+            })?;
+        let placed = Rect {
+            x: best.x,
+            y: best.y,
+            w,
+            h,
+        };
+        self.split(&placed);
+        self.used.w = self.used.w.max(placed.right());
+        self.used.h = self.used.h.max(placed.bottom());
+        Some(placed)
+    }
+
+    /// Cut the used rect out of every free rect it overlaps.
+    fn split(&mut self, used: &Rect) {
+        let mut free: Vec<Rect> = vec![];
+        for f in &self.free {
+            if !f.intersects(used) {
+                free.push(*f);
+                continue;
+            }
+            if used.x > f.x {
+                free.push(Rect {
+                    w: used.x - f.x,
+                    ..*f
+                });
+            }
+            if used.right() < f.right() {
+                free.push(Rect {
+                    x: used.right(),
+                    w: f.right() - used.right(),
+                    ..*f
+                });
+            }
+            if used.y > f.y {
+                free.push(Rect {
+                    h: used.y - f.y,
+                    ..*f
+                });
+            }
+            if used.bottom() < f.bottom() {
+                free.push(Rect {
+                    y: used.bottom(),
+                    h: f.bottom() - used.bottom(),
+                    ..*f
+                });
+            }
+        }
+
+        // drop rects that are inside others (keeping one of any duplicates)
+        self.free = free
+            .iter()
+            .enumerate()
+            .filter(|(i, a)| {
+                !free
+                    .iter()
+                    .enumerate()
+                    .any(|(j, b)| *i != j && b.contains(a) && (*a != b || j < *i))
+            })
+            .map(|(_, a)| *a)
+            .collect();
+    }
+}
+
+/// Pack rects of the given sizes into as few pages as possible.
+///
+/// Returns where each rect went, and the size of each page (cropped to what's used).
+pub fn pack(sizes: &[(u32, u32)], max_size: u32) -> (Vec<Placement>, Vec<(u32, u32)>) {
+    let mut placements = vec![Placement::default(); sizes.len()];
+    let mut pages: Vec<Page> = vec![];
+
+    // big rects first, so that small ones fill the gaps
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|i| {
+        let (w, h) = sizes[*i];
+        std::cmp::Reverse((w.max(h), w * h))
+    });
+
+    for i in order {
+        let (w, h) = sizes[i];
+        if w == 0 || h == 0 {
+            continue;
+        }
+
+        let mut found = None;
+        for (p, page) in pages.iter_mut().enumerate() {
+            if let Some(rect) = page.insert(w, h) {
+                found = Some((p, rect));
+                break;
+            }
+        }
+
+        let (page, rect) = match found {
+            Some(found) => found,
+            None => {
+                pages.push(Page::new(max_size.max(w), max_size.max(h)));
+                let rect = pages.last_mut().unwrap().insert(w, h).unwrap();
+                (pages.len() - 1, rect)
+            }
+        };
+        placements[i] = Placement {
+            page,
+            x: rect.x,
+            y: rect.y,
+        };
+    }
+
+    let page_sizes = pages.iter().map(|p| (p.used.w, p.used.h)).collect();
+    (placements, page_sizes)
+}
+
+/// Pack textures into page images, updating their offsets and page indices to match.
+///
+/// Offsets point at the texture itself, inside of its extrusion.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build(textures: &mut [Texture], config: &AtlasConfig) -> Vec<image::RgbaImage> {
+    let border = config.extrude * 2 + config.padding;
+    let sizes: Vec<(u32, u32)> = textures
+        .iter()
+        .map(|tex| (tex.size.x as u32 + border, tex.size.y as u32 + border))
+        .collect();
+    let (placements, page_sizes) = pack(&sizes, config.max_size);
+
+    let mut pages: Vec<image::RgbaImage> = page_sizes
+        .iter()
+        .map(|(w, h)| image::RgbaImage::new(*w, *h))
+        .collect();
+
+    for (tex, placement) in textures.iter_mut().zip(placements) {
+        let (w, h) = (tex.size.x as u32, tex.size.y as u32);
+        if w == 0 || h == 0 {
+            continue;
+        }
+        let x = placement.x + config.extrude;
+        let y = placement.y + config.extrude;
+        tex.offset = Vec2::new(x as f32, y as f32);
+        tex.page = placement.page;
+
+        let page = &mut pages[placement.page];
+        let extrude = config.extrude as i64;
+        for py in -extrude..h as i64 + extrude {
+            for px in -extrude..w as i64 + extrude {
+                // pixels outside of the texture repeat its nearest edge
+                let sx = px.clamp(0, w as i64 - 1) as u32;
+                let sy = py.clamp(0, h as i64 - 1) as u32;
+                let idx = ((sy * w + sx) * 4) as usize;
+                let Some(pixel) = tex.pixels.get(idx..idx + 4) else {
+                    continue;
+                };
+                page.put_pixel(
+                    (x as i64 + px) as u32,
+                    (y as i64 + py) as u32,
+                    image::Rgba([pixel[0], pixel[1], pixel[2], pixel[3]]),
+                );
+            }
+        }
+    }
+
+    pages
+}
+
+/// Name of a page's image in saved files.
+pub fn page_file(page: usize) -> String {
+    if page == 0 {
+        "textures.png".to_string()
+    } else {
+        format!("textures_{}.png", page)
+    }
+}
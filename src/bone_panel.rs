@@ -62,29 +62,6 @@ pub fn draw(ui: &mut egui::Ui, shared: &mut Shared) {
             .labelled_by(l.id);
     });
 
-    ui.horizontal(|ui| {
-        ui.label("Texture:");
-        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            if ui_mod::button("Get Image", ui).clicked() {
-                if shared.bind_groups.len() == 0 {
-                    #[cfg(not(target_arch = "wasm32"))]
-                    open_file_dialog();
-
-                    #[cfg(target_arch = "wasm32")]
-                    toggleFileDialog(true);
-                } else {
-                    shared.ui.image_modal = true;
-                }
-            };
-            let mut tex_name = "None";
-            if shared.selected_bone().unwrap().tex_idx != -1 {
-                tex_name =
-                    &shared.armature.textures[shared.selected_bone().unwrap().tex_idx as usize].name
-            }
-            ui.label(tex_name);
-        })
-    });
-
     ui.add_space(3.5);
 
     let mut bone = shared.selected_bone().unwrap().clone();
@@ -98,7 +75,7 @@ pub fn draw(ui: &mut egui::Ui, shared: &mut Shared) {
         ($float:expr, $id:expr, $element:expr, $modifier:expr, $ui:expr, $label:expr) => {
             (edited, $float) = float_input($id.to_string(), shared, $ui, $float, $modifier);
             if edited {
-                shared.save_edited_bone();
+                shared.history.seal();
                 shared.edit_bone($element, $float, true);
             }
             if $label != "" {
@@ -149,14 +126,440 @@ pub fn draw(ui: &mut egui::Ui, shared: &mut Shared) {
             );
         });
     });
+
+    ui.add_space(3.);
+    ui.separator();
+    ui.add_space(3.);
+    draw_slots_section(ui, shared);
+
+    if shared.selected_slot().is_some_and(|s| s.tex_idx != -1) {
+        ui.add_space(3.);
+        ui.separator();
+        ui.add_space(3.);
+        draw_regions_section(ui, shared);
+    }
+    if shared.bone_tex_size(bone.id).is_some() {
+        ui.add_space(3.);
+        ui.separator();
+        ui.add_space(3.);
+        draw_mesh_section(ui, shared);
+    }
+
+    ui.add_space(3.);
+    ui.separator();
+    ui.add_space(3.);
+
+    let id = bone.id;
+    let ik_idx = shared
+        .armature
+        .ik_constraints
+        .iter()
+        .position(|c| c.bone_id == id);
+    if ik_idx.is_none() {
+        if ui_mod::button("Add IK", ui)
+            .on_hover_text("Rotate this bone's parents so it reaches for a target")
+            .clicked()
+        {
+            shared.armature.ik_constraints.push(IkConstraint {
+                bone_id: id,
+                ..Default::default()
+            });
+        }
+        return;
+    }
+    let ik_idx = ik_idx.unwrap();
+
+    ui.horizontal(|ui| {
+        ui.heading("IK");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui_mod::button("Remove", ui).clicked() {
+                shared.armature.ik_constraints.remove(ik_idx);
+            }
+        });
+    });
+    if ik_idx >= shared.armature.ik_constraints.len() {
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        label!("Target:", ui);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let mut target_name = "None".to_string();
+            let target_id = shared.armature.ik_constraints[ik_idx].target_id;
+            if let Some(target) = shared.find_bone(target_id) {
+                target_name = target.name.clone();
+            }
+            egui::ComboBox::new("ik_target", "")
+                .selected_text(target_name)
+                .show_ui(ui, |ui| {
+                    let target_id = &mut shared.armature.ik_constraints[ik_idx].target_id;
+                    ui.selectable_value(target_id, -1, "None");
+                    for b in &shared.armature.bones {
+                        if b.id != id {
+                            ui.selectable_value(target_id, b.id, b.name.clone());
+                        }
+                    }
+                });
+        });
+    });
+    ui.horizontal(|ui| {
+        label!("Chain:", ui);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.add(
+                egui::DragValue::new(&mut shared.armature.ik_constraints[ik_idx].chain_length)
+                    .range(1..=16)
+                    .speed(0.1),
+            )
+            .on_hover_text("How many parents are rotated");
+        });
+    });
+    ui.horizontal(|ui| {
+        label!("Bend:", ui);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.checkbox(
+                &mut shared.armature.ik_constraints[ik_idx].bend_positive,
+                "Positive",
+            );
+        });
+    });
+    ui.horizontal(|ui| {
+        label!("Mix:", ui);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let mut mix = shared.armature.ik_constraints[ik_idx].mix;
+            if shared.is_animating() {
+                mix = sample(
+                    shared.selected_animation().unwrap(),
+                    id,
+                    AnimElement::IkMix,
+                    mix,
+                    shared.ui.anim.selected_frame as f32,
+                );
+            }
+            input!(mix, "ik_mix", &AnimElement::IkMix, 1., ui, "");
+        });
+    });
+}
+
+/// What's drawn on the selected bone, and the settings of the selected slot.
+fn draw_slots_section(ui: &mut egui::Ui, shared: &mut Shared) {
+    let bone_id = shared.selected_bone().unwrap().id;
+
+    ui.horizontal(|ui| {
+        ui.heading("Slots");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui_mod::button("Add", ui)
+                .on_hover_text("Draw another texture on this bone, in front of everything")
+                .clicked()
+            {
+                shared.selected_slot_idx = shared.add_slot(bone_id);
+            }
+        });
+    });
+
+    let mut removed = None;
+    for i in 0..shared.armature.slots.len() {
+        if shared.armature.slots[i].bone_id != bone_id {
+            continue;
+        }
+        ui.push_id(("slot", i), |ui| {
+            ui.horizontal(|ui| {
+                let name = shared.armature.slots[i].name.clone();
+                if ui
+                    .selectable_label(shared.selected_slot_idx == i, name)
+                    .clicked()
+                {
+                    shared.selected_slot_idx = i;
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui_mod::button("X", ui).clicked() {
+                        removed = Some(i);
+                    }
+                });
+            });
+        });
+    }
+    if let Some(i) = removed {
+        shared.remove_slot(i);
+    }
+
+    ui.add_space(3.);
+
+    if shared.selected_slot().is_some() {
+        ui.horizontal(|ui| {
+            let l = ui.label("Name:");
+            ui.text_edit_singleline(&mut shared.selected_slot_mut().unwrap().name)
+                .labelled_by(l.id);
+        });
+    }
+
+    // while animating, this is what's attached on the selected frame (and picking keys it)
+    let shown = shared
+        .selected_slot()
+        .map(|_| shared.slots()[shared.selected_slot_idx].clone());
+
+    // picking a texture without a slot makes one for it
+    ui.horizontal(|ui| {
+        ui.label("Texture:");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui_mod::button("Get Image", ui).clicked() {
+                if shared.bind_groups.is_empty() {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    open_file_dialog();
+
+                    #[cfg(target_arch = "wasm32")]
+                    toggleFileDialog(true);
+                } else {
+                    shared.ui.image_modal = true;
+                }
+            };
+            let mut tex_name = "None";
+            if let Some(slot) = shown.as_ref().filter(|s| s.tex_idx != -1) {
+                tex_name = &shared.armature.textures[slot.tex_idx as usize].name
+            }
+            ui.label(tex_name);
+        })
+    });
+
+    let Some(slot) = shown else {
+        return;
+    };
+
+    if slot.tex_idx != -1 && !shared.armature.textures[slot.tex_idx as usize].regions.is_empty() {
+        ui.horizontal(|ui| {
+            ui.label("Region:");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let regions = shared.armature.textures[slot.tex_idx as usize].regions.clone();
+                let mut selected = slot.region_idx;
+                let region_name = match regions.get(slot.region_idx as usize) {
+                    Some(region) if slot.region_idx != -1 => region.name.clone(),
+                    _ => "Whole Image".to_string(),
+                };
+                egui::ComboBox::new("region", "")
+                    .selected_text(region_name)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, -1, "Whole Image");
+                        for (i, region) in regions.iter().enumerate() {
+                            ui.selectable_value(&mut selected, i as i32, region.name.clone());
+                        }
+                    });
+                if selected != slot.region_idx {
+                    shared.set_attachment(slot.tex_idx, selected);
+                }
+            });
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Color:");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let color = &mut shared.selected_slot_mut().unwrap().color;
+            let mut rgba = [color.r, color.g, color.b, color.a];
+            ui.color_edit_button_rgba_unmultiplied(&mut rgba);
+            *color = Color::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+        });
+    });
+
     ui.horizontal(|ui| {
-        label!("Z-Index:", ui);
+        ui.label("Blend:");
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            input!(bone.zindex, "zindex", &AnimElement::Zindex, 1., ui, "");
+            let blend = &mut shared.selected_slot_mut().unwrap().blend;
+            egui::ComboBox::new("blend", "")
+                .selected_text(blend.to_string())
+                .show_ui(ui, |ui| {
+                    for mode in BlendMode::ALL {
+                        ui.selectable_value(blend, mode, mode.to_string());
+                    }
+                });
         });
     });
 }
 
+/// Regions of the selected slot's texture, so that it can be used as a sprite sheet.
+fn draw_regions_section(ui: &mut egui::Ui, shared: &mut Shared) {
+    let tex_idx = shared.selected_slot().unwrap().tex_idx;
+    let tex = &shared.armature.textures[tex_idx as usize];
+    let (tex_size, tex_name) = (tex.size, tex.name.clone());
+
+    ui.horizontal(|ui| {
+        ui.heading("Regions");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui_mod::button("Detect", ui)
+                .on_hover_text("Make a region for every island of opaque pixels")
+                .clicked()
+            {
+                let tex = &shared.armature.textures[tex_idx as usize];
+                let regions = crate::regions::alpha_islands(&tex.pixels, tex_size, &tex_name);
+                shared.set_regions(tex_idx, regions);
+            }
+            if ui_mod::button("Add", ui).clicked() {
+                let regions = &mut shared.armature.textures[tex_idx as usize].regions;
+                regions.push(Region {
+                    name: format!("{}_{}", tex_name, regions.len()),
+                    offset: Vec2::ZERO,
+                    size: tex_size,
+                });
+            }
+        });
+    });
+
+    let grid = &mut shared.ui.region_grid;
+    grid.0 = grid.0.max(1);
+    grid.1 = grid.1.max(1);
+    ui.horizontal(|ui| {
+        ui.label("Grid:");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui_mod::button("Split", ui)
+                .on_hover_text("Split the image into equally sized regions")
+                .clicked()
+            {
+                let (columns, rows) = shared.ui.region_grid;
+                let regions = crate::regions::grid(tex_size, columns, rows, &tex_name);
+                shared.set_regions(tex_idx, regions);
+            }
+            let grid = &mut shared.ui.region_grid;
+            ui.add(egui::DragValue::new(&mut grid.1).range(1..=256).speed(0.1));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut grid.0).range(1..=256).speed(0.1));
+        });
+    });
+
+    let mut removed = None;
+    let regions = &mut shared.armature.textures[tex_idx as usize].regions;
+    for (i, region) in regions.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut region.name).desired_width(120.));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui_mod::button("X", ui).clicked() {
+                        removed = Some(i);
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut region.offset.x).range(0.0..=tex_size.x));
+                ui.add(egui::DragValue::new(&mut region.offset.y).range(0.0..=tex_size.y));
+                ui.add(
+                    egui::DragValue::new(&mut region.size.x)
+                        .range(1.0..=tex_size.x - region.offset.x),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut region.size.y)
+                        .range(1.0..=tex_size.y - region.offset.y),
+                );
+            });
+        });
+    }
+    if let Some(region_idx) = removed {
+        shared.remove_region(tex_idx, region_idx as i32);
+    }
+}
+
+fn draw_mesh_section(ui: &mut egui::Ui, shared: &mut Shared) {
+    let is_mesh = shared.selected_bone().unwrap().is_mesh;
+    ui.horizontal(|ui| {
+        ui.heading("Mesh");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if is_mesh && ui_mod::button("Remove", ui).clicked() {
+                shared.history.seal();
+                crate::mesh_editor::remove_mesh(shared.selected_bone_mut().unwrap());
+                if shared.edit_mode == EditMode::Mesh {
+                    shared.edit_mode = EditMode::Move;
+                }
+            } else if !is_mesh
+                && ui_mod::button("Create", ui)
+                    .on_hover_text("Make the texture deformable, by editing its vertices")
+                    .clicked()
+            {
+                shared.history.seal();
+                let bone = shared.selected_bone().unwrap();
+                let tex_size = shared.bone_tex_size(bone.id).unwrap();
+                crate::mesh_editor::make_mesh(shared.selected_bone_mut().unwrap(), tex_size);
+                shared.edit_mode = EditMode::Mesh;
+            }
+        });
+    });
+
+    if !is_mesh {
+        return;
+    }
+
+    let vert = shared.ui.selected_vertex;
+    if vert.is_none() || vert.unwrap() >= shared.selected_bone().unwrap().weights.len() {
+        ui.label("Select a vertex in Mesh mode to edit its weights.");
+        return;
+    }
+    let vert = vert.unwrap();
+
+    ui.label("Weights:").on_hover_text(
+        "How much each bone moves this vertex. Vertices without weights follow this bone.",
+    );
+
+    let bones: Vec<(i32, String)> = shared
+        .armature
+        .bones
+        .iter()
+        .map(|b| (b.id, b.name.clone()))
+        .collect();
+
+    let mut removed = None;
+    for w in 0..shared.selected_bone().unwrap().weights[vert].len() {
+        ui.horizontal(|ui| {
+            let weight = shared.selected_bone().unwrap().weights[vert][w];
+            let mut bone_id = weight.bone_id;
+            let mut value = weight.weight;
+
+            let mut name = "None".to_string();
+            if let Some(b) = bones.iter().find(|b| b.0 == bone_id) {
+                name = b.1.clone();
+            }
+            egui::ComboBox::new(("weight_bone", w), "")
+                .selected_text(name)
+                .width(80.)
+                .show_ui(ui, |ui| {
+                    for b in &bones {
+                        ui.selectable_value(&mut bone_id, b.0, b.1.clone());
+                    }
+                });
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui_mod::button("X", ui).clicked() {
+                    removed = Some(w);
+                }
+                let drag = ui.add(
+                    egui::DragValue::new(&mut value)
+                        .range(0..=1)
+                        .speed(0.01),
+                );
+                if drag.drag_started() || drag.gained_focus() {
+                    shared.history.seal();
+                }
+            });
+
+            if bone_id != weight.bone_id {
+                shared.history.seal();
+            }
+            shared.selected_bone_mut().unwrap().weights[vert][w] = BoneWeight {
+                bone_id,
+                weight: value,
+            };
+        });
+    }
+
+    if let Some(w) = removed {
+        shared.history.seal();
+        shared.selected_bone_mut().unwrap().weights[vert].remove(w);
+    }
+
+    if ui_mod::button("Add Weight", ui).clicked() {
+        shared.history.seal();
+        let id = shared.selected_bone().unwrap().id;
+        shared.selected_bone_mut().unwrap().weights[vert].push(BoneWeight {
+            bone_id: id,
+            weight: 1.,
+        });
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn open_file_dialog() {
     #[cfg(not(target_arch = "wasm32"))]
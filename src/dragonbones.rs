@@ -0,0 +1,1504 @@
+//! Converting between armatures and DragonBones JSON (`name_ske.json`), with its
+//! texture atlases (`name_tex.json`).
+//!
+//! DragonBones is y-down, with clockwise rotations in degrees, and its units are
+//! pixels (`HARD_SCALE` of a world unit). As with `spine`, every imported slot gets
+//! a bone of its own to carry its display's offset and pivot, parented to the slot's
+//! bone.
+//!
+//! Both directions return warnings like `spine`'s.
+
+use serde_json::{json, Map, Value};
+
+use crate::runtime::*;
+use crate::spine::{num, str_of};
+
+/// Version of the JSON written by `export`.
+pub const DB_VERSION: &str = "5.5";
+
+const DEFAULT_FPS: i32 = 24;
+
+/// A texture of a DragonBones atlas.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubTexture {
+    pub name: String,
+    /// Where the texture is in the atlas, and its size before rotation.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Packed rotated by 90 degrees (clockwise).
+    pub rotated: bool,
+    /// Where the original image starts, relative to the packed texture (so, usually
+    /// negative), and its size. Only set if its transparent edges were stripped.
+    pub frame_x: i32,
+    pub frame_y: i32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+/// Parse a DragonBones texture atlas (`name_tex.json`), returning the path of its
+/// image and its textures.
+pub fn parse_tex(json: &str) -> Result<(String, Vec<SubTexture>), String> {
+    let root: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let Some(sub_textures) = root["SubTexture"].as_array() else {
+        return Err("That's not a DragonBones atlas (it has no SubTexture).".to_string());
+    };
+
+    let int = |value: &Value, key: &str| value[key].as_f64().unwrap_or(0.).round() as i32;
+    let sub_textures = sub_textures
+        .iter()
+        .map(|sub| SubTexture {
+            name: str_of(sub, "name"),
+            x: int(sub, "x").max(0) as u32,
+            y: int(sub, "y").max(0) as u32,
+            width: int(sub, "width").max(0) as u32,
+            height: int(sub, "height").max(0) as u32,
+            rotated: sub["rotated"].as_bool().unwrap_or(false),
+            frame_x: int(sub, "frameX"),
+            frame_y: int(sub, "frameY"),
+            frame_width: int(sub, "frameWidth").max(0) as u32,
+            frame_height: int(sub, "frameHeight").max(0) as u32,
+        })
+        .collect();
+    Ok((str_of(&root, "imagePath"), sub_textures))
+}
+
+/// Cut a texture out of its atlas image, undoing its rotation and whitespace stripping.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn extract_sub_texture(atlas: &image::RgbaImage, sub: &SubTexture) -> Option<Texture> {
+    let (packed_w, packed_h) = match sub.rotated {
+        true => (sub.height, sub.width),
+        false => (sub.width, sub.height),
+    };
+    if sub.x + packed_w > atlas.width() || sub.y + packed_h > atlas.height() {
+        return None;
+    }
+
+    let mut packed = image::imageops::crop_imm(atlas, sub.x, sub.y, packed_w, packed_h).to_image();
+    if sub.rotated {
+        packed = image::imageops::rotate270(&packed);
+    }
+
+    let width = sub.frame_width.max(sub.width);
+    let height = sub.frame_height.max(sub.height);
+    let mut img = image::RgbaImage::new(width, height);
+    image::imageops::replace(&mut img, &packed, -sub.frame_x as i64, -sub.frame_y as i64);
+
+    Some(Texture {
+        name: sub.name.clone(),
+        size: Vec2::new(width as f32, height as f32),
+        pixels: img.into_raw().into(),
+        ..Default::default()
+    })
+}
+
+/// Convert DragonBones JSON to armatures (one for each of its own), returning them
+/// with warnings about anything that was left out.
+///
+/// `textures` are the images that displays can use, named after their paths
+/// (see `extract_sub_texture`).
+pub fn import(ske: &str, textures: &[Texture]) -> Result<(Vec<Armature>, Vec<String>), String> {
+    let root: Value = serde_json::from_str(ske).map_err(|e| e.to_string())?;
+    let Some(db_armatures) = root["armature"].as_array() else {
+        return Err("That's not a DragonBones skeleton (it has no armatures).".to_string());
+    };
+
+    let fps = fps_of(&root).unwrap_or(DEFAULT_FPS);
+    let mut warnings: Vec<String> = vec![];
+    let mut armatures = vec![];
+    for db_armature in db_armatures {
+        let mut armature_warnings = vec![];
+        let armature = import_armature(db_armature, fps, textures, &mut armature_warnings)?;
+        warnings.extend(
+            armature_warnings
+                .into_iter()
+                .map(|warning| format!("{}: {}", armature.name, warning)),
+        );
+        armatures.push(armature);
+    }
+    Ok((armatures, warnings))
+}
+
+fn import_armature(
+    db_armature: &Value,
+    fps: i32,
+    textures: &[Texture],
+    warnings: &mut Vec<String>,
+) -> Result<Armature, String> {
+    let mut armature = Armature {
+        name: str_of(db_armature, "name"),
+        textures: textures.to_vec(),
+        ..Default::default()
+    };
+    let fps = fps_of(db_armature).unwrap_or(fps);
+
+    // bones
+    let db_bones = db_armature["bone"].as_array().map_or(&[][..], |b| &b[..]);
+    let mut bone_ids: Vec<(String, i32)> = vec![];
+    for (i, db_bone) in db_bones.iter().enumerate() {
+        let name = str_of(db_bone, "name");
+        let parent_id = match db_bone["parent"].as_str() {
+            Some(parent) => match bone_ids.iter().find(|(n, _)| n == parent) {
+                Some((_, id)) => *id,
+                None => return Err(format!("Bone '{}' has an unknown parent.", name)),
+            },
+            None => -1,
+        };
+        bone_ids.push((name.clone(), i as i32));
+
+        for inherit in ["inheritTranslation", "inheritRotation", "inheritScale"] {
+            if db_bone[inherit].as_bool() == Some(false) {
+                warnings.push(format!("Bone '{}': {} is always on.", name, inherit));
+            }
+        }
+
+        let mut bone = Bone {
+            id: i as i32,
+            name: name.clone(),
+            parent_id,
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        };
+        set_transform(&mut bone, &db_bone["transform"], &name, warnings);
+        armature.bones.push(bone);
+    }
+
+    // displays of the first skin, by slot name
+    let skins = db_armature["skin"].as_array().map_or(&[][..], |s| &s[..]);
+    let skin = skins.first().map_or(&[][..], |skin| {
+        skin["slot"].as_array().map_or(&[][..], |s| &s[..])
+    });
+    if skins.len() > 1 {
+        let others: Vec<String> = skins[1..].iter().map(|s| str_of(s, "name")).collect();
+        warnings.push(format!(
+            "Only the first skin is imported, so these were left out: {}",
+            others.join(", ")
+        ));
+    }
+
+    // slots, each with a bone carrying its display's offset, in order of their z
+    let db_slots = db_armature["slot"].as_array().map_or(&[][..], |s| &s[..]);
+    let mut by_z: Vec<usize> = (0..db_slots.len()).collect();
+    by_z.sort_by_key(|i| db_slots[*i]["z"].as_i64().unwrap_or(*i as i64));
+    // index of each DragonBones slot in the armature's, if it was kept
+    let mut slot_idx: Vec<Option<usize>> = vec![None; db_slots.len()];
+    for db_idx in by_z {
+        let slot = &db_slots[db_idx];
+        let name = str_of(slot, "name");
+        let parent = slot["parent"].as_str().unwrap_or("");
+        let Some((_, parent_id)) = bone_ids.iter().find(|(n, _)| n == parent) else {
+            warnings.push(format!(
+                "Slot '{}' is on an unknown bone, so it was left out.",
+                name
+            ));
+            continue;
+        };
+
+        // multipliers are in percent, and offsets (from 0 to 255) are added on top
+        let color = &slot["color"];
+        let multiplier = |c: &str| color[c].as_f64().map_or(1., |v| v as f32 / 100.);
+        let tint = Color::new(
+            multiplier("rM"),
+            multiplier("gM"),
+            multiplier("bM"),
+            multiplier("aM"),
+        );
+        let offset = ["aO", "rO", "gO", "bO"]
+            .iter()
+            .any(|c| color[c].as_f64().is_some_and(|v| v != 0.));
+        if offset {
+            warnings.push(format!(
+                "Slot '{}': color offsets aren't supported.",
+                name
+            ));
+        }
+        let blend = match slot["blendMode"].as_str().unwrap_or("normal") {
+            "normal" => BlendMode::Normal,
+            "add" => BlendMode::Additive,
+            "multiply" => BlendMode::Multiply,
+            "screen" => BlendMode::Screen,
+            other => {
+                warnings.push(format!(
+                    "Slot '{}': the {} blend mode isn't supported.",
+                    name, other
+                ));
+                BlendMode::Normal
+            }
+        };
+
+        let mut bone = Bone {
+            id: armature.bones.len() as i32,
+            name: name.clone(),
+            parent_id: *parent_id,
+            scale: Vec2::new(1., 1.),
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        };
+        let mut tex_idx = -1;
+
+        let displays = skin
+            .iter()
+            .find(|s| str_of(s, "name") == name)
+            .and_then(|s| s["display"].as_array())
+            .map_or(&[][..], |d| &d[..]);
+        let display_idx = slot["displayIndex"].as_i64().unwrap_or(0);
+        if displays.len() > 1 {
+            warnings.push(format!(
+                "Slot '{}': only its setup display is imported, so {} others were left out.",
+                name,
+                displays.len() - 1
+            ));
+        }
+
+        let display = usize::try_from(display_idx)
+            .ok()
+            .and_then(|idx| displays.get(idx));
+        if let Some(display) = display {
+            let kind = display["type"].as_str().unwrap_or("image");
+            if kind != "image" {
+                warnings.push(format!(
+                    "Slot '{}': {} displays aren't supported.",
+                    name, kind
+                ));
+            } else {
+                set_transform(&mut bone, &display["transform"], &name, warnings);
+                if let Some(pivot) = display["pivot"].as_object() {
+                    bone.pivot = Vec2::new(
+                        pivot.get("x").and_then(|x| x.as_f64()).unwrap_or(0.5) as f32,
+                        pivot.get("y").and_then(|y| y.as_f64()).unwrap_or(0.5) as f32,
+                    );
+                }
+
+                let path = display["path"]
+                    .as_str()
+                    .unwrap_or(display["name"].as_str().unwrap_or(""));
+                match armature.textures.iter().position(|t| t.name == path) {
+                    Some(idx) => tex_idx = idx as i32,
+                    None => warnings.push(format!(
+                        "Slot '{}': the image '{}' wasn't found.",
+                        name, path
+                    )),
+                }
+            }
+        }
+
+        slot_idx[db_idx] = Some(armature.slots.len());
+        armature.slots.push(Slot {
+            name,
+            bone_id: bone.id,
+            tex_idx,
+            color: tint,
+            blend,
+            ..Default::default()
+        });
+        armature.bones.push(bone);
+    }
+
+    for (kind, what) in [
+        ("ik", "IK constraints"),
+        ("path", "path constraints"),
+        ("constraint", "constraints"),
+        ("event", "events"),
+    ] {
+        let count = db_armature[kind].as_array().map_or(0, |items| items.len());
+        if count > 0 {
+            warnings.push(format!("{} {} were left out.", count, what));
+        }
+    }
+
+    // animations
+    let animations = db_armature["animation"]
+        .as_array()
+        .map_or(&[][..], |a| &a[..]);
+    for db_anim in animations {
+        let anim_name = str_of(db_anim, "name");
+        let mut anim = Animation {
+            name: anim_name.clone(),
+            fps,
+            ..Default::default()
+        };
+        let mut curves = Curves::default();
+
+        let timelines = db_anim["bone"].as_array().map_or(&[][..], |b| &b[..]);
+        for timeline in timelines {
+            let bone_name = str_of(timeline, "name");
+            let Some((_, bone_id)) = bone_ids.iter().find(|(n, _)| *n == bone_name) else {
+                continue;
+            };
+            for (kind, frames) in timeline.as_object().into_iter().flatten() {
+                let Some(frames) = frames.as_array() else {
+                    continue;
+                };
+                // which elements the frames' values are for, and what values are missing as
+                let elements: &[(&str, AnimElement, f32)] = match kind.as_str() {
+                    "translateFrame" => &[
+                        ("x", AnimElement::PositionX, 0.),
+                        ("y", AnimElement::PositionY, 0.),
+                    ],
+                    "rotateFrame" => &[("rotate", AnimElement::Rotation, 0.)],
+                    "scaleFrame" => &[
+                        ("x", AnimElement::ScaleX, 1.),
+                        ("y", AnimElement::ScaleY, 1.),
+                    ],
+                    "name" => continue,
+                    _ => {
+                        warnings.push(format!(
+                            "Animation '{}': {} of bone '{}' were left out.",
+                            anim_name, kind, bone_name
+                        ));
+                        continue;
+                    }
+                };
+                for (field, element, default) in elements {
+                    let mut values: Vec<f32> =
+                        frames.iter().map(|f| num(f, field, *default)).collect();
+                    if *element == AnimElement::Rotation {
+                        values = unwrap_rotations(frames, &values);
+                    }
+                    // y-down and clockwise
+                    let unit = match element {
+                        AnimElement::Rotation => -1_f32.to_radians(),
+                        AnimElement::PositionX => HARD_SCALE,
+                        AnimElement::PositionY => -HARD_SCALE,
+                        _ => 1.,
+                    };
+                    add_keys(
+                        &mut anim,
+                        &mut curves,
+                        *bone_id,
+                        element.clone(),
+                        frames,
+                        &values,
+                        unit,
+                    );
+                }
+            }
+        }
+
+        // draw orders, as (slot, offset) pairs like in Spine
+        let z_frames = db_anim["zOrder"]["frame"].as_array();
+        let mut frame = 0;
+        for z_frame in z_frames.into_iter().flatten() {
+            let pairs: Vec<i64> = z_frame["zOrder"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_i64())
+                .collect();
+            let offsets: Vec<(usize, i64)> = pairs
+                .chunks_exact(2)
+                .map(|pair| (pair[0].max(0) as usize, pair[1]))
+                .collect();
+            let order = crate::spine::apply_offsets(db_slots.len(), &offsets)
+                .into_iter()
+                .filter_map(|db_idx| slot_idx[db_idx])
+                .collect();
+            anim.keyframes.push(Keyframe {
+                frame,
+                bone_id: -1,
+                element: AnimElement::DrawOrder,
+                element_id: AnimElement::DrawOrder as i32,
+                draw_order: order,
+                ..Default::default()
+            });
+            frame += z_frame["duration"].as_i64().unwrap_or(1) as i32;
+        }
+
+        for (kind, what) in [
+            ("slot", "slot keys (displays and colors)"),
+            ("ffd", "mesh deform keys"),
+            ("mesh", "mesh deform keys"),
+            ("ik", "IK keys"),
+            ("frame", "events"),
+        ] {
+            if db_anim.get(kind).is_some_and(|v| !v.is_null()) {
+                warnings.push(format!(
+                    "Animation '{}': {} were left out.",
+                    anim_name, what
+                ));
+            }
+        }
+        if curves.simplified {
+            warnings.push(format!(
+                "Animation '{}': curves with more than 2 control points were simplified.",
+                anim_name
+            ));
+        }
+        if curves.partial_easing {
+            warnings.push(format!(
+                "Animation '{}': partial eases were approximated with bezier curves.",
+                anim_name
+            ));
+        }
+
+        anim.keyframes.sort_by_key(|kf| kf.frame);
+        armature.animations.push(anim);
+    }
+
+    Ok(armature)
+}
+
+/// Set a bone's position, rotation and scale from a DragonBones transform.
+fn set_transform(bone: &mut Bone, transform: &Value, name: &str, warnings: &mut Vec<String>) {
+    let skew_x = num(transform, "skX", 0.);
+    let skew_y = num(transform, "skY", 0.);
+    if skew_x != skew_y {
+        warnings.push(format!("'{}': skewing isn't supported.", name));
+    }
+    bone.pos = Vec2::new(num(transform, "x", 0.), -num(transform, "y", 0.)) * HARD_SCALE;
+    bone.rot = -skew_y.to_radians();
+    bone.scale = Vec2::new(num(transform, "scX", 1.), num(transform, "scY", 1.));
+}
+
+/// Rotations of a rotate timeline, with the turns DragonBones adds between them.
+///
+/// Rotations take the shortest way around, unless a frame's `clockwise` sets which
+/// way to go to the next one.
+fn unwrap_rotations(frames: &[Value], values: &[f32]) -> Vec<f32> {
+    let mut unwrapped: Vec<f32> = vec![];
+    let mut clockwise: i64 = 0;
+    for (frame, value) in frames.iter().zip(values) {
+        let mut rotation = *value;
+        if let Some(prev) = unwrapped.last() {
+            if clockwise == 0 {
+                let diff = rotation - prev;
+                rotation = prev + diff - 360. * (diff / 360.).round();
+            } else {
+                // one of the turns is taken by the way there
+                let reached = match clockwise > 0 {
+                    true => rotation >= *prev,
+                    false => rotation <= *prev,
+                };
+                if reached {
+                    clockwise -= clockwise.signum();
+                }
+                rotation += 360. * clockwise as f32;
+            }
+        }
+        unwrapped.push(rotation);
+        clockwise = frame
+            .get("clockwise")
+            .or(frame.get("tweenRotate"))
+            .and_then(|c| c.as_i64())
+            .unwrap_or(0);
+    }
+    unwrapped
+}
+
+/// Curves that could only be approximated while importing.
+#[derive(Default)]
+struct Curves {
+    simplified: bool,
+    partial_easing: bool,
+}
+
+/// Add the frames of one element of a timeline to an animation.
+///
+/// `values` are in DragonBones' units, which `unit` converts from.
+fn add_keys(
+    anim: &mut Animation,
+    curves: &mut Curves,
+    bone_id: i32,
+    element: AnimElement,
+    frames: &[Value],
+    values: &[f32],
+    unit: f32,
+) {
+    let mut frame = 0;
+    let mut transition = Transition::Linear;
+    let mut handles = BezierHandles::default();
+    for (db_frame, value) in frames.iter().zip(values) {
+        anim.keyframes.push(Keyframe {
+            frame,
+            bone_id,
+            element: element.clone(),
+            element_id: element.clone() as i32,
+            value: value * unit,
+            transition: transition.clone(),
+            handles,
+            ..Default::default()
+        });
+
+        // a frame's tween is how it goes to the next, which is where keyframes keep it
+        (transition, handles) = tween_of(db_frame, curves);
+        frame += db_frame["duration"].as_i64().unwrap_or(1) as i32;
+    }
+}
+
+/// Get the transition for the tween of a frame.
+fn tween_of(frame: &Value, curves: &mut Curves) -> (Transition, BezierHandles) {
+    if let Some(curve) = frame["curve"].as_array() {
+        let points: Vec<f32> = curve
+            .iter()
+            .map(|p| p.as_f64().unwrap_or(0.) as f32)
+            .collect();
+        if points.len() >= 4 {
+            // longer curves go through points between their ends
+            curves.simplified |= points.len() > 4;
+            let handles = BezierHandles {
+                start: Vec2::new(points[0], points[1]),
+                end: Vec2::new(points[points.len() - 2], points[points.len() - 1]),
+            };
+            return (Transition::Bezier, handles);
+        }
+    }
+
+    // DragonBones eases with a quad (or sine, for both ways), blended with linear
+    let Some(easing) = frame["tweenEasing"].as_f64().map(|e| e as f32) else {
+        return (Transition::Stepped, BezierHandles::default());
+    };
+    if easing == 0. {
+        return (Transition::Linear, BezierHandles::default());
+    }
+    let (full, strength) = match easing {
+        e if e < 0. => (Transition::QuadIn, -e),
+        e if e <= 1. => (Transition::QuadOut, e),
+        e => (Transition::SineInOut, e - 1.),
+    };
+    if strength >= 1. {
+        return (full, BezierHandles::default());
+    }
+
+    curves.partial_easing = true;
+    let linear = Transition::Linear.approximate_bezier().unwrap();
+    let eased = full.approximate_bezier().unwrap();
+    let lerp = |a: Vec2, b: Vec2| a + (b - a) * strength;
+    let handles = BezierHandles {
+        start: lerp(linear.start, eased.start),
+        end: lerp(linear.end, eased.end),
+    };
+    (Transition::Bezier, handles)
+}
+
+/// Write the atlas for textures that have been packed into the same page
+/// (see `atlas::build`).
+///
+/// Every texture is a sub-texture, and so is every region of one
+/// (see `spine::region_name`).
+pub fn write_tex(
+    name: &str,
+    textures: &[&Texture],
+    image_path: &str,
+    (width, height): (u32, u32),
+) -> Value {
+    let mut sub_textures = vec![];
+    for tex in textures {
+        let mut entries = vec![(tex.name.clone(), tex.offset, tex.size)];
+        for (i, region) in tex.regions.iter().enumerate() {
+            let name = crate::spine::region_name(tex, i as i32);
+            entries.push((name, tex.offset + region.offset, region.size));
+        }
+        for (name, offset, size) in entries {
+            sub_textures.push(json!({
+                "name": name,
+                "x": offset.x as u32,
+                "y": offset.y as u32,
+                "width": size.x as u32,
+                "height": size.y as u32,
+            }));
+        }
+    }
+    json!({
+        "name": name,
+        "imagePath": image_path,
+        "width": width,
+        "height": height,
+        "SubTexture": sub_textures,
+    })
+}
+
+/// Convert armatures to DragonBones JSON, returning it with warnings about anything
+/// that was left out.
+///
+/// Displays use the sub-texture names of `spine::region_name`, which should be
+/// unique across every armature.
+pub fn export(armatures: &[Armature], name: &str) -> (Value, Vec<String>) {
+    let mut warnings: Vec<String> = vec![];
+    let fps = armatures
+        .iter()
+        .find_map(|a| a.animations.first())
+        .map_or(DEFAULT_FPS, |anim| anim.fps.max(1));
+
+    let mut names: Vec<String> = vec![];
+    let mut db_armatures = vec![];
+    for armature in armatures {
+        let armature_name = unique_name(&mut names, &armature.name, "armature");
+        let mut armature_warnings = vec![];
+        db_armatures.push(export_armature(
+            armature,
+            &armature_name,
+            fps,
+            &mut armature_warnings,
+        ));
+        warnings.extend(
+            armature_warnings
+                .into_iter()
+                .map(|warning| format!("{}: {}", armature_name, warning)),
+        );
+    }
+
+    let ske = json!({
+        "frameRate": fps,
+        "name": name,
+        "version": DB_VERSION,
+        "compatibleVersion": DB_VERSION,
+        "armature": db_armatures,
+    });
+    (ske, warnings)
+}
+
+fn export_armature(armature: &Armature, name: &str, fps: i32, warnings: &mut Vec<String>) -> Value {
+    // DragonBones needs unique names, and parents to come before their children
+    let mut names: Vec<String> = vec![];
+    let root_name = unique_name(&mut names, "root", "bone");
+    let mut bone_names: Vec<(i32, String)> = vec![];
+    let mut db_bones = vec![json!({
+        "name": root_name,
+        "transform": transform_of(armature.pos, 0., Vec2::new(1., 1.)),
+    })];
+    let mut added = vec![false; armature.bones.len()];
+    loop {
+        let mut progress = false;
+        for (i, bone) in armature.bones.iter().enumerate() {
+            let parent = bone_names.iter().find(|(id, _)| *id == bone.parent_id);
+            let has_parent = armature.bones.iter().any(|b| b.id == bone.parent_id);
+            if added[i] || (has_parent && parent.is_none()) {
+                continue;
+            }
+            let parent = parent.map_or(root_name.clone(), |(_, name)| name.clone());
+            let name = unique_name(&mut names, &bone.name, "bone");
+            db_bones.push(json!({
+                "name": name,
+                "parent": parent,
+                "transform": transform_of(bone.pos, bone.rot, bone.scale),
+            }));
+            bone_names.push((bone.id, name));
+            added[i] = true;
+            progress = true;
+        }
+        if !progress {
+            break;
+        }
+    }
+    for (i, bone) in armature.bones.iter().enumerate() {
+        if !added[i] {
+            warnings.push(format!(
+                "Bone '{}' has cyclic parents, so it was left out.",
+                bone.name
+            ));
+        }
+    }
+    let name_of = |id: i32| bone_names.iter().find(|(i, _)| *i == id).map(|(_, n)| n);
+
+    // slots, in draw order
+    let mut slot_names: Vec<String> = vec![];
+    let mut slots = vec![];
+    let mut skin_slots = vec![];
+    // armature slot index of each DragonBones slot
+    let mut db_slot_idx: Vec<usize> = vec![];
+    for (i, slot) in armature.slots.iter().enumerate() {
+        let bone = armature.bones.iter().find(|b| b.id == slot.bone_id);
+        let (Some(bone), Some(bone_name)) = (bone, name_of(slot.bone_id)) else {
+            warnings.push(format!(
+                "Slot '{}' is on a bone that was left out, so it was too.",
+                slot.name
+            ));
+            continue;
+        };
+        let name = unique_name(&mut slot_names, &slot.name, "slot");
+
+        let mut db_slot = json!({ "name": name, "parent": bone_name });
+        if slot.color != Color::WHITE {
+            let percent = |c: f32| (c * 100.).round();
+            db_slot["color"] = json!({
+                "rM": percent(slot.color.r),
+                "gM": percent(slot.color.g),
+                "bM": percent(slot.color.b),
+                "aM": percent(slot.color.a),
+            });
+        }
+        let blend = match slot.blend {
+            BlendMode::Normal => None,
+            BlendMode::Additive => Some("add"),
+            BlendMode::Multiply => Some("multiply"),
+            BlendMode::Screen => Some("screen"),
+        };
+        if let Some(blend) = blend {
+            db_slot["blendMode"] = json!(blend);
+        }
+
+        let mut display = vec![];
+        if let Some(tex) = armature.textures.get(slot.tex_idx as usize) {
+            let warning = format!(
+                "Bone '{}': meshes aren't exported, so its whole texture is shown instead.",
+                bone.name
+            );
+            if bone.is_mesh && !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+            display.push(json!({
+                "name": crate::spine::region_name(tex, slot.region_idx),
+                "type": "image",
+                "pivot": { "x": bone.pivot.x, "y": bone.pivot.y },
+            }));
+        } else {
+            db_slot["displayIndex"] = json!(-1);
+        }
+
+        slots.push(db_slot);
+        skin_slots.push(json!({ "name": name, "display": display }));
+        db_slot_idx.push(i);
+    }
+
+    if !armature.ik_constraints.is_empty() {
+        warnings.push(format!(
+            "{} IK constraints were left out.",
+            armature.ik_constraints.len()
+        ));
+    }
+
+    // animations
+    let mut approximated: Vec<Transition> = vec![];
+    let mut db_anims = vec![];
+    for anim in &armature.animations {
+        // every animation has the armature's fps, so others are retimed to it
+        let retime = fps as f32 / anim.fps.max(1) as f32;
+        if anim.fps != fps {
+            warnings.push(format!(
+                "Animation '{}': DragonBones has the same fps for every animation, \
+                 so it was retimed from {} to {} fps.",
+                anim.name, anim.fps, fps
+            ));
+        }
+        let mut anim = anim.clone();
+        for kf in &mut anim.keyframes {
+            kf.frame = (kf.frame as f32 * retime).round() as i32;
+        }
+
+        let mut timelines = vec![];
+        for bone in &armature.bones {
+            let Some(name) = name_of(bone.id) else {
+                continue;
+            };
+            let mut timeline = Map::new();
+            timeline.insert("name".to_string(), json!(name));
+            for (kind, x, y) in [
+                (
+                    "translateFrame",
+                    AnimElement::PositionX,
+                    Some(AnimElement::PositionY),
+                ),
+                ("rotateFrame", AnimElement::Rotation, None),
+                ("scaleFrame", AnimElement::ScaleX, Some(AnimElement::ScaleY)),
+            ] {
+                let keys = paired_keys(&anim, bone, x.clone(), y, warnings);
+                if keys.is_empty() {
+                    continue;
+                }
+
+                let mut frames = vec![];
+                let mut prev: Option<(i32, f32)> = None;
+                for (i, (frame, values)) in keys.iter().enumerate() {
+                    let mut db_frame = Map::new();
+                    // the first frame is always at the start
+                    if i == 0 && *frame > 0 {
+                        frames.push(json!({ "duration": frame, "tweenEasing": 0 }));
+                        let last = frames.last_mut().unwrap();
+                        set_values(last, kind, values);
+                    }
+                    let next = keys.get(i + 1);
+                    let duration = next.map_or(0, |(next_frame, _)| next_frame - frame);
+                    db_frame.insert("duration".to_string(), json!(duration));
+                    if let Some(next) = next.map(|(f, _)| *f) {
+                        let kf = keyframe_at(&anim, bone.id, x.clone(), next);
+                        set_tween(&mut db_frame, kf, &mut approximated, &anim.name, warnings);
+                    }
+                    let mut db_frame = Value::Object(db_frame);
+                    set_values(&mut db_frame, kind, values);
+
+                    // rotations would otherwise take the shortest way around
+                    if kind == "rotateFrame" {
+                        if let Some((prev_idx, prev_value)) = prev {
+                            let diff = values.0 - prev_value;
+                            if diff.abs() >= 180. {
+                                frames[prev_idx as usize]["clockwise"] =
+                                    json!(diff.signum() as i32);
+                            }
+                        }
+                        prev = Some((frames.len() as i32, values.0));
+                    }
+                    frames.push(db_frame);
+                }
+                timeline.insert(kind.to_string(), Value::Array(frames));
+            }
+            if timeline.len() > 1 {
+                timelines.push(Value::Object(timeline));
+            }
+        }
+
+        // draw orders, as (slot, offset) pairs like in Spine
+        let draw_orders: Vec<&Keyframe> = anim
+            .keyframes
+            .iter()
+            .filter(|kf| kf.element == AnimElement::DrawOrder)
+            .collect();
+        let mut z_frames = vec![];
+        for (i, kf) in draw_orders.iter().enumerate() {
+            if i == 0 && kf.frame > 0 {
+                z_frames.push(json!({ "duration": kf.frame }));
+            }
+            let order: Vec<usize> = kf
+                .draw_order
+                .iter()
+                .filter_map(|idx| db_slot_idx.iter().position(|s| s == idx))
+                .collect();
+            let pairs: Vec<i64> = crate::spine::offsets_of(&order)
+                .into_iter()
+                .flat_map(|(slot, offset)| [slot as i64, offset])
+                .collect();
+            let next = draw_orders.get(i + 1);
+            let duration = next.map_or(0, |next| next.frame - kf.frame);
+            z_frames.push(json!({ "duration": duration, "zOrder": pairs }));
+        }
+
+        let mut left_out: Vec<&str> = vec![];
+        for kf in &anim.keyframes {
+            let what = match kf.element {
+                AnimElement::PivotX | AnimElement::PivotY => "pivot",
+                AnimElement::IkMix => "IK",
+                AnimElement::Deform => "deform",
+                AnimElement::Attachment => "attachment",
+                _ => continue,
+            };
+            if !left_out.contains(&what) {
+                left_out.push(what);
+            }
+        }
+        if !left_out.is_empty() {
+            warnings.push(format!(
+                "Animation '{}': {} keyframes were left out.",
+                anim.name,
+                left_out.join(", ")
+            ));
+        }
+
+        let duration = anim.keyframes.iter().map(|kf| kf.frame).max().unwrap_or(0);
+        let mut db_anim = json!({
+            "name": anim.name,
+            "duration": duration,
+            "playTimes": 0,
+            "bone": timelines,
+        });
+        if !z_frames.is_empty() {
+            db_anim["zOrder"] = json!({ "frame": z_frames });
+        }
+        db_anims.push(db_anim);
+    }
+    for transition in approximated {
+        warnings.push(format!(
+            "{} transitions were approximated with bezier curves.",
+            transition
+        ));
+    }
+
+    let mut db_armature = json!({
+        "type": "Armature",
+        "name": name,
+        "frameRate": fps,
+        "bone": db_bones,
+        "slot": slots,
+        "skin": [{ "name": "", "slot": skin_slots }],
+        "animation": db_anims,
+    });
+    if let Some(anim) = armature.animations.first() {
+        db_armature["defaultActions"] = json!([{ "gotoAndPlay": anim.name }]);
+    }
+    db_armature
+}
+
+/// Frames and values (in DragonBones' units) of a bone's timeline, with the values of
+/// its `y` element too if it has one.
+///
+/// Elements keyed on different frames are sampled at every frame between them, as
+/// DragonBones keys both at once.
+fn paired_keys(
+    anim: &Animation,
+    bone: &Bone,
+    x: AnimElement,
+    y: Option<AnimElement>,
+    warnings: &mut Vec<String>,
+) -> Vec<(i32, (f32, f32))> {
+    let frames_of = |element: &AnimElement| -> Vec<i32> {
+        anim.keyframes
+            .iter()
+            .filter(|kf| kf.bone_id == bone.id && kf.element == *element)
+            .map(|kf| kf.frame)
+            .collect()
+    };
+    let x_frames = frames_of(&x);
+    let y_frames = y.as_ref().map_or(vec![], frames_of);
+    if x_frames.is_empty() && y_frames.is_empty() {
+        return vec![];
+    }
+
+    let unit = match x {
+        AnimElement::Rotation => -1_f32.to_radians(),
+        AnimElement::PositionX => HARD_SCALE,
+        _ => 1.,
+    };
+    let y_unit = match y {
+        Some(AnimElement::PositionY) => -HARD_SCALE,
+        _ => 1.,
+    };
+    let value_at = |frame: i32| {
+        let value_x = sample(
+            anim,
+            bone.id,
+            x.clone(),
+            AnimElement::default_of(&x),
+            frame as f32,
+        );
+        let value_y = y.as_ref().map_or(0., |y| {
+            sample(
+                anim,
+                bone.id,
+                y.clone(),
+                AnimElement::default_of(y),
+                frame as f32,
+            )
+        });
+        (frame, (value_x / unit, value_y / y_unit))
+    };
+
+    if y.is_none() || x_frames == y_frames {
+        return x_frames.into_iter().map(value_at).collect();
+    }
+
+    warnings.push(format!(
+        "Animation '{}': {} and {} of bone '{}' are keyed on different frames, \
+         so they were sampled at every frame.",
+        anim.name,
+        x,
+        y.as_ref().unwrap(),
+        bone.name
+    ));
+    let first = *x_frames.iter().chain(&y_frames).min().unwrap();
+    let last = *x_frames.iter().chain(&y_frames).max().unwrap();
+    (first..=last).map(value_at).collect()
+}
+
+/// The keyframe of a bone's element at a frame, if there's one.
+fn keyframe_at(
+    anim: &Animation,
+    bone_id: i32,
+    element: AnimElement,
+    frame: i32,
+) -> Option<&Keyframe> {
+    anim.keyframes
+        .iter()
+        .find(|kf| kf.bone_id == bone_id && kf.element == element && kf.frame == frame)
+}
+
+fn set_values(frame: &mut Value, kind: &str, (x, y): &(f32, f32)) {
+    match kind {
+        "rotateFrame" => frame["rotate"] = json!(x),
+        _ => {
+            frame["x"] = json!(x);
+            frame["y"] = json!(y);
+        }
+    }
+}
+
+/// Set how a frame tweens to the next, from the transition of the next keyframe.
+///
+/// Keyframes that were sampled in between (see `paired_keys`) tween linearly.
+fn set_tween(
+    frame: &mut Map<String, Value>,
+    next: Option<&Keyframe>,
+    approximated: &mut Vec<Transition>,
+    anim_name: &str,
+    warnings: &mut Vec<String>,
+) {
+    let Some(next) = next else {
+        frame.insert("tweenEasing".to_string(), json!(0));
+        return;
+    };
+    let (easing, handles) = match next.transition {
+        Transition::Linear => (Some(0.), None),
+        Transition::Stepped => (None, None),
+        Transition::QuadIn => (Some(-1.), None),
+        Transition::QuadOut => (Some(1.), None),
+        Transition::SineInOut => (Some(2.), None),
+        Transition::Bezier => (None, Some(next.handles)),
+        _ => match next.transition.approximate_bezier() {
+            Some(handles) => {
+                if !approximated.contains(&next.transition) {
+                    approximated.push(next.transition.clone());
+                }
+                (None, Some(handles))
+            }
+            None => {
+                let warning = format!(
+                    "Animation '{}': {} transitions aren't supported, so they were made linear.",
+                    anim_name, next.transition
+                );
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                (Some(0.), None)
+            }
+        },
+    };
+    if let Some(easing) = easing {
+        frame.insert("tweenEasing".to_string(), json!(easing));
+    }
+    if let Some(h) = handles {
+        frame.insert(
+            "curve".to_string(),
+            json!([h.start.x, h.start.y, h.end.x, h.end.y]),
+        );
+    }
+}
+
+fn transform_of(pos: Vec2, rot: f32, scale: Vec2) -> Value {
+    let rot = -rot.to_degrees();
+    json!({
+        "x": pos.x / HARD_SCALE,
+        "y": -pos.y / HARD_SCALE,
+        "skX": rot,
+        "skY": rot,
+        "scX": scale.x,
+        "scY": scale.y,
+    })
+}
+
+/// Read a DragonBones skeleton (`name_ske.json`), with its images.
+///
+/// Images are taken from the atlases next to it: `name_tex.json`, and
+/// `name_tex_2.json`, `name_tex_3.json`... if there are more.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_files(path: &str) -> Result<(Root, Vec<String>), crate::error::FileError> {
+    use crate::error::FileError;
+    use std::path::Path;
+
+    let ske = std::fs::read_to_string(path)?;
+    let (dir, name) = split_path(path);
+
+    let mut textures: Vec<Texture> = vec![];
+    let mut warnings: Vec<String> = vec![];
+    for p in 0.. {
+        let tex_path = tex_path(&dir, &name, p);
+        if !tex_path.exists() {
+            if p == 0 {
+                warnings.push(format!(
+                    "{} wasn't found, so there are no images.",
+                    tex_path.display()
+                ));
+            }
+            break;
+        }
+        let (image_path, sub_textures) =
+            parse_tex(&std::fs::read_to_string(&tex_path)?).map_err(FileError::Schema)?;
+        let image_path = match image_path.is_empty() {
+            true => tex_path.with_extension("png"),
+            false => dir.join(image_path),
+        };
+        let img = image::open(Path::new(&image_path))?.into_rgba8();
+        for sub in &sub_textures {
+            match extract_sub_texture(&img, sub) {
+                Some(tex) => textures.push(tex),
+                None => warnings.push(format!("Texture '{}' lies outside of its atlas.", sub.name)),
+            }
+        }
+    }
+
+    let (armatures, import_warnings) = import(&ske, &textures).map_err(FileError::Schema)?;
+    warnings.extend(import_warnings);
+    let root = Root {
+        version: crate::migrate::FORMAT_VERSION,
+        armatures,
+        ..Default::default()
+    };
+    Ok((root, warnings))
+}
+
+/// Write armatures as DragonBones JSON to `path` (`name_ske.json`), with their
+/// textures packed into atlases next to it (`name_tex.json` and `name_tex.png`, then
+/// `name_tex_2.json`... for more pages).
+///
+/// Returns warnings about anything that was left out.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_files(
+    path: &str,
+    armatures: &[Armature],
+    atlas_config: &crate::atlas::AtlasConfig,
+) -> Result<Vec<String>, crate::error::FileError> {
+    let (dir, name) = split_path(path);
+
+    // sub-textures are shared by every armature, so their names have to be unique
+    let mut armatures = armatures.to_vec();
+    let mut tex_names: Vec<String> = vec![];
+    for armature in &mut armatures {
+        for tex in &mut armature.textures {
+            tex.name = unique_name(&mut tex_names, &tex.name, "texture");
+        }
+    }
+
+    let counts: Vec<usize> = armatures.iter().map(|a| a.textures.len()).collect();
+    let mut textures: Vec<Texture> = armatures
+        .iter_mut()
+        .flat_map(|armature| std::mem::take(&mut armature.textures))
+        .collect();
+    let pages = crate::atlas::build(&mut textures, atlas_config);
+
+    for (p, page) in pages.iter().enumerate() {
+        let tex_path = tex_path(&dir, &name, p);
+        let image_path = tex_path.with_extension("png");
+        let image_name = image_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let page_textures: Vec<&Texture> = textures.iter().filter(|t| t.page == p).collect();
+        let tex = write_tex(
+            &name,
+            &page_textures,
+            &image_name,
+            (page.width(), page.height()),
+        );
+        std::fs::write(&tex_path, serde_json::to_string_pretty(&tex)?)?;
+        page.save_with_format(image_path, image::ImageFormat::Png)?;
+    }
+
+    let mut textures = textures.into_iter();
+    for (armature, count) in armatures.iter_mut().zip(counts) {
+        armature.textures = textures.by_ref().take(count).collect();
+    }
+
+    let (ske, warnings) = export(&armatures, &name);
+    std::fs::write(path, serde_json::to_string_pretty(&ske)?)?;
+    Ok(warnings)
+}
+
+/// Folder of a DragonBones file, and the name its files share.
+#[cfg(not(target_arch = "wasm32"))]
+fn split_path(path: &str) -> (std::path::PathBuf, String) {
+    let path = std::path::Path::new(path);
+    let dir = path
+        .parent()
+        .unwrap_or(std::path::Path::new(""))
+        .to_path_buf();
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = file
+        .strip_suffix("_ske.json")
+        .or(file.strip_suffix(".json"))
+        .unwrap_or(&file)
+        .to_string();
+    (dir, name)
+}
+
+/// Path of the atlas of a page of a DragonBones file.
+#[cfg(not(target_arch = "wasm32"))]
+fn tex_path(dir: &std::path::Path, name: &str, page: usize) -> std::path::PathBuf {
+    match page {
+        0 => dir.join(format!("{}_tex.json", name)),
+        _ => dir.join(format!("{}_tex_{}.json", name, page + 1)),
+    }
+}
+
+fn unique_name(names: &mut Vec<String>, name: &str, fallback: &str) -> String {
+    let name = if name.is_empty() { fallback } else { name };
+    let mut unique = name.to_string();
+    let mut n = 1;
+    while names.contains(&unique) {
+        n += 1;
+        unique = format!("{}_{}", name, n);
+    }
+    names.push(unique.clone());
+    unique
+}
+
+fn fps_of(value: &Value) -> Option<i32> {
+    value["frameRate"]
+        .as_f64()
+        .map(|fps| fps.round() as i32)
+        .filter(|fps| *fps > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn rotations_turn_the_way_theyre_told() {
+        let unwrap =
+            |frames: Value, values: &[f32]| unwrap_rotations(frames.as_array().unwrap(), values);
+        // the shortest way around
+        assert_eq!(unwrap(json!([{}, {}]), &[170., -170.]), [170., 190.]);
+        // clockwise, for as many turns as it takes
+        assert_eq!(
+            unwrap(json!([{ "clockwise": 1 }, {}]), &[0., 90.]),
+            [0., 90.]
+        );
+        assert_eq!(
+            unwrap(json!([{ "clockwise": 2 }, {}]), &[0., 90.]),
+            [0., 450.]
+        );
+        assert_eq!(
+            unwrap(json!([{ "clockwise": 1 }, {}]), &[0., -90.]),
+            [0., 270.]
+        );
+        // and the other way
+        assert_eq!(
+            unwrap(json!([{ "clockwise": -1 }, {}]), &[0., 90.]),
+            [0., -270.]
+        );
+        // as 5.0 and earlier spelled it, continuing from where the turn ended
+        let frames = json!([{ "tweenRotate": 1 }, {}, {}]);
+        assert_eq!(unwrap(frames, &[0., -90., -100.]), [0., 270., 260.]);
+    }
+
+    #[test]
+    fn eases_blend_with_linear() {
+        let tween = |frame: Value| {
+            let mut curves = Curves::default();
+            let (transition, handles) = tween_of(&frame, &mut curves);
+            (
+                transition,
+                handles,
+                curves.partial_easing,
+                curves.simplified,
+            )
+        };
+        assert_eq!(tween(json!({})).0, Transition::Stepped);
+        assert_eq!(tween(json!({ "tweenEasing": 0 })).0, Transition::Linear);
+        assert_eq!(tween(json!({ "tweenEasing": -1 })).0, Transition::QuadIn);
+        assert_eq!(tween(json!({ "tweenEasing": 1 })).0, Transition::QuadOut);
+        assert_eq!(tween(json!({ "tweenEasing": 2 })).0, Transition::SineInOut);
+
+        // halfway between linear and a full quad ease
+        let (transition, handles, partial, _) = tween(json!({ "tweenEasing": 0.5 }));
+        assert_eq!((transition, partial), (Transition::Bezier, true));
+        assert_near(handles.start, Vec2::new(0.25, 0.5));
+        assert_near(handles.end, Vec2::new(0.945, 1.));
+        let (_, handles, _, _) = tween(json!({ "tweenEasing": -0.25 }));
+        assert_near(handles.start, Vec2::new(0.0275, 0.));
+        assert_near(handles.end, Vec2::new(0.875, 0.75));
+
+        let (transition, handles, partial, simplified) =
+            tween(json!({ "curve": [0.1, 0.2, 0.5, 0.5, 0.8, 0.9] }));
+        assert_eq!(
+            (transition, partial, simplified),
+            (Transition::Bezier, false, true)
+        );
+        assert_near(handles.start, Vec2::new(0.1, 0.2));
+        assert_near(handles.end, Vec2::new(0.8, 0.9));
+    }
+
+    #[test]
+    fn transforms_are_flipped_to_y_up() {
+        let mut bone = Bone::default();
+        let mut warnings = vec![];
+        let transform = json!({ "x": 100, "y": 50, "skX": 30, "skY": 30, "scX": 2, "scY": -1 });
+        set_transform(&mut bone, &transform, "arm", &mut warnings);
+        assert_near(bone.pos, Vec2::new(0.5, -0.25));
+        assert!((bone.rot - -30_f32.to_radians()).abs() < 0.0001);
+        assert_near(bone.scale, Vec2::new(2., -1.));
+        assert!(warnings.is_empty());
+
+        // and back
+        let exported = transform_of(bone.pos, bone.rot, bone.scale);
+        for field in ["x", "y", "skX", "skY", "scX", "scY"] {
+            assert!((num(&exported, field, 0.) - num(&transform, field, 0.)).abs() < 0.001);
+        }
+
+        set_transform(&mut bone, &json!({ "skX": 10 }), "arm", &mut warnings);
+        assert_eq!(warnings, ["'arm': skewing isn't supported."]);
+    }
+
+    #[test]
+    fn files_are_imported() {
+        let dir = std::env::temp_dir().join(format!("skelform_db_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // "leg" is packed turned clockwise
+        let leg = image::RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8, y as u8, 9, 255]));
+        let mut page =
+            image::RgbaImage::from_fn(8, 8, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        image::imageops::replace(&mut page, &image::imageops::rotate90(&leg), 4, 4);
+        page.save(dir.join("hero_tex.png")).unwrap();
+        let tex = json!({
+            "imagePath": "hero_tex.png",
+            "SubTexture": [
+                { "name": "arm", "x": 2, "y": 1, "width": 3, "height": 2 },
+                { "name": "leg", "x": 4, "y": 4, "width": 3, "height": 2, "rotated": true },
+            ],
+        });
+        std::fs::write(dir.join("hero_tex.json"), tex.to_string()).unwrap();
+        let ske = json!({
+            "frameRate": 24,
+            "armature": [{
+                "name": "Hero",
+                "bone": [
+                    { "name": "root" },
+                    { "name": "arm", "parent": "root",
+                      "transform": { "x": 100, "y": 50, "skX": 90, "skY": 90 } },
+                ],
+                "slot": [{ "name": "arm", "parent": "arm" }],
+                "skin": [{ "slot": [{ "name": "arm", "display": [
+                    { "name": "arm", "transform": { "x": 10, "y": -4 }, "pivot": { "x": 0, "y": 1 } }
+                ] }] }],
+                "ik": [{ "name": "reach", "bone": "arm", "target": "root" }],
+                "animation": [{ "name": "wave", "duration": 20, "bone": [{
+                    "name": "arm",
+                    "rotateFrame": [
+                        { "duration": 10, "tweenEasing": 0, "clockwise": 1, "rotate": 0 },
+                        { "duration": 10, "tweenEasing": 0.5, "rotate": -90 },
+                        { "duration": 0, "rotate": 0 },
+                    ],
+                    "translateFrame": [
+                        { "duration": 20, "x": 10, "y": 20 },
+                        { "duration": 0 },
+                    ],
+                }] }],
+            }],
+        });
+        let path = dir.join("hero_ske.json");
+        std::fs::write(&path, ske.to_string()).unwrap();
+        let read = read_files(path.to_str().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (root, warnings) = read.unwrap();
+
+        assert_eq!(
+            warnings,
+            [
+                "Hero: 1 IK constraints were left out.",
+                "Hero: Animation 'wave': partial eases were approximated with bezier curves.",
+            ]
+        );
+        let armature = &root.armatures[0];
+        assert_eq!(armature.name, "Hero");
+        let cropped = image::imageops::crop_imm(&page, 2, 1, 3, 2).to_image();
+        assert!(armature.textures[0].pixels[..] == cropped.as_raw()[..]);
+        assert!(armature.textures[1].pixels[..] == leg.as_raw()[..]);
+
+        // y-down and clockwise
+        let arm = &armature.bones[1];
+        assert_near(arm.pos, Vec2::new(0.5, -0.25));
+        assert!((arm.rot - -FRAC_PI_2).abs() < 0.0001);
+        let slot_bone = &armature.bones[2];
+        assert_eq!((slot_bone.parent_id, armature.slots[0].tex_idx), (1, 0));
+        assert_near(slot_bone.pos, Vec2::new(10., 4.) * HARD_SCALE);
+        assert_near(slot_bone.pivot, Vec2::new(0., 1.));
+
+        let anim = &armature.animations[0];
+        assert_eq!(anim.fps, 24);
+        let deg = -1_f32.to_radians();
+        assert_keys(
+            anim,
+            &[
+                (
+                    0,
+                    1,
+                    AnimElement::PositionX,
+                    10. * HARD_SCALE,
+                    Transition::Linear,
+                ),
+                (
+                    0,
+                    1,
+                    AnimElement::PositionY,
+                    -20. * HARD_SCALE,
+                    Transition::Linear,
+                ),
+                (0, 1, AnimElement::Rotation, 0., Transition::Linear),
+                // all the way around clockwise, then the shortest way back
+                (10, 1, AnimElement::Rotation, 270. * deg, Transition::Linear),
+                (20, 1, AnimElement::PositionX, 0., Transition::Stepped),
+                (20, 1, AnimElement::PositionY, 0., Transition::Stepped),
+                (20, 1, AnimElement::Rotation, 360. * deg, Transition::Bezier),
+            ],
+        );
+    }
+
+    #[test]
+    fn exported_armatures_import_the_same() {
+        let bone = |id, name: &str, parent_id, pos, rot, scale| Bone {
+            id,
+            name: name.to_string(),
+            parent_id,
+            pos,
+            rot,
+            scale,
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        };
+        let with = |mut kf: Keyframe, transition| {
+            kf.transition = transition;
+            kf
+        };
+        let mut curved = key(0, AnimElement::Rotation, 24, 0.5);
+        curved.transition = Transition::Bezier;
+        curved.handles = BezierHandles {
+            start: Vec2::new(0.25, 0.1),
+            end: Vec2::new(0.75, 0.9),
+        };
+        let armature = Armature {
+            name: "Hero".to_string(),
+            bones: vec![
+                bone(0, "hip", -1, Vec2::new(0.5, 0.25), 0.3, Vec2::new(1., 2.)),
+                bone(1, "knee", 0, Vec2::new(0., -0.5), -1., Vec2::new(1., 1.)),
+            ],
+            animations: vec![Animation {
+                name: "walk".to_string(),
+                fps: 24,
+                keyframes: vec![
+                    key(0, AnimElement::Rotation, 0, 0.),
+                    key(1, AnimElement::PositionX, 0, 0.1),
+                    key(1, AnimElement::PositionY, 0, 0.2),
+                    key(1, AnimElement::ScaleX, 0, 1.),
+                    key(1, AnimElement::ScaleY, 0, 2.),
+                    // more than half a turn, which has to be kept clockwise
+                    with(key(0, AnimElement::Rotation, 12, 4.), Transition::Stepped),
+                    with(key(1, AnimElement::ScaleX, 12, 3.), Transition::QuadIn),
+                    with(key(1, AnimElement::ScaleY, 12, 1.), Transition::QuadIn),
+                    curved.clone(),
+                    key(1, AnimElement::PositionX, 24, -0.1),
+                    key(1, AnimElement::PositionY, 24, 0.),
+                ],
+            }],
+            ..Default::default()
+        };
+
+        let (json, warnings) = export(std::slice::from_ref(&armature), "hero");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let (imported, warnings) = import(&json.to_string(), &[]).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let imported = &imported[0];
+
+        // under a root bone of their own
+        assert_eq!(imported.name, "Hero");
+        assert_eq!(imported.bones.len(), 3);
+        for (bone, og) in imported.bones[1..].iter().zip(&armature.bones) {
+            assert_eq!((&bone.name, bone.parent_id), (&og.name, og.parent_id + 1));
+            assert_near(bone.pos, og.pos);
+            assert!((bone.rot - og.rot).abs() < 0.0001);
+            assert_near(bone.scale, og.scale);
+        }
+
+        let mut expected = keys_of(&armature.animations[0]);
+        for key in &mut expected {
+            key.1 += 1;
+        }
+        let anim = &imported.animations[0];
+        assert_eq!((&anim.name, anim.fps), (&"walk".to_string(), 24));
+        assert_keys(anim, &expected);
+        let rotation = |kf: &&Keyframe| kf.frame == 24 && kf.element == AnimElement::Rotation;
+        let handles = anim.keyframes.iter().find(rotation).unwrap().handles;
+        assert_near(handles.start, curved.handles.start);
+        assert_near(handles.end, curved.handles.end);
+    }
+}
@@ -0,0 +1,61 @@
+//! Errors from reading and writing armature files and exports.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Json(serde_json::Error),
+    Image(image::ImageError),
+    /// The file was read fine, but its contents don't make up a valid armature.
+    Schema(String),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::Io(err) => write!(f, "Couldn't access the file: {}", err),
+            FileError::Zip(err) => write!(f, "That's not a SkelForm armature! ({})", err),
+            FileError::Json(err) => write!(f, "The armature data is corrupted: {}", err),
+            FileError::Image(err) => write!(f, "A texture couldn't be read: {}", err),
+            FileError::Schema(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileError::Io(err) => Some(err),
+            FileError::Zip(err) => Some(err),
+            FileError::Json(err) => Some(err),
+            FileError::Image(err) => Some(err),
+            FileError::Schema(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for FileError {
+    fn from(err: std::io::Error) -> Self {
+        FileError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for FileError {
+    fn from(err: zip::result::ZipError) -> Self {
+        FileError::Zip(err)
+    }
+}
+
+impl From<serde_json::Error> for FileError {
+    fn from(err: serde_json::Error) -> Self {
+        FileError::Json(err)
+    }
+}
+
+impl From<image::ImageError> for FileError {
+    fn from(err: image::ImageError) -> Self {
+        FileError::Image(err)
+    }
+}
@@ -0,0 +1,374 @@
+//! Writing rendered animation frames to image sequences, animations and videos.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{io::Write, path::Path};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::FileError;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum ExportFormat {
+    /// Numbered PNG files
+    #[default]
+    PngSequence,
+    Gif,
+    /// Animated (lossless) WebP
+    WebP,
+    /// Anything ffmpeg can make out of the output path's extension. Requires ffmpeg in $PATH.
+    Ffmpeg,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::PngSequence,
+        ExportFormat::Gif,
+        ExportFormat::WebP,
+        ExportFormat::Ffmpeg,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::PngSequence => "PNG Sequence",
+            ExportFormat::Gif => "GIF",
+            ExportFormat::WebP => "WebP",
+            ExportFormat::Ffmpeg => "Video (ffmpeg)",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::PngSequence => "png",
+            ExportFormat::Gif => "gif",
+            ExportFormat::WebP => "webp",
+            ExportFormat::Ffmpeg => "mp4",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct ExportSettings {
+    pub format: ExportFormat,
+    /// File to write to. PNG sequences are numbered after it
+    /// (`walk.png` becomes `walk_0000.png`, `walk_0001.png`, ...).
+    pub path: String,
+    pub fps: u32,
+    /// First frame of the animation to export.
+    pub start: i32,
+    /// Last frame of the animation to export (inclusive), or -1 for its last keyframe.
+    pub end: i32,
+    /// Resolution of the exported frames.
+    pub width: u32,
+    pub height: u32,
+    /// Leave the background transparent, instead of filling it in.
+    pub transparent: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            format: ExportFormat::default(),
+            path: "output.png".to_string(),
+            fps: 60,
+            start: 0,
+            end: -1,
+            width: 1280,
+            height: 720,
+            transparent: false,
+        }
+    }
+}
+
+/// A backend that frames are written to, one by one.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait Exporter {
+    fn add_frame(&mut self, frame: &image::RgbaImage) -> Result<(), FileError>;
+
+    /// Flush everything to disk. Nothing is guaranteed to be written until this is called.
+    fn finish(self: Box<Self>) -> Result<(), FileError>;
+}
+
+/// Create the backend for the settings' format.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn exporter(
+    settings: &ExportSettings,
+    width: u32,
+    height: u32,
+) -> Result<Box<dyn Exporter>, FileError> {
+    let fps = settings.fps.max(1);
+    Ok(match settings.format {
+        ExportFormat::PngSequence => Box::new(PngSequence {
+            path: settings.path.clone(),
+            frame: 0,
+        }),
+        ExportFormat::Gif => {
+            let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+                return Err(FileError::Image(image::ImageError::Limits(
+                    image::error::LimitError::from_kind(
+                        image::error::LimitErrorKind::DimensionError,
+                    ),
+                )));
+            };
+            let file = std::io::BufWriter::new(std::fs::File::create(&settings.path)?);
+            let mut encoder = gif::Encoder::new(file, width, height, &[]).map_err(gif_error)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(gif_error)?;
+            Box::new(Gif {
+                encoder,
+                fps,
+                frame: 0,
+            })
+        }
+        ExportFormat::WebP => Box::new(WebP {
+            path: settings.path.clone(),
+            fps,
+            width,
+            height,
+            frames: vec![],
+        }),
+        ExportFormat::Ffmpeg => Box::new(Ffmpeg::spawn(&settings.path, fps, width, height)?),
+    })
+}
+
+/// Write every frame with the settings' format, reporting how many are done as it goes.
+///
+/// Frames are written as they come out of `frames` (eg. `Offscreen::render_animation`),
+/// so only one is held at a time.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export(
+    settings: &ExportSettings,
+    frames: impl IntoIterator<Item = image::RgbaImage>,
+    mut progress: impl FnMut(usize),
+) -> Result<(), FileError> {
+    let mut frames = frames.into_iter();
+    let Some(first) = frames.next() else {
+        let err = "There are no frames to export.".to_string();
+        return Err(FileError::Schema(err));
+    };
+    let mut exporter = exporter(settings, first.width(), first.height())?;
+    exporter.add_frame(&first)?;
+    progress(1);
+    for (i, frame) in frames.enumerate() {
+        exporter.add_frame(&frame)?;
+        progress(i + 2);
+    }
+    exporter.finish()
+}
+
+/// How long a frame is shown for, in `units` per second (1000 for milliseconds).
+///
+/// Rounding is spread across frames, so that the total stays in sync with the fps.
+#[cfg(not(target_arch = "wasm32"))]
+fn frame_duration(frame: usize, fps: u32, units: u32) -> u32 {
+    let at = |frame: usize| (frame as f64 * units as f64 / fps as f64).round() as u32;
+    at(frame + 1) - at(frame)
+}
+
+/// `dir/walk.png` -> `dir/walk_0003.png`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn sequence_path(path: &str, frame: usize) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = format!("{}_{:04}.png", stem, frame);
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct PngSequence {
+    path: String,
+    frame: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Exporter for PngSequence {
+    fn add_frame(&mut self, frame: &image::RgbaImage) -> Result<(), FileError> {
+        frame.save_with_format(
+            sequence_path(&self.path, self.frame),
+            image::ImageFormat::Png,
+        )?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), FileError> {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Gif {
+    encoder: gif::Encoder<std::io::BufWriter<std::fs::File>>,
+    fps: u32,
+    frame: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn gif_error(err: gif::EncodingError) -> FileError {
+    match err {
+        gif::EncodingError::Io(err) => FileError::Io(err),
+        err => FileError::Image(image::ImageError::Encoding(
+            image::error::EncodingError::new(image::ImageFormat::Gif.into(), err),
+        )),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Exporter for Gif {
+    fn add_frame(&mut self, frame: &image::RgbaImage) -> Result<(), FileError> {
+        let mut pixels = frame.as_raw().clone();
+        let (width, height) = (frame.width() as u16, frame.height() as u16);
+        let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        // delays are in hundredths of a second
+        gif_frame.delay = frame_duration(self.frame, self.fps, 100) as u16;
+        // clear the previous frame instead of drawing over it
+        gif_frame.dispose = gif::DisposalMethod::Background;
+        self.encoder.write_frame(&gif_frame).map_err(gif_error)?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), FileError> {
+        // writes the trailer
+        let mut file = self.encoder.into_inner()?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// The `image` crate only encodes still WebPs, so each frame is encoded on its own
+/// and then wrapped in an animated container.
+#[cfg(not(target_arch = "wasm32"))]
+struct WebP {
+    path: String,
+    fps: u32,
+    width: u32,
+    height: u32,
+    // encoded image chunks of each frame
+    frames: Vec<Vec<u8>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Exporter for WebP {
+    fn add_frame(&mut self, frame: &image::RgbaImage) -> Result<(), FileError> {
+        let mut still = vec![];
+        image::codecs::webp::WebPEncoder::new_lossless(&mut still).encode(
+            frame.as_raw(),
+            frame.width(),
+            frame.height(),
+            image::ExtendedColorType::Rgba8,
+        )?;
+
+        // keep only the image data of the still (skipping the RIFF header and extended info)
+        let mut chunks = vec![];
+        let mut i = 12;
+        while i + 8 <= still.len() {
+            let size = u32::from_le_bytes(still[i + 4..i + 8].try_into().unwrap()) as usize;
+            let end = (i + 8 + size + size % 2).min(still.len());
+            if matches!(&still[i..i + 4], b"ALPH" | b"VP8 " | b"VP8L") {
+                chunks.extend_from_slice(&still[i..end]);
+            }
+            i = end;
+        }
+        self.frames.push(chunks);
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), FileError> {
+        fn u24(value: u32) -> [u8; 3] {
+            let bytes = value.to_le_bytes();
+            [bytes[0], bytes[1], bytes[2]]
+        }
+
+        let mut body: Vec<u8> = b"WEBP".to_vec();
+
+        // canvas, flagged as animated with alpha
+        body.extend(b"VP8X");
+        body.extend(10u32.to_le_bytes());
+        body.extend([0x10 | 0x02, 0, 0, 0]);
+        body.extend(u24(self.width - 1));
+        body.extend(u24(self.height - 1));
+
+        // transparent background, looping forever
+        body.extend(b"ANIM");
+        body.extend(6u32.to_le_bytes());
+        body.extend([0, 0, 0, 0, 0, 0]);
+
+        for (i, chunks) in self.frames.iter().enumerate() {
+            body.extend(b"ANMF");
+            body.extend((16 + chunks.len() as u32).to_le_bytes());
+            body.extend(u24(0));
+            body.extend(u24(0));
+            body.extend(u24(self.width - 1));
+            body.extend(u24(self.height - 1));
+            body.extend(u24(frame_duration(i, self.fps, 1000)));
+            // replace the previous frame instead of blending over it
+            body.push(0x02);
+            body.extend(chunks);
+        }
+
+        let mut file = std::fs::File::create(&self.path)?;
+        file.write_all(b"RIFF")?;
+        file.write_all(&(body.len() as u32).to_le_bytes())?;
+        file.write_all(&body)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Ffmpeg {
+    child: std::process::Child,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Ffmpeg {
+    fn spawn(path: &str, fps: u32, width: u32, height: u32) -> Result<Ffmpeg, FileError> {
+        let child = std::process::Command::new("ffmpeg")
+            .args([
+                "-f",
+                "rawvideo",
+                "-video_size",
+                &format!("{}x{}", width, height),
+                "-framerate",
+                &fps.to_string(),
+                "-pixel_format",
+                "rgba",
+                "-i",
+                "-",
+                // fast preset
+                "-preset",
+                "veryfast",
+                "-y",
+                path,
+                "-loglevel",
+                "error",
+            ])
+            .stdin(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::inherit())
+            .spawn()
+            .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to run ffmpeg: {}", e)))?;
+        Ok(Ffmpeg { child })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Exporter for Ffmpeg {
+    fn add_frame(&mut self, frame: &image::RgbaImage) -> Result<(), FileError> {
+        self.child
+            .stdin
+            .as_mut()
+            .unwrap()
+            .write_all(frame.as_raw())?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), FileError> {
+        // closing stdin lets ffmpeg know there are no more frames
+        drop(self.child.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            let err = std::io::Error::other(format!("ffmpeg exited with {}", status));
+            return Err(FileError::Io(err));
+        }
+        Ok(())
+    }
+}
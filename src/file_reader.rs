@@ -40,6 +40,7 @@ pub const FILES: [&str; 4] = [
 ];
 
 pub const EXPORT_VID_DONE: &str = "Done!";
+pub const EXPORT_VID_FAILED: &str = "Export failed: ";
 
 pub fn read(shared: &mut Shared, renderer: &Option<Renderer>, context: &egui::Context) {
     if let Some(_) = renderer.as_ref() {
@@ -130,7 +131,7 @@ pub fn read_image_loaders(
 
     // check if this texture already exists
     for tex in &shared.armature.textures {
-        if pixels == tex.pixels {
+        if pixels[..] == tex.pixels[..] {
             return;
         }
     }
@@ -154,15 +155,21 @@ pub fn read_image_loaders(
     shared.armature.textures.push(crate::Texture {
         offset: Vec2::ZERO,
         size: dimensions,
-        pixels,
+        pixels: pixels.into(),
         name: name.clone(),
+        ..Default::default()
     });
 
+    shared.armature_dirty = true;
+
     // assign this texture to the selected bone
-    shared.selected_bone_mut().unwrap().tex_idx =
-        shared.armature.textures.len() as i32 - 1;
+    let tex_idx = shared.armature.textures.len() as i32 - 1;
+    shared.set_attachment(tex_idx, -1);
 
-    // assign texture mame to bone if it's using new bone name
+    // assign texture mame to bone (and its slot) if it's using new bone name
+    if shared.selected_slot().unwrap().name == NEW_BONE_NAME {
+        shared.selected_slot_mut().unwrap().name = name.clone();
+    }
     if shared.selected_bone_mut().unwrap().name == NEW_BONE_NAME {
         shared.selected_bone_mut().unwrap().name = name;
     }
@@ -176,7 +183,10 @@ pub fn read_save(shared: &mut Shared) {
 
     let path = fs::read_to_string(TEMP_SAVE_PATH).unwrap();
 
-    shared.save_path = path.clone();
+    // exports shouldn't be where the project itself keeps being saved to
+    if utils::format_of(&path) == utils::Format::Skf {
+        shared.save_path = path.clone();
+    }
 
     utils::save(path, shared);
 
@@ -196,9 +206,6 @@ pub fn read_import(
     }
 
     let path = fs::read_to_string(TEMP_IMPORT_PATH).unwrap();
-
-    shared.save_path = path.clone();
-
     utils::import(path, shared, queue, device, bind_group_layout, context);
 
     del_temp_files();
@@ -211,7 +218,8 @@ pub fn read_exported_video_frame(shared: &mut Shared) {
     }
     let frame = fs::read_to_string(TEMP_EXPORT_VID_TEXT).unwrap();
     shared.ui.modal_headline = frame;
-    shared.ui.forced_modal = shared.ui.modal_headline != EXPORT_VID_DONE;
+    shared.ui.forced_modal = shared.ui.modal_headline != EXPORT_VID_DONE
+        && !shared.ui.modal_headline.starts_with(EXPORT_VID_FAILED);
     fs::remove_file(TEMP_EXPORT_VID_TEXT).unwrap();
 }
 
@@ -0,0 +1,104 @@
+//! Handles drawn over the selected bones, to move, rotate and scale them by dragging.
+//!
+//! The gizmo sits on the selection's pivot (see `Shared::pivot`), with its axes along
+//! the active bone's as the renderer places it. It's drawn in `renderer`.
+
+use crate::*;
+
+/// Increments that dragging snaps to, while holding ctrl (cmd on Mac).
+pub const SNAP_MOVE: f32 = 0.1;
+pub const SNAP_ROTATE: f32 = std::f32::consts::PI / 12.;
+pub const SNAP_SCALE: f32 = 0.1;
+
+// length of the arms and radius of the ring, relative to the camera's zoom
+const SIZE: f32 = 0.2;
+// how close the mouse has to be to grab a handle, relative to the size
+const REACH: f32 = 0.1;
+
+/// One of the active bone's axes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// Part of the gizmo that can be dragged.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Handle {
+    /// Moves or scales along an axis only.
+    Axis(Axis),
+    /// Moves freely, or scales evenly.
+    Center,
+    /// Rotates.
+    Ring,
+}
+
+impl Handle {
+    pub fn axis(&self) -> Option<Axis> {
+        match self {
+            Handle::Axis(axis) => Some(*axis),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Gizmo {
+    /// World position of the pivot.
+    pub origin: Vec2,
+    /// World directions of the active bone's axes, of length 1.
+    pub x_axis: Vec2,
+    pub y_axis: Vec2,
+    /// Length of the arms and radius of the ring, in world space.
+    pub size: f32,
+}
+
+impl Gizmo {
+    /// The gizmo of the selected bones, if there are any to transform.
+    pub fn new(shared: &Shared) -> Option<Gizmo> {
+        if shared.selected_bone().is_none() || shared.edit_mode == EditMode::Mesh {
+            return None;
+        }
+        let (x_axis, y_axis) = shared.active_axes();
+        Some(Gizmo {
+            origin: shared.selection_transform(Vec2::ZERO, None).pivot,
+            x_axis,
+            y_axis,
+            size: SIZE * shared.camera.zoom,
+        })
+    }
+
+    pub fn axis(&self, axis: Axis) -> Vec2 {
+        match axis {
+            Axis::X => self.x_axis,
+            Axis::Y => self.y_axis,
+        }
+    }
+
+    /// The handle at a world position, if any. Only the ring is shown while rotating,
+    /// and the rest otherwise.
+    pub fn handle_at(&self, mode: &EditMode, point: Vec2) -> Option<Handle> {
+        let local = point - self.origin;
+        let reach = self.size * REACH;
+        if *mode == EditMode::Rotate {
+            let on_ring = (local.length() - self.size).abs() < reach;
+            return on_ring.then_some(Handle::Ring);
+        }
+
+        if local.dot(self.x_axis).abs() < reach && local.dot(self.y_axis).abs() < reach {
+            return Some(Handle::Center);
+        }
+        for (axis, across) in [(Axis::X, self.y_axis), (Axis::Y, self.x_axis)] {
+            let along = local.dot(self.axis(axis));
+            if along > reach && along < self.size + reach && local.dot(across).abs() < reach {
+                return Some(Handle::Axis(axis));
+            }
+        }
+        None
+    }
+}
+
+/// Round a value to the closest increment.
+pub fn snap(value: f32, increment: f32) -> f32 {
+    (value / increment).round() * increment
+}
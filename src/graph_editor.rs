@@ -0,0 +1,323 @@
+//! Graph (function curve) mode of the timeline, plotting each animated element over time.
+
+use egui::Stroke;
+
+use keyframe_editor::LINE_OFFSET;
+use ui::COLOR_ACCENT;
+
+use crate::*;
+
+// room above and below the curves, so keyframes at the extremes can be grabbed
+const PADDING: f32 = 15.;
+const SCROLLBAR_HEIGHT: f32 = 13.;
+
+// how many times each frame is sampled when drawing curves
+const SAMPLES: usize = 4;
+
+const GRID_LINES: usize = 4;
+
+pub fn channel_color(element: &AnimElement) -> egui::Color32 {
+    #[rustfmt::skip]
+    let (r, g, b) = match element {
+        AnimElement::PositionX  => (230, 90,  90),
+        AnimElement::PositionY  => (110, 200, 90),
+        AnimElement::Rotation   => (90,  150, 230),
+        AnimElement::ScaleX     => (230, 170, 70),
+        AnimElement::ScaleY     => (200, 100, 210),
+        AnimElement::PivotX     => (80,  200, 200),
+        AnimElement::PivotY     => (210, 210, 110),
+        AnimElement::DrawOrder  => (170, 170, 170),
+        AnimElement::IkMix      => (240, 240, 240),
+        AnimElement::Deform     => (120, 120, 120),
+        AnimElement::Attachment => (200, 150, 110),
+    };
+    egui::Color32::from_rgb(r, g, b)
+}
+
+pub fn is_visible(shared: &Shared, id: i32, element: &AnimElement) -> bool {
+    !shared.ui.anim.graph_hidden.contains(&(id, element.clone()))
+}
+
+pub fn toggle_visible(shared: &mut Shared, id: i32, element: &AnimElement) {
+    let channel = (id, element.clone());
+    let hidden = &mut shared.ui.anim.graph_hidden;
+    if let Some(idx) = hidden.iter().position(|c| *c == channel) {
+        hidden.remove(idx);
+    } else {
+        hidden.push(channel);
+    }
+}
+
+/// Every bone element with keyframes in the selected animation that isn't hidden.
+///
+/// Deforms and draw orders aren't included, since they don't have a single value to plot.
+pub fn visible_channels(shared: &Shared) -> Vec<(i32, AnimElement)> {
+    let mut channels: Vec<(i32, AnimElement)> = vec![];
+    for kf in &shared.selected_animation().unwrap().keyframes {
+        if !kf.element.has_value() {
+            continue;
+        }
+        let channel = (kf.bone_id, kf.element.clone());
+        if !channels.contains(&channel) && is_visible(shared, kf.bone_id, &kf.element) {
+            channels.push(channel);
+        }
+    }
+    channels
+}
+
+/// Zoom the graph to fit the requested curves, if framing was requested.
+///
+/// Returns the horizontal scroll offset that brings them into view.
+pub fn apply_framing(shared: &mut Shared, available_width: f32) -> Option<f32> {
+    let framing = shared.ui.anim.graph_framing.take()?;
+
+    let mut channels = visible_channels(shared);
+    if framing == GraphFraming::Selection && shared.selected_bone().is_some() {
+        let id = shared.selected_bone().unwrap().id;
+        let selected: Vec<(i32, AnimElement)> =
+            channels.iter().filter(|c| c.0 == id).cloned().collect();
+        if !selected.is_empty() {
+            channels = selected;
+        }
+    }
+
+    let anim = shared.selected_animation().unwrap();
+    let frames: Vec<i32> = anim
+        .keyframes
+        .iter()
+        .filter(|kf| channels.contains(&(kf.bone_id, kf.element.clone())))
+        .map(|kf| kf.frame)
+        .collect();
+    if frames.is_empty() {
+        return None;
+    }
+    let first = *frames.iter().min().unwrap();
+    let last = *frames.iter().max().unwrap();
+
+    // sample the curves themselves, since some transitions (eg. back and elastic) overshoot
+    let mut min = f32::MAX;
+    let mut max = f32::MIN;
+    for (id, element) in &channels {
+        for s in 0..=((last - first) as usize * SAMPLES) {
+            let frame = first as f32 + s as f32 / SAMPLES as f32;
+            let value = sample(anim, *id, element, frame);
+            min = min.min(value);
+            max = max.max(value);
+        }
+    }
+
+    // fit the frames into the view
+    let span = (last - first).max(1) as f32;
+    let view_width = (available_width - LINE_OFFSET * 2.).max(50.);
+    let fps = anim.fps as f32;
+
+    shared.ui.anim.graph_min = min;
+    shared.ui.anim.graph_max = max;
+    shared.ui.anim.timeline_zoom = span * 400. / fps / view_width;
+
+    Some(first as f32 * view_width / span)
+}
+
+pub fn draw(ui: &mut egui::Ui, shared: &mut Shared, width: f32, hitbox: f32, offset: Option<f32>) {
+    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+        egui::Frame::new()
+            .fill(COLOR_ACCENT)
+            .inner_margin(3)
+            .show(ui, |ui| {
+                let mut scroll_area = egui::ScrollArea::horizontal().id_salt("graph");
+                if let Some(offset) = offset {
+                    scroll_area = scroll_area.horizontal_scroll_offset(offset);
+                }
+                let response = scroll_area.show(ui, |ui| {
+                    ui.set_width(width);
+                    ui.set_height(ui.available_height());
+
+                    let cursor = shared.ui.get_cursor(ui);
+                    keyframe_editor::draw_lines(ui, shared, hitbox, cursor);
+
+                    let view = View::new(ui, shared, hitbox);
+                    draw_grid(ui, &view);
+                    draw_curves(ui, shared, &view);
+                    draw_keyframes(ui, shared, &view);
+                });
+                shared.ui.anim.timeline_offset.x = response.state.offset.x;
+                shared.ui.anim.bottom_bar_top = ui.min_rect().bottom() + 3.;
+            });
+    });
+}
+
+/// Maps frames and values to the screen, and back.
+struct View {
+    left: f32,
+    top: f32,
+    bottom: f32,
+    hitbox: f32,
+    min: f32,
+    max: f32,
+}
+
+impl View {
+    fn new(ui: &egui::Ui, shared: &Shared, hitbox: f32) -> View {
+        let rect = ui.min_rect();
+        let mut min = shared.ui.anim.graph_min;
+        let mut max = shared.ui.anim.graph_max;
+
+        // flat curves still need some range to be drawn in
+        if (max - min).abs() < f32::EPSILON {
+            min -= 1.;
+            max += 1.;
+        }
+
+        View {
+            left: rect.left() + LINE_OFFSET,
+            top: rect.top() + PADDING,
+            bottom: rect.bottom() - PADDING - SCROLLBAR_HEIGHT,
+            hitbox,
+            min,
+            max,
+        }
+    }
+
+    fn to_screen(&self, frame: f32, value: f32) -> egui::Pos2 {
+        let progress = (value - self.min) / (self.max - self.min);
+        egui::pos2(
+            self.left + frame * self.hitbox * 2.,
+            self.bottom - progress * (self.bottom - self.top),
+        )
+    }
+
+    fn to_frame(&self, x: f32) -> f32 {
+        (x - self.left) / (self.hitbox * 2.)
+    }
+
+    fn to_value(&self, y: f32) -> f32 {
+        let progress = (self.bottom - y) / (self.bottom - self.top);
+        self.min + progress * (self.max - self.min)
+    }
+}
+
+/// Sample an element's curve the same way bones are animated.
+fn sample(anim: &Animation, id: i32, element: &AnimElement, frame: f32) -> f32 {
+    runtime::sample(
+        anim,
+        id,
+        element.clone(),
+        AnimElement::default_of(element),
+        frame,
+    )
+}
+
+fn draw_grid(ui: &egui::Ui, view: &View) {
+    // labels stick to the left edge, no matter how far the graph is scrolled
+    let label_x = ui.clip_rect().left() + 3.;
+    for i in 0..=GRID_LINES {
+        let value = view.min + (view.max - view.min) * i as f32 / GRID_LINES as f32;
+        let y = view.to_screen(0., value).y;
+        ui.painter().hline(
+            ui.min_rect().x_range(),
+            y,
+            Stroke::new(1., ui::COLOR_FRAMELINE_PASTLAST),
+        );
+        ui.painter().text(
+            egui::pos2(label_x, y),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{:.2}", value),
+            egui::FontId::monospace(10.),
+            ui::COLOR_TEXT,
+        );
+    }
+}
+
+fn draw_curves(ui: &egui::Ui, shared: &Shared, view: &View) {
+    let anim = shared.selected_animation().unwrap();
+    let frames = shared.ui.anim.lines_x.len();
+    for (id, element) in visible_channels(shared) {
+        let points: Vec<egui::Pos2> = (0..frames * SAMPLES)
+            .map(|s| {
+                let frame = s as f32 / SAMPLES as f32;
+                view.to_screen(frame, sample(anim, id, &element, frame))
+            })
+            .collect();
+        ui.painter().add(egui::Shape::line(
+            points,
+            Stroke::new(2., channel_color(&element)),
+        ));
+    }
+}
+
+fn draw_keyframes(ui: &mut egui::Ui, shared: &mut Shared, view: &View) {
+    let pointer = ui.ctx().input(|i| i.pointer.hover_pos());
+    if !ui.ctx().input(|i| i.pointer.primary_down()) {
+        shared.ui.anim.graph_dragged = None;
+    }
+
+    // dragging is tracked by index rather than by response, since keyframes
+    // are re-sorted (and their rects moved) while being dragged
+    if let (Some(idx), Some(pointer)) = (shared.ui.anim.graph_dragged, pointer) {
+        drag_keyframe(shared, idx, view, pointer);
+        shared.cursor_icon = egui::CursorIcon::Grabbing;
+    }
+
+    for i in 0..shared.selected_animation().unwrap().keyframes.len() {
+        let kf = &shared.selected_animation().unwrap().keyframes[i];
+        if !is_visible(shared, kf.bone_id, &kf.element) || !kf.element.has_value() {
+            continue;
+        }
+
+        let pos = view.to_screen(kf.frame as f32, kf.value);
+        let frame = kf.frame;
+        let mut fill = channel_color(&kf.element);
+        let mut stroke = egui::Color32::WHITE;
+        if shared.ui.anim.graph_dragged == Some(i) || frame == shared.ui.anim.selected_frame {
+            (fill, stroke) = (stroke, fill);
+        }
+        ui.painter().circle(pos, 4., fill, Stroke::new(1.5, stroke));
+
+        let rect = egui::Rect::from_center_size(pos, egui::Vec2::splat(10.));
+        let response: egui::Response = ui.allocate_rect(rect, egui::Sense::drag());
+
+        if response.hovered() {
+            shared.cursor_icon = egui::CursorIcon::Grab;
+        }
+
+        if response.drag_started() && shared.ui.anim.graph_dragged.is_none() {
+            shared.ui.anim.graph_dragged = Some(i);
+            shared.ui.anim.selected_frame = frame;
+        }
+    }
+}
+
+/// Move a keyframe to the frame and value under the pointer.
+fn drag_keyframe(shared: &mut Shared, idx: usize, view: &View, pointer: egui::Pos2) {
+    let keyframes = &mut shared.selected_animation_mut().unwrap().keyframes;
+    if idx >= keyframes.len() {
+        shared.ui.anim.graph_dragged = None;
+        return;
+    }
+
+    let id = keyframes[idx].bone_id;
+    let element = keyframes[idx].element.clone();
+    let frame = view.to_frame(pointer.x).round().max(0.) as i32;
+
+    // an element can't have 2 keyframes on the same frame
+    let taken = keyframes
+        .iter()
+        .enumerate()
+        .any(|(i, kf)| i != idx && kf.bone_id == id && kf.element == element && kf.frame == frame);
+    if !taken {
+        keyframes[idx].frame = frame;
+    }
+    keyframes[idx].value = view.to_value(pointer.y);
+
+    let frame = keyframes[idx].frame;
+    shared.sort_keyframes();
+    shared.ui.anim.selected_frame = frame;
+
+    // find the keyframe again, in case sorting moved it
+    shared.ui.anim.graph_dragged = shared
+        .selected_animation()
+        .unwrap()
+        .keyframes
+        .iter()
+        .position(|kf| kf.bone_id == id && kf.element == element && kf.frame == frame);
+}
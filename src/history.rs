@@ -0,0 +1,652 @@
+//! Undo and redo, as a history of reversible commands.
+//!
+//! Each command holds the parts of an armature that an edit changed, both before and
+//! after, so it can be applied and reverted any number of times. Rather than every
+//! edit making its own, `History::record` compares the armature with how it was after
+//! the last command, so nothing that changes it can be left out. Commands are named
+//! after what they changed, and edits of the same thing in a row (like each step of a
+//! drag) are merged into one until the history is sealed.
+
+use crate::runtime::*;
+
+/// Default for `History::budget`.
+pub const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    /// The armature as of the last command, which edits are found by comparing with.
+    /// Taken the first time an armature is recorded.
+    snapshot: Option<Armature>,
+    /// If the next command can't merge into the last one.
+    sealed: bool,
+    /// Roughly how many bytes the commands can take up, before the oldest are forgotten.
+    pub budget: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+            snapshot: None,
+            sealed: true,
+            budget: DEFAULT_BUDGET,
+        }
+    }
+}
+
+impl History {
+    /// Record everything that changed in the armature since the last command as a new
+    /// one, or as part of the last one if it changed the same thing and the history
+    /// isn't sealed. Returns false if nothing changed.
+    pub fn record(&mut self, armature: &Armature) -> bool {
+        let Some(snapshot) = &mut self.snapshot else {
+            self.snapshot = Some(armature.clone());
+            return false;
+        };
+        let changes = Change::all_between(snapshot, armature);
+        if changes.is_empty() {
+            return false;
+        }
+        for change in &changes {
+            change.apply(snapshot);
+        }
+
+        let command = Command::new(changes);
+        self.redo.clear();
+        match self.undo.last_mut() {
+            Some(last) if !self.sealed && last.key == command.key => {
+                let changes = Change::merge(&last.changes, &command.changes, snapshot);
+                if changes.is_empty() {
+                    // edited back to how it was
+                    self.undo.pop();
+                } else {
+                    *last = Command::new(changes);
+                }
+            }
+            _ => self.undo.push(command),
+        }
+        self.sealed = false;
+        self.trim();
+        true
+    }
+
+    /// Start a new command on the next edit, instead of merging it into the last one.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Revert the last command (after recording any edits that weren't yet), and
+    /// return it.
+    pub fn undo(&mut self, armature: &mut Armature) -> Option<&Command> {
+        self.record(armature);
+        let command = self.undo.pop()?;
+        command.revert(armature);
+        command.revert(self.snapshot.as_mut().unwrap());
+        self.sealed = true;
+        self.redo.push(command);
+        self.redo.last()
+    }
+
+    /// Apply the last undone command again, and return it.
+    pub fn redo(&mut self, armature: &mut Armature) -> Option<&Command> {
+        self.record(armature);
+        let command = self.redo.pop()?;
+        command.apply(armature);
+        command.apply(self.snapshot.as_mut().unwrap());
+        self.sealed = true;
+        self.undo.push(command);
+        self.undo.last()
+    }
+
+    /// Commands that can be undone, from the oldest.
+    pub fn undo_steps(&self) -> &[Command] {
+        &self.undo
+    }
+
+    /// Commands that can be redone, from the last one undone.
+    pub fn redo_steps(&self) -> &[Command] {
+        &self.redo
+    }
+
+    /// If an armature has been recorded yet, to compare the next edits with.
+    pub fn has_snapshot(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Roughly how many bytes the commands and the snapshot take up.
+    pub fn weight(&self) -> usize {
+        let commands: usize = self.undo.iter().chain(&self.redo).map(|c| c.weight).sum();
+        // the snapshot's pixels are the armature's own (see `Pixels`), so they're free
+        let snapshot = self.snapshot.as_ref().map_or(0, |s| {
+            s.weight() - s.textures.iter().map(|t| t.pixels.len()).sum::<usize>()
+        });
+        commands + snapshot
+    }
+
+    // forget the oldest commands until the rest fit the budget, always keeping the last
+    fn trim(&mut self) {
+        let mut weight = self.weight();
+        while weight > self.budget && self.undo.len() > 1 {
+            weight -= self.undo.remove(0).weight;
+        }
+    }
+}
+
+/// A reversible edit of an armature.
+#[derive(Clone, PartialEq)]
+pub struct Command {
+    label: String,
+    /// What was edited, regardless of how. Commands in a row with the same key merge.
+    key: String,
+    changes: Vec<Change>,
+    weight: usize,
+}
+
+impl Command {
+    fn new(changes: Vec<Change>) -> Self {
+        // named after the most important change, see `Change::all_between`
+        let label = changes[0].label();
+        let key: Vec<String> = changes.iter().map(|c| c.key()).collect();
+        Self {
+            label,
+            key: key.join(", "),
+            weight: changes.iter().map(|c| c.weight()).sum(),
+            changes,
+        }
+    }
+
+    /// What the command did, such as "Rename bone 'Arm' to 'Left Arm'".
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// If any textures were changed, which need uploading again after undoing or
+    /// redoing this.
+    pub fn changes_textures(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|c| matches!(c, Change::Textures(_)))
+    }
+
+    pub fn apply(&self, armature: &mut Armature) {
+        for change in &self.changes {
+            change.apply(armature);
+        }
+    }
+
+    pub fn revert(&self, armature: &mut Armature) {
+        for change in self.changes.iter().rev() {
+            change.revert(armature);
+        }
+    }
+}
+
+/// A part of an armature that was changed.
+#[derive(Clone, PartialEq)]
+pub enum Change {
+    Textures(Splice<Texture>),
+    Bones(Splice<Bone>),
+    Slots(Splice<Slot>),
+    IkConstraints(Splice<IkConstraint>),
+    Animations(Splice<Animation>),
+    /// The armature's name and position.
+    Armature {
+        before: (String, Vec2),
+        after: (String, Vec2),
+    },
+}
+
+// calls `$do` with each list of an armature, and the change to it
+macro_rules! lists {
+    ($do:ident) => {
+        $do!(textures, Textures);
+        $do!(bones, Bones);
+        $do!(slots, Slots);
+        $do!(ik_constraints, IkConstraints);
+        $do!(animations, Animations);
+    };
+}
+
+impl Change {
+    /// Everything that differs between two versions of an armature, in order of how
+    /// much each says about the edit (eg. removing a texture also unassigns slots).
+    pub fn all_between(before: &Armature, after: &Armature) -> Vec<Change> {
+        let mut changes = vec![];
+        macro_rules! between {
+            ($list:ident, $variant:ident) => {
+                if let Some(splice) = Splice::between(&before.$list, &after.$list) {
+                    changes.push(Change::$variant(splice));
+                }
+            };
+        }
+        lists!(between);
+
+        let placement = |a: &Armature| (a.name.clone(), a.pos);
+        if placement(before) != placement(after) {
+            changes.push(Change::Armature {
+                before: placement(before),
+                after: placement(after),
+            });
+        }
+        changes
+    }
+
+    pub fn apply(&self, armature: &mut Armature) {
+        macro_rules! apply {
+            ($list:ident, $variant:ident) => {
+                if let Change::$variant(splice) = self {
+                    splice.apply(&mut armature.$list);
+                }
+            };
+        }
+        lists!(apply);
+        if let Change::Armature { after, .. } = self {
+            (armature.name, armature.pos) = after.clone();
+        }
+    }
+
+    pub fn revert(&self, armature: &mut Armature) {
+        macro_rules! revert {
+            ($list:ident, $variant:ident) => {
+                if let Change::$variant(splice) = self {
+                    splice.revert(&mut armature.$list);
+                }
+            };
+        }
+        lists!(revert);
+        if let Change::Armature { before, .. } = self {
+            (armature.name, armature.pos) = before.clone();
+        }
+    }
+
+    /// Combine the changes of two commands in a row, given the armature after both.
+    fn merge(first: &[Change], second: &[Change], now: &Armature) -> Vec<Change> {
+        let mut merged: Vec<Change> = vec![];
+        for change in first.iter().chain(second) {
+            if merged.iter().any(|c| c.same_part(change)) {
+                continue;
+            }
+            // revert both on a copy of just this part, to find how it was before them
+            let mut after = Armature::default();
+            change.copy_part(now, &mut after);
+            let mut before = after.clone();
+            for c in second.iter().rev().chain(first.iter().rev()) {
+                if c.same_part(change) {
+                    c.revert(&mut before);
+                }
+            }
+            merged.extend(Change::all_between(&before, &after));
+        }
+
+        merged.sort_by_key(|c| c.order());
+        merged
+    }
+
+    // position in the order of `all_between`
+    fn order(&self) -> usize {
+        match self {
+            Change::Textures(_) => 0,
+            Change::Bones(_) => 1,
+            Change::Slots(_) => 2,
+            Change::IkConstraints(_) => 3,
+            Change::Animations(_) => 4,
+            Change::Armature { .. } => 5,
+        }
+    }
+
+    fn same_part(&self, other: &Change) -> bool {
+        self.order() == other.order()
+    }
+
+    // copy the part of an armature this changes
+    fn copy_part(&self, from: &Armature, to: &mut Armature) {
+        macro_rules! copy {
+            ($list:ident, $variant:ident) => {
+                if let Change::$variant(_) = self {
+                    to.$list = from.$list.clone();
+                }
+            };
+        }
+        lists!(copy);
+        if let Change::Armature { .. } = self {
+            (to.name, to.pos) = (from.name.clone(), from.pos);
+        }
+    }
+
+    /// What this did, for the history.
+    pub fn label(&self) -> String {
+        self.describe().1
+    }
+
+    // what was changed, but not to what
+    fn key(&self) -> String {
+        let (part, at) = match self {
+            Change::Textures(s) => ("textures", s.at),
+            Change::Bones(s) => ("bones", s.at),
+            Change::Slots(s) => ("slots", s.at),
+            Change::IkConstraints(s) => ("ik", s.at),
+            Change::Animations(s) => ("animations", s.at),
+            Change::Armature { .. } => ("armature", 0),
+        };
+        format!("{} {} {}", part, at, self.describe().0)
+    }
+
+    // the kind of edit, and a label for it
+    fn describe(&self) -> (&'static str, String) {
+        match self {
+            Change::Textures(s) => match s.describe("texture", |t| &t.name, describe_texture) {
+                // textures only come from files
+                ("add", label) => ("add", label.replacen("Add", "Import", 1)),
+                described => described,
+            },
+            Change::Bones(s) => match s.describe("bone", |b| &b.name, describe_bone) {
+                // bones transformed together
+                (kind @ ("pos" | "rot" | "scale"), _) if s.edited() > 1 => {
+                    let verb = match kind {
+                        "pos" => "Move",
+                        "rot" => "Rotate",
+                        _ => "Scale",
+                    };
+                    (kind, format!("{} {} bones", verb, s.edited()))
+                }
+                described => described,
+            },
+            Change::Slots(s) => match s.describe("slot", |s| &s.name, describe_slot) {
+                ("order", _) => ("order", "Change draw order".to_string()),
+                described => described,
+            },
+            Change::IkConstraints(s) => s.describe(
+                "IK constraint",
+                |_| "",
+                |_, _| ("ik", "Edit IK constraint".into()),
+            ),
+            Change::Animations(s) => s.describe("animation", |a| &a.name, describe_animation),
+            Change::Armature { before, after } if before.0 != after.0 => {
+                ("name", format!("Rename armature to '{}'", after.0))
+            }
+            Change::Armature { .. } => ("pos", "Move armature".to_string()),
+        }
+    }
+
+    fn weight(&self) -> usize {
+        match self {
+            Change::Textures(s) => s.weight(),
+            Change::Bones(s) => s.weight(),
+            Change::Slots(s) => s.weight(),
+            Change::IkConstraints(s) => s.weight(),
+            Change::Animations(s) => s.weight(),
+            Change::Armature { before, after } => before.0.len() + after.0.len(),
+        }
+    }
+}
+
+fn describe_texture(before: &Texture, after: &Texture) -> (&'static str, String) {
+    if before.regions != after.regions {
+        ("regions", format!("Edit regions of '{}'", after.name))
+    } else {
+        ("texture", format!("Edit texture '{}'", after.name))
+    }
+}
+
+fn describe_bone(before: &Bone, after: &Bone) -> (&'static str, String) {
+    let name = &after.name;
+    if before.name != after.name {
+        return (
+            "name",
+            format!("Rename bone '{}' to '{}'", before.name, name),
+        );
+    }
+    if before.parent_id != after.parent_id {
+        return ("parent", format!("Reparent bone '{}'", name));
+    }
+    let mesh = |b: &Bone| {
+        (
+            b.is_mesh,
+            b.vertices.clone(),
+            b.indices.clone(),
+            b.weights.clone(),
+        )
+    };
+    if mesh(before) != mesh(after) {
+        return ("mesh", format!("Edit mesh of '{}'", name));
+    }
+    // rotating and scaling around a pivot also moves
+    if before.rot != after.rot {
+        return ("rot", format!("Rotate '{}'", name));
+    }
+    if before.scale != after.scale {
+        return ("scale", format!("Scale '{}'", name));
+    }
+    if before.pos != after.pos {
+        return ("pos", format!("Move '{}'", name));
+    }
+    ("pivot", format!("Move pivot of '{}'", name))
+}
+
+fn describe_slot(before: &Slot, after: &Slot) -> (&'static str, String) {
+    let name = &after.name;
+    if before.name != after.name {
+        return (
+            "name",
+            format!("Rename slot '{}' to '{}'", before.name, name),
+        );
+    }
+    if (before.tex_idx, before.region_idx) != (after.tex_idx, after.region_idx) {
+        return ("texture", format!("Change texture of '{}'", name));
+    }
+    if before.color != after.color {
+        return ("color", format!("Change color of '{}'", name));
+    }
+    if before.blend != after.blend {
+        return ("blend", format!("Change blend mode of '{}'", name));
+    }
+    ("bone", format!("Move slot '{}' to another bone", name))
+}
+
+fn describe_animation(before: &Animation, after: &Animation) -> (&'static str, String) {
+    let name = &after.name;
+    if before.name != after.name {
+        return (
+            "name",
+            format!("Rename animation '{}' to '{}'", before.name, name),
+        );
+    }
+    if before.fps != after.fps {
+        return ("fps", format!("Change fps of '{}'", name));
+    }
+    // one kind, so that keying something and then dragging it is one step
+    let edited = Splice::between(&before.keyframes, &after.keyframes).unwrap();
+    let mut frames = edited.before.iter().chain(&edited.after).map(|kf| kf.frame);
+    let first = frames.next().unwrap();
+    let label = if frames.all(|frame| frame == first) {
+        let verb = match edited.before.len().cmp(&edited.after.len()) {
+            std::cmp::Ordering::Less => "Add",
+            std::cmp::Ordering::Greater => "Delete",
+            std::cmp::Ordering::Equal => "Edit",
+        };
+        let plural = if edited.before.len().max(edited.after.len()) > 1 {
+            "s"
+        } else {
+            ""
+        };
+        format!("{} keyframe{} @{}", verb, plural, first)
+    } else {
+        match edited.before.len().cmp(&edited.after.len()) {
+            std::cmp::Ordering::Less => format!("Add keyframes to '{}'", name),
+            std::cmp::Ordering::Greater => format!("Delete keyframes of '{}'", name),
+            std::cmp::Ordering::Equal => format!("Edit keyframes of '{}'", name),
+        }
+    };
+    ("keyframes", label)
+}
+
+/// A range of a list that was replaced with other items, like with `Vec::splice`.
+#[derive(Clone, PartialEq)]
+pub struct Splice<T> {
+    pub at: usize,
+    pub before: Vec<T>,
+    pub after: Vec<T>,
+}
+
+impl<T: Clone + PartialEq + Weigh> Splice<T> {
+    /// The smallest range that differs between two versions of a list, if any does.
+    pub fn between(before: &[T], after: &[T]) -> Option<Self> {
+        let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+        if prefix == before.len() && prefix == after.len() {
+            return None;
+        }
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Some(Self {
+            at: prefix,
+            before: before[prefix..before.len() - suffix].to_vec(),
+            after: after[prefix..after.len() - suffix].to_vec(),
+        })
+    }
+
+    pub fn apply(&self, list: &mut Vec<T>) {
+        let range = self.at..self.at + self.before.len();
+        list.splice(range, self.after.iter().cloned());
+    }
+
+    pub fn revert(&self, list: &mut Vec<T>) {
+        let range = self.at..self.at + self.after.len();
+        list.splice(range, self.before.iter().cloned());
+    }
+
+    // label items added, removed and reordered, and leave edits of one to `edit`
+    fn describe(
+        &self,
+        what: &str,
+        name: impl Fn(&T) -> &str,
+        edit: impl Fn(&T, &T) -> (&'static str, String),
+    ) -> (&'static str, String) {
+        let named = |item: &T| match name(item) {
+            "" => what.to_string(),
+            item => format!("{} '{}'", what, item),
+        };
+        match (&self.before[..], &self.after[..]) {
+            ([], [item]) => ("add", format!("Add {}", named(item))),
+            ([], items) => ("add", format!("Add {} {}s", items.len(), what)),
+            ([item], []) => ("remove", format!("Delete {}", named(item))),
+            (items, []) => ("remove", format!("Delete {} {}s", items.len(), what)),
+            ([before], [after]) => edit(before, after),
+            (before, after) => {
+                let moved = before.len() == after.len() && before.iter().all(|i| after.contains(i));
+                if moved {
+                    ("order", format!("Reorder {}s", what))
+                } else if before.len() == after.len() {
+                    // eg. renaming a bone also renames its slot
+                    edit(&before[0], &after[0])
+                } else {
+                    ("items", format!("Edit {} {}s", after.len(), what))
+                }
+            }
+        }
+    }
+
+    // how many items were edited in place
+    fn edited(&self) -> usize {
+        if self.before.len() != self.after.len() {
+            return 0;
+        }
+        self.before
+            .iter()
+            .zip(&self.after)
+            .filter(|(before, after)| before != after)
+            .count()
+    }
+
+    fn weight(&self) -> usize {
+        self.before
+            .iter()
+            .chain(&self.after)
+            .map(|i| i.weight())
+            .sum()
+    }
+}
+
+/// Roughly how many bytes something takes up, for `History::budget`.
+pub trait Weigh {
+    fn weight(&self) -> usize;
+}
+
+impl Weigh for Texture {
+    fn weight(&self) -> usize {
+        size_of::<Texture>()
+            + self.name.len()
+            + self.pixels.len()
+            + self
+                .regions
+                .iter()
+                .map(|r| size_of::<Region>() + r.name.len())
+                .sum::<usize>()
+    }
+}
+
+impl Weigh for Bone {
+    fn weight(&self) -> usize {
+        size_of::<Bone>()
+            + self.name.len()
+            + self.vertices.len() * size_of::<Vertex>()
+            + self.indices.len() * size_of::<u32>()
+            + self
+                .weights
+                .iter()
+                .map(|w| w.len() * size_of::<BoneWeight>())
+                .sum::<usize>()
+    }
+}
+
+impl Weigh for Slot {
+    fn weight(&self) -> usize {
+        size_of::<Slot>() + self.name.len()
+    }
+}
+
+impl Weigh for IkConstraint {
+    fn weight(&self) -> usize {
+        size_of::<IkConstraint>()
+    }
+}
+
+impl Weigh for Keyframe {
+    fn weight(&self) -> usize {
+        size_of::<Keyframe>()
+            + self.deform.len() * size_of::<Vec2>()
+            + self.draw_order.len() * size_of::<usize>()
+    }
+}
+
+impl Weigh for Armature {
+    fn weight(&self) -> usize {
+        fn sum<T: Weigh>(items: &[T]) -> usize {
+            items.iter().map(|i| i.weight()).sum()
+        }
+        size_of::<Armature>()
+            + self.name.len()
+            + sum(&self.bones)
+            + sum(&self.slots)
+            + sum(&self.animations)
+            + sum(&self.textures)
+            + sum(&self.ik_constraints)
+    }
+}
+
+impl Weigh for Animation {
+    fn weight(&self) -> usize {
+        size_of::<Animation>()
+            + self.name.len()
+            + self.keyframes.iter().map(|kf| kf.weight()).sum::<usize>()
+    }
+}
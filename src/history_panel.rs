@@ -0,0 +1,55 @@
+//! UI History window, listing every step that can be undone or redone.
+
+use crate::*;
+
+pub fn draw(egui_ctx: &egui::Context, shared: &mut Shared) {
+    let mut open = shared.ui.history_open;
+    let mut jump_to: Option<usize> = None;
+
+    egui::Window::new("History")
+        .open(&mut open)
+        .resizable(true)
+        .default_width(175.)
+        .default_pos(egui::Pos2::new(
+            shared.ui.camera_bar_pos.x - 200.,
+            shared.ui.edit_bar_pos.y + 40.,
+        ))
+        .show(egui_ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let done = shared.history.undo_steps().len();
+
+                // clicking a step undoes or redoes up to it
+                if ui::selection_button("Start", done == 0, ui).clicked() {
+                    jump_to = Some(0);
+                }
+                for (i, step) in shared.history.undo_steps().iter().enumerate() {
+                    if ui::selection_button(step.label(), i + 1 == done, ui).clicked() {
+                        jump_to = Some(i + 1);
+                    }
+                }
+
+                // what was undone, from the next step to redo
+                let redo = shared.history.redo_steps();
+                if !redo.is_empty() {
+                    ui.separator();
+                }
+                for (i, step) in redo.iter().rev().enumerate() {
+                    let text = egui::RichText::new(step.label()).color(ui::COLOR_TEXT_FADED);
+                    let button = egui::Button::new(text)
+                        .fill(ui::COLOR_MAIN)
+                        .corner_radius(egui::CornerRadius::ZERO);
+                    let response = ui
+                        .add(button)
+                        .on_hover_cursor(egui::CursorIcon::PointingHand);
+                    if response.clicked() {
+                        jump_to = Some(done + i + 1);
+                    }
+                }
+            });
+        });
+
+    shared.ui.history_open = open;
+    if let Some(steps) = jump_to {
+        shared.jump_history(steps);
+    }
+}
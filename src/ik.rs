@@ -0,0 +1,175 @@
+//! Inverse kinematics, rotating chains of bones so that their ends reach a target.
+
+use std::f32::consts::PI;
+
+use crate::runtime::*;
+
+// CCD passes over a chain, and how close the end has to be to stop early
+const CCD_ITERATIONS: usize = 20;
+const TOLERANCE: f32 = 0.001;
+
+/// Apply IK constraints (in order) to bones in local space.
+///
+/// Only rotations are changed. The mix of each constraint is sampled from `anim`
+/// if it has been keyed.
+pub fn solve(
+    constraints: &[IkConstraint],
+    anim: Option<&Animation>,
+    frame: f32,
+    bones: &mut [Bone],
+) {
+    for constraint in constraints {
+        let mix = match anim {
+            Some(anim) => sample(
+                anim,
+                constraint.bone_id,
+                AnimElement::IkMix,
+                constraint.mix,
+                frame,
+            ),
+            None => constraint.mix,
+        }
+        .clamp(0., 1.);
+        if mix <= 0. {
+            continue;
+        }
+
+        let Some((end, joints)) = chain(bones, constraint) else {
+            continue;
+        };
+        let Some(target_idx) = bones.iter().position(|b| b.id == constraint.target_id) else {
+            continue;
+        };
+
+        // the target might be part of the chain, so pin it down before anything moves
+        let target = world_transforms(bones)[target_idx].translation();
+        let original: Vec<f32> = joints.iter().map(|j| bones[*j].rot).collect();
+
+        if joints.len() == 2 {
+            two_bone(
+                bones,
+                end,
+                joints[1],
+                joints[0],
+                target,
+                constraint.bend_positive,
+            );
+        } else {
+            ccd(bones, end, &joints, target);
+        }
+
+        for (j, rot) in joints.iter().zip(original) {
+            bones[*j].rot = rot + wrap(bones[*j].rot - rot) * mix;
+        }
+    }
+}
+
+/// Get the index of the constraint's end bone, and of the joints that rotate it
+/// (starting from its parent).
+///
+/// Chains stop early at root bones.
+fn chain(bones: &[Bone], constraint: &IkConstraint) -> Option<(usize, Vec<usize>)> {
+    let end = bones.iter().position(|b| b.id == constraint.bone_id)?;
+
+    let mut joints: Vec<usize> = vec![];
+    let mut current = end;
+    while joints.len() < constraint.chain_length.max(0) as usize {
+        let parent_id = bones[current].parent_id;
+        let Some(parent) = bones.iter().position(|b| b.id == parent_id) else {
+            break;
+        };
+
+        // cyclic parents
+        if parent == end || joints.contains(&parent) {
+            break;
+        }
+
+        joints.push(parent);
+        current = parent;
+    }
+
+    if joints.is_empty() {
+        return None;
+    }
+    Some((end, joints))
+}
+
+/// Solve a 2-joint chain with the law of cosines.
+fn two_bone(
+    bones: &mut [Bone],
+    end: usize,
+    upper: usize,
+    lower: usize,
+    target: Vec2,
+    bend_positive: bool,
+) {
+    let world = world_transforms(bones);
+    let upper_pos = world[upper].translation();
+    let lower_pos = world[lower].translation();
+    let end_pos = world[end].translation();
+
+    let upper_len = (lower_pos - upper_pos).length();
+    let lower_len = (end_pos - lower_pos).length();
+    let to_target = target - upper_pos;
+    let dist = to_target.length();
+    if upper_len == 0. || dist == 0. {
+        return;
+    }
+
+    // unreachable targets leave the chain fully stretched towards (or folded away from) them
+    let dist = dist.clamp((upper_len - lower_len).abs(), upper_len + lower_len);
+    let cos =
+        (upper_len * upper_len + dist * dist - lower_len * lower_len) / (2. * upper_len * dist);
+    let mut bend = cos.clamp(-1., 1.).acos();
+    if !bend_positive {
+        bend = -bend;
+    }
+
+    let angle = to_target.y.atan2(to_target.x) + bend;
+    let lower_target = upper_pos + Vec2::new(angle.cos(), angle.sin()) * upper_len;
+    rotate_towards(bones, &world, upper, lower_pos, lower_target);
+
+    let world = world_transforms(bones);
+    let end_pos = world[end].translation();
+    rotate_towards(bones, &world, lower, end_pos, target);
+}
+
+/// Cyclic coordinate descent: rotate each joint (from the end up) to aim the end at the
+/// target, until it's close enough.
+fn ccd(bones: &mut [Bone], end: usize, joints: &[usize], target: Vec2) {
+    for _ in 0..CCD_ITERATIONS {
+        for joint in joints {
+            let world = world_transforms(bones);
+            let end_pos = world[end].translation();
+            if (end_pos - target).length() < TOLERANCE {
+                return;
+            }
+            rotate_towards(bones, &world, *joint, end_pos, target);
+        }
+    }
+}
+
+/// Rotate a bone around its origin, so that `from` (in world space) points at `to`.
+fn rotate_towards(bones: &mut [Bone], world: &[Transform], idx: usize, from: Vec2, to: Vec2) {
+    let origin = world[idx].translation();
+    let from = from - origin;
+    let to = to - origin;
+    if from.length() == 0. || to.length() == 0. {
+        return;
+    }
+
+    let mut delta = wrap(to.y.atan2(to.x) - from.y.atan2(from.x));
+
+    // mirrored parents flip the direction of local rotations
+    let parent = bones.iter().position(|b| b.id == bones[idx].parent_id);
+    if parent.is_some_and(|p| world[p].determinant() < 0.) {
+        delta = -delta;
+    }
+
+    bones[idx].rot += delta;
+}
+
+/// Wrap an angle to -PI..PI.
+fn wrap(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(PI * 2.) - PI
+}
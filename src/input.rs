@@ -3,7 +3,6 @@
 use crate::*;
 
 use winit::event::ElementState;
-use winit::keyboard::*;
 
 pub fn keyboard_input(
     key: &winit::keyboard::KeyCode,
@@ -30,100 +29,6 @@ pub fn keyboard_input(
             }
         }
     }
-
-    if shared.input.is_pressing(KeyCode::Equal) {
-        ui::set_zoom(shared.camera.zoom - 0.1, shared)
-    } else if shared.input.is_pressing(KeyCode::Minus) {
-        ui::set_zoom(shared.camera.zoom + 0.1, shared);
-    }
-
-    if *key == KeyCode::SuperLeft {
-        if *state == ElementState::Pressed {
-            shared.input.modifier = 1;
-        } else {
-            shared.input.modifier = -1;
-        }
-    }
-
-    let mut undo = false;
-    let mut redo = false;
-    if shared.input.modifier == 1 {
-        if shared.input.is_pressing(KeyCode::KeyZ) && shared.undo_actions.len() != 0 {
-            undo = true;
-        } else if shared.input.is_pressing(KeyCode::KeyY) && shared.redo_actions.len() != 0 {
-            redo = true;
-        }
-    }
-
-    if undo || redo {
-        let action: Action;
-        if undo {
-            action = shared.undo_actions.last().unwrap().clone();
-        } else {
-            action = shared.redo_actions.last().unwrap().clone();
-        }
-        let mut new_action = action.clone();
-
-        match &action.action {
-            ActionEnum::Bone => {
-                if action.action_type == ActionType::Created {
-                    shared.selected_bone_idx = usize::MAX;
-                    if undo {
-                        for (i, bone) in shared.armature.bones.iter().enumerate() {
-                            if bone.id == action.id {
-                                shared.armature.bones.remove(i);
-                                break;
-                            }
-                        }
-                    } else {
-                        armature_window::new_bone(shared, -1);
-                    }
-                } else {
-                    new_action.bone = shared.armature.bones[action.id as usize].clone();
-                    *shared.find_bone_mut(action.id).unwrap() = action.bone.clone();
-
-                    for i in 0..shared.armature.bones.len() {
-                        shared.organize_bone(i);
-                    }
-                }
-            }
-            ActionEnum::Animation => {
-                if action.action_type == ActionType::Created {
-                    shared.ui.anim.selected = usize::MAX;
-                    if undo {
-                        shared.armature.animations.pop();
-                    } else {
-                        keyframe_editor::new_animation(shared);
-                    }
-                } else {
-                    new_action.animation = shared.armature.animations[action.id as usize].clone();
-                    shared.armature.animations[action.id as usize] = action.animation.clone();
-                }
-            }
-            _ => {}
-        }
-
-        if undo {
-            shared.redo_actions.push(new_action);
-            shared.undo_actions.pop();
-        } else {
-            shared.undo_actions.push(new_action);
-            shared.redo_actions.pop();
-        }
-    }
-
-    if shared
-        .input
-        .is_pressing(winit::keyboard::KeyCode::SuperLeft)
-    {
-        if shared.input.is_pressing(winit::keyboard::KeyCode::KeyS) {
-            if shared.save_path == "" {
-                utils::open_save_dialog();
-            } else {
-                utils::save(shared.save_path.clone(), shared);
-            }
-        }
-    }
 }
 
 pub fn mouse_input(shared: &mut crate::shared::Shared) {
@@ -6,7 +6,7 @@ use ui::COLOR_ACCENT;
 
 use crate::*;
 
-const LINE_OFFSET: f32 = 30.;
+pub const LINE_OFFSET: f32 = 30.;
 
 pub fn draw(egui_ctx: &egui::Context, shared: &mut Shared) {
     if !shared.ui.anim.playing {
@@ -21,17 +21,8 @@ pub fn draw(egui_ctx: &egui::Context, shared: &mut Shared) {
 
         shared.ui.anim.selected_frame = (elapsed / frametime) as i32;
         if shared.ui.anim.selected_frame >= shared.last_keyframe().unwrap().frame {
-            if shared.recording {
-                if shared.ui.anim.loops > 0 {
-                    shared.ui.anim.loops -= 1;
-                } else {
-                    shared.ui.anim.playing = false;
-                    shared.recording = false;
-                }
-            } else {
-                shared.ui.anim.elapsed = Some(std::time::Instant::now());
-                shared.ui.anim.played_frame = 0;
-            }
+            shared.ui.anim.elapsed = Some(std::time::Instant::now());
+            shared.ui.anim.played_frame = 0;
         }
     }
 
@@ -94,11 +85,6 @@ fn draw_animations_list(ui: &mut egui::Ui, shared: &mut Shared) {
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             ui.add_space(5.);
                             if ui::button("New", ui).clicked() {
-                                shared.undo_actions.push(Action {
-                                    action: ActionEnum::Animation,
-                                    action_type: ActionType::Created,
-                                    ..Default::default()
-                                });
                                 new_animation(shared);
                                 let idx = shared.armature.animations.len() - 1;
                                 shared.ui.original_name = "".to_string();
@@ -158,6 +144,12 @@ fn timeline_editor(ui: &mut egui::Ui, shared: &mut Shared) {
                 draw_bones_list(ui, shared, &mut bone_tops);
             }
 
+            // zoom into the curves before anything is laid out with the old zoom
+            let mut graph_offset = None;
+            if shared.ui.anim.graph {
+                graph_offset = graph_editor::apply_framing(shared, ui.available_width());
+            }
+
             // calculate how far apart each keyframe should visually be
             let gap = 400.;
             let hitbox = gap
@@ -199,7 +191,11 @@ fn timeline_editor(ui: &mut egui::Ui, shared: &mut Shared) {
                 // so that the remaining height can be taken up by timeline graph.
                 ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
                     draw_bottom_bar(ui, shared);
-                    draw_timeline_graph(ui, shared, width, bone_tops, hitbox);
+                    if shared.ui.anim.graph {
+                        graph_editor::draw(ui, shared, width, hitbox, graph_offset);
+                    } else {
+                        draw_timeline_graph(ui, shared, width, bone_tops, hitbox);
+                    }
                 });
             });
         });
@@ -246,15 +242,38 @@ pub fn draw_bones_list(ui: &mut egui::Ui, shared: &mut Shared, bone_tops: &mut B
                             }
                         }
 
-                        let mut last_id = -1;
+                        let mut last_id = None;
                         for ti in tops_init {
-                            if last_id != ti.id {
-                                ui.label(shared.find_bone(ti.id).unwrap().name.clone());
+                            if last_id != Some(ti.id) {
+                                // draw orders are keyed on the armature, rather than a bone
+                                let name = match shared.find_bone(ti.id) {
+                                    Some(bone) => bone.name.clone(),
+                                    None => "Armature".to_string(),
+                                };
+                                ui.label(name);
                             }
                             let mut top = 0.;
                             ui.horizontal(|ui| {
                                 ui.add_space(30.);
-                                let label = ui.label(ti.element.to_string());
+                                if !shared.ui.anim.graph {
+                                    let label = ui.label(ti.element.to_string());
+                                    top = label.rect.top();
+                                    return;
+                                }
+
+                                // in graph mode, labels double as the legend and toggle their curve
+                                let mut text = egui::RichText::new(ti.element.to_string());
+                                if graph_editor::is_visible(shared, ti.id, &ti.element) {
+                                    text = text.color(graph_editor::channel_color(&ti.element));
+                                } else {
+                                    text = text.strikethrough();
+                                }
+                                let label = ui
+                                    .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                    .on_hover_cursor(egui::CursorIcon::PointingHand);
+                                if label.clicked() {
+                                    graph_editor::toggle_visible(shared, ti.id, &ti.element);
+                                }
                                 top = label.rect.top();
                             });
                             for kf in &mut shared.selected_animation_mut().unwrap().keyframes {
@@ -266,7 +285,7 @@ pub fn draw_bones_list(ui: &mut egui::Ui, shared: &mut Shared, bone_tops: &mut B
                                     })
                                 }
                             }
-                            last_id = ti.id;
+                            last_id = Some(ti.id);
                         }
                     });
             });
@@ -315,13 +334,6 @@ pub fn draw_top_bar(ui: &mut egui::Ui, shared: &mut Shared, width: f32, hitbox:
                         continue;
                     }
 
-                    shared.undo_actions.push(shared::Action {
-                        action: ActionEnum::Animation,
-                        action_type: ActionType::Edited,
-                        id: shared.ui.anim.selected as i32,
-                        animation: shared.selected_animation().unwrap().clone(),
-                        ..Default::default()
-                    });
                     shared.cursor_icon = egui::CursorIcon::Grabbing;
                     let cursor = shared.ui.get_cursor(ui);
 
@@ -466,6 +478,31 @@ pub fn draw_bottom_bar(ui: &mut egui::Ui, shared: &mut Shared) {
 
             ui.add_space(20.);
 
+            let str = if shared.ui.anim.graph {
+                "Keyframes"
+            } else {
+                "Graph"
+            };
+            if ui::button(str, ui).clicked() {
+                shared.ui.anim.graph = !shared.ui.anim.graph;
+                if shared.ui.anim.graph {
+                    shared.ui.anim.graph_framing = Some(GraphFraming::All);
+                }
+            }
+            if shared.ui.anim.graph {
+                if ui::button("Frame All", ui).clicked() {
+                    shared.ui.anim.graph_framing = Some(GraphFraming::All);
+                }
+                if ui::button("Frame Selected", ui)
+                    .on_hover_text("Frame the curves of the selected bone")
+                    .clicked()
+                {
+                    shared.ui.anim.graph_framing = Some(GraphFraming::Selection);
+                }
+            }
+
+            ui.add_space(20.);
+
             ui.label("Frame:");
             ui.add(egui::DragValue::new(&mut shared.ui.anim.selected_frame).speed(0.1));
 
@@ -488,44 +525,7 @@ fn draw_frame_lines(
     hitbox: f32,
     cursor: Vec2,
 ) {
-    shared.ui.anim.lines_x = vec![];
-
-    let mut x = 0.;
-    let mut i = 0;
-    while x < ui.min_rect().width() {
-        x = i as f32 * hitbox * 2. + LINE_OFFSET;
-
-        shared.ui.anim.lines_x.push(x);
-
-        let mut color = ui::COLOR_FRAMELINE;
-        if shared.last_keyframe() != None && i > shared.last_keyframe().unwrap().frame {
-            color = ui::COLOR_FRAMELINE_PASTLAST;
-        }
-
-        let above_scrollbar = cursor.y < ui.min_rect().height() - 13.;
-        let in_ui = cursor.y > 0.;
-
-        if shared.ui.anim.selected_frame == i {
-            color = egui::Color32::WHITE;
-        } else if in_ui && cursor.x < x + hitbox && cursor.x > x - hitbox && above_scrollbar {
-            shared.cursor_icon = egui::CursorIcon::PointingHand;
-            color = ui::COLOR_FRAMELINE_HOVERED;
-
-            // select this frame if clicked
-            if shared.input.mouse_left == 0 {
-                shared.ui.anim.selected_frame = i;
-            }
-        }
-
-        // draw the line!
-        ui.painter().vline(
-            ui.min_rect().left() + x,
-            egui::Rangef { min: 0., max: 999. },
-            Stroke { width: 2., color },
-        );
-
-        i += 1;
-    }
+    draw_lines(ui, shared, hitbox, cursor);
 
     // used to determine lowest rendered icon, to add extra space at the bottom
     let mut height = 0.;
@@ -549,11 +549,8 @@ fn draw_frame_lines(
         }
 
         let rect = egui::Rect::from_min_size((pos - offset).into(), size.into());
-        let mut idx = kf.element.clone().clone() as usize;
-        if idx > shared.ui.anim.icon_images.len() - 1 {
-            idx = shared.ui.anim.icon_images.len() - 1;
-        }
-        egui::Image::new(&shared.ui.anim.icon_images[shared::ANIM_ICON_ID[idx]]).paint_at(ui, rect);
+        let idx = shared::ANIM_ICON_ID[kf.element.clone() as usize];
+        egui::Image::new(&shared.ui.anim.icon_images[idx]).paint_at(ui, rect);
 
         let rect = egui::Rect::from_center_size(pos.into(), (size * 0.5).into());
         let response: egui::Response = ui.allocate_rect(rect, egui::Sense::drag());
@@ -588,6 +585,48 @@ fn draw_frame_lines(
     ui.allocate_rect(rect, egui::Sense::empty());
 }
 
+/// Draw the vertical line of each frame, and select one if it's clicked.
+pub fn draw_lines(ui: &mut egui::Ui, shared: &mut Shared, hitbox: f32, cursor: Vec2) {
+    shared.ui.anim.lines_x = vec![];
+
+    let mut x = 0.;
+    let mut i = 0;
+    while x < ui.min_rect().width() {
+        x = i as f32 * hitbox * 2. + LINE_OFFSET;
+
+        shared.ui.anim.lines_x.push(x);
+
+        let mut color = ui::COLOR_FRAMELINE;
+        if shared.last_keyframe().is_some_and(|kf| i > kf.frame) {
+            color = ui::COLOR_FRAMELINE_PASTLAST;
+        }
+
+        let above_scrollbar = cursor.y < ui.min_rect().height() - 13.;
+        let in_ui = cursor.y > 0.;
+
+        if shared.ui.anim.selected_frame == i {
+            color = egui::Color32::WHITE;
+        } else if in_ui && cursor.x < x + hitbox && cursor.x > x - hitbox && above_scrollbar {
+            shared.cursor_icon = egui::CursorIcon::PointingHand;
+            color = ui::COLOR_FRAMELINE_HOVERED;
+
+            // select this frame if clicked
+            if shared.input.mouse_left == 0 {
+                shared.ui.anim.selected_frame = i;
+            }
+        }
+
+        // draw the line!
+        ui.painter().vline(
+            ui.min_rect().left() + x,
+            egui::Rangef { min: 0., max: 999. },
+            Stroke { width: 2., color },
+        );
+
+        i += 1;
+    }
+}
+
 fn draw_diamond(ui: &egui::Ui, pos: Vec2) {
     let size = 5.0;
 
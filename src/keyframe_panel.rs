@@ -22,9 +22,9 @@ pub fn draw(ui: &mut egui::Ui, shared: &mut Shared) {
         egui::ComboBox::new("transition_dropdown".to_string(), "")
             .selected_text(transition.to_string())
             .show_ui(ui, |ui| {
-                transition!(Transition::Linear, ui);
-                transition!(Transition::SineIn, ui);
-                transition!(Transition::SineOut, ui);
+                for t in Transition::ALL {
+                    transition!(t.clone(), ui);
+                }
             })
             .response;
 
@@ -38,4 +38,93 @@ pub fn draw(ui: &mut egui::Ui, shared: &mut Shared) {
             }
         }
     });
+
+    if shared.selected_keyframe().is_some()
+        && shared.selected_keyframe().unwrap().transition == Transition::Bezier
+    {
+        ui.add_space(5.);
+        draw_curve_editor(ui, shared);
+    }
+}
+
+/// Graph of the bezier transition, with draggable handles.
+fn draw_curve_editor(ui: &mut egui::Ui, shared: &mut Shared) {
+    let mut handles = shared.selected_keyframe().unwrap().handles;
+    let og_handles = handles;
+
+    let size = egui::Vec2::splat(ui.available_width().min(150.));
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+    // curve space goes from 0 to 1, with Y going up
+    let to_screen = |point: Vec2| -> egui::Pos2 {
+        egui::pos2(
+            rect.left() + point.x * rect.width(),
+            rect.bottom() - point.y * rect.height(),
+        )
+    };
+    let to_curve = |pos: egui::Pos2| -> Vec2 {
+        Vec2::new(
+            (pos.x - rect.left()) / rect.width(),
+            (rect.bottom() - pos.y) / rect.height(),
+        )
+    };
+
+    ui.painter()
+        .rect_filled(rect, egui::CornerRadius::ZERO, ui::COLOR_ACCENT);
+
+    // drag handles
+    let anchors = [Vec2::ZERO, Vec2::new(1., 1.)];
+    for (i, anchor) in anchors.iter().enumerate() {
+        let handle = if i == 0 { &mut handles.start } else { &mut handles.end };
+
+        let handle_rect = egui::Rect::from_center_size(to_screen(*handle), egui::Vec2::splat(10.));
+        let response = ui.interact(
+            handle_rect,
+            ui.id().with(("bezier_handle", i)),
+            egui::Sense::drag(),
+        );
+
+        if response.hovered() {
+            shared.cursor_icon = egui::CursorIcon::Grab;
+        }
+
+        if response.dragged() {
+            shared.cursor_icon = egui::CursorIcon::Grabbing;
+            if let Some(pointer) = response.interact_pointer_pos() {
+                *handle = to_curve(pointer);
+
+                // time can't go backwards
+                handle.x = handle.x.clamp(0., 1.);
+            }
+        }
+
+        ui.painter().line_segment(
+            [to_screen(*anchor), to_screen(*handle)],
+            egui::Stroke::new(1., ui::COLOR_TEXT),
+        );
+        ui.painter()
+            .circle_filled(to_screen(*handle), 4., egui::Color32::WHITE);
+    }
+
+    // draw the curve itself
+    let segments = 40;
+    let mut points = vec![];
+    for i in 0..segments + 1 {
+        let x = i as f32 / segments as f32;
+        points.push(to_screen(Vec2::new(x, handles.progress(x))));
+    }
+    ui.painter().add(egui::Shape::line(
+        points,
+        egui::Stroke::new(2., egui::Color32::WHITE),
+    ));
+
+    // change all fields to use new handles
+    if og_handles != handles {
+        let selected_frame = shared.ui.anim.selected_frame;
+        for kf in &mut shared.selected_animation_mut().unwrap().keyframes {
+            if kf.frame == selected_frame {
+                kf.handles = handles;
+            }
+        }
+    }
 }
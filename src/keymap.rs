@@ -0,0 +1,382 @@
+//! Keyboard shortcuts, and the actions they (and the top bar's menus) run.
+//!
+//! Every menu entry is an `Action`, so anything in a menu can also be bound to a key.
+//! Each platform has its own default shortcuts, which can be rebound in the editor.
+//! Rebound shortcuts are kept in `keymap.json`, in SkelForm's config folder.
+
+use crate::*;
+
+use egui::os::OperatingSystem;
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Import,
+    Save,
+    SaveAs,
+    Export,
+    ExportSpine,
+    ExportDragonBones,
+    AtlasSettings,
+    Undo,
+    Redo,
+    Shortcuts,
+    ZoomIn,
+    ZoomOut,
+    History,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::Import,
+        Action::Save,
+        Action::SaveAs,
+        Action::Export,
+        Action::ExportSpine,
+        Action::ExportDragonBones,
+        Action::AtlasSettings,
+        Action::Undo,
+        Action::Redo,
+        Action::Shortcuts,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::History,
+    ];
+
+    /// Name of the action in the keymap file.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::Import => "import",
+            Action::Save => "save",
+            Action::SaveAs => "save_as",
+            Action::Export => "export",
+            Action::ExportSpine => "export_spine",
+            Action::ExportDragonBones => "export_dragonbones",
+            Action::AtlasSettings => "atlas_settings",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Shortcuts => "shortcuts",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::History => "history",
+        }
+    }
+
+    /// Name of the action in menus.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Import => "Import",
+            Action::Save => "Save",
+            Action::SaveAs => "Save As",
+            Action::Export => "Export",
+            Action::ExportSpine => "Export to Spine",
+            Action::ExportDragonBones => "Export to DragonBones",
+            Action::AtlasSettings => "Atlas Settings",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::Shortcuts => "Keyboard Shortcuts",
+            Action::ZoomIn => "Zoom In",
+            Action::ZoomOut => "Zoom Out",
+            Action::History => "History",
+        }
+    }
+
+    /// Shortcut of the action on a platform, unless it's rebound.
+    pub fn default_shortcut(&self, os: OperatingSystem) -> Option<KeyboardShortcut> {
+        let mac = matches!(os, OperatingSystem::Mac | OperatingSystem::IOS);
+        let cmd = if mac {
+            Modifiers::MAC_CMD
+        } else {
+            Modifiers::CTRL
+        };
+        let (modifiers, key) = match self {
+            Action::Import => (cmd, Key::I),
+            Action::Save => (cmd, Key::S),
+            Action::SaveAs => (cmd | Modifiers::SHIFT, Key::S),
+            Action::Export => (cmd, Key::E),
+            Action::Undo => (cmd, Key::Z),
+            Action::Redo if mac => (cmd | Modifiers::SHIFT, Key::Z),
+            Action::Redo => (cmd, Key::Y),
+            Action::Shortcuts => (cmd, Key::Comma),
+            Action::ZoomIn => (Modifiers::NONE, Key::Equals),
+            Action::ZoomOut => (Modifiers::NONE, Key::Minus),
+            Action::History => (cmd | Modifiers::SHIFT, Key::H),
+            Action::ExportSpine | Action::ExportDragonBones | Action::AtlasSettings => return None,
+        };
+        Some(KeyboardShortcut::new(modifiers, key))
+    }
+
+    pub fn run(&self, shared: &mut Shared) {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::Import => utils::open_import_dialog(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::Save if shared.save_path.is_empty() => utils::open_save_dialog(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::Save => utils::save(shared.save_path.clone(), shared),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::SaveAs => utils::open_save_dialog(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::ExportSpine => utils::open_export_dialog(utils::Format::Spine),
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::ExportDragonBones => utils::open_export_dialog(utils::Format::DragonBones),
+            // there are no file dialogs on the web
+            #[cfg(target_arch = "wasm32")]
+            Action::Import
+            | Action::Save
+            | Action::SaveAs
+            | Action::ExportSpine
+            | Action::ExportDragonBones => {}
+            Action::Export => shared.ui.export_modal = true,
+            Action::AtlasSettings => shared.ui.atlas_modal = true,
+            Action::Undo => shared.undo(),
+            Action::Redo => shared.redo(),
+            Action::Shortcuts => shared.ui.keymap_modal = true,
+            Action::ZoomIn => ui::set_zoom(shared.camera.zoom - 0.1, shared),
+            Action::ZoomOut => ui::set_zoom(shared.camera.zoom + 0.1, shared),
+            Action::History => shared.ui.history_open = !shared.ui.history_open,
+        }
+    }
+}
+
+/// The shortcut of every action, if it has one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Keymap {
+    shortcuts: [Option<KeyboardShortcut>; Action::ALL.len()],
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::defaults(OperatingSystem::from_target_os())
+    }
+}
+
+impl Keymap {
+    /// The default shortcuts of a platform.
+    pub fn defaults(os: OperatingSystem) -> Self {
+        Self {
+            shortcuts: Action::ALL.map(|action| action.default_shortcut(os)),
+        }
+    }
+
+    pub fn get(&self, action: Action) -> Option<KeyboardShortcut> {
+        self.shortcuts[action as usize]
+    }
+
+    /// Bind an action to a shortcut (or nothing). An action that had the shortcut
+    /// already is unbound, and returned.
+    pub fn set(&mut self, action: Action, shortcut: Option<KeyboardShortcut>) -> Option<Action> {
+        let mut taken_from = None;
+        if shortcut.is_some() {
+            for other in Action::ALL {
+                if other != action && self.get(other) == shortcut {
+                    self.shortcuts[other as usize] = None;
+                    taken_from = Some(other);
+                }
+            }
+        }
+        self.shortcuts[action as usize] = shortcut;
+        taken_from
+    }
+
+    /// Shortcut of an action as shown in menus, or nothing if it has none.
+    pub fn text(&self, action: Action) -> String {
+        self.get(action).map(format_shortcut).unwrap_or_default()
+    }
+
+    /// The actions whose shortcuts were pressed this frame. Their key presses are
+    /// consumed, so nothing else reacts to them.
+    pub fn pressed(&self, context: &egui::Context) -> Vec<Action> {
+        let mut bound: Vec<(Action, KeyboardShortcut)> = Action::ALL
+            .iter()
+            .filter_map(|action| Some((*action, self.get(*action)?)))
+            .collect();
+
+        // extra modifiers are ignored when matching, so the most specific go first
+        // (eg. Ctrl+Shift+S before Ctrl+S)
+        let specificity = |m: Modifiers| {
+            [m.alt, m.ctrl, m.shift, m.mac_cmd]
+                .iter()
+                .filter(|m| **m)
+                .count()
+        };
+        bound.sort_by_key(|(_, shortcut)| std::cmp::Reverse(specificity(shortcut.modifiers)));
+
+        context.input_mut(|input| {
+            bound
+                .iter()
+                .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(action, _)| *action)
+                .collect()
+        })
+    }
+
+    /// Keymap file contents, with every action's shortcut (or null).
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        for action in Action::ALL {
+            let shortcut = self.get(action).map(format_shortcut);
+            map.insert(action.id().to_string(), shortcut.into());
+        }
+        map.into()
+    }
+
+    /// Read a keymap file. Actions it leaves out keep their defaults on `os`, unless
+    /// the file gives their shortcut to another action (as `set` would).
+    ///
+    /// Two actions given the same shortcut by the file is an error, since only one of
+    /// them could ever run.
+    pub fn from_json(json: &serde_json::Value, os: OperatingSystem) -> Result<Self, String> {
+        let map = json
+            .as_object()
+            .ok_or("The keymap isn't a JSON object.".to_string())?;
+        let mut keymap = Self::defaults(os);
+        let mut bound: Vec<(KeyboardShortcut, &str)> = vec![];
+        for (id, shortcut) in map {
+            let action = Action::ALL
+                .into_iter()
+                .find(|a| a.id() == id)
+                .ok_or(format!("There's no action called '{}'.", id))?;
+            let shortcut = match shortcut {
+                serde_json::Value::Null => None,
+                serde_json::Value::String(text) => {
+                    let shortcut =
+                        parse_shortcut(text).map_err(|err| format!("{}: {}", id, err))?;
+                    Some(shortcut)
+                }
+                _ => return Err(format!("{}: shortcuts are written like \"Ctrl+S\".", id)),
+            };
+            if let Some(shortcut) = shortcut {
+                if let Some((_, other)) = bound.iter().find(|(s, _)| *s == shortcut) {
+                    return Err(format!(
+                        "{} and {} are both bound to {}.",
+                        other,
+                        id,
+                        format_shortcut(shortcut)
+                    ));
+                }
+                bound.push((shortcut, id));
+            }
+            keymap.set(action, shortcut);
+        }
+        Ok(keymap)
+    }
+
+    /// Where the keymap is kept, in the user's config folder.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path() -> Option<std::path::PathBuf> {
+        use std::path::PathBuf;
+        let var = |name: &str| {
+            std::env::var_os(name)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        let config = if cfg!(target_os = "windows") {
+            var("APPDATA")
+        } else if cfg!(target_os = "macos") {
+            var("HOME").map(|home| home.join("Library/Application Support"))
+        } else {
+            var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+        };
+        Some(config?.join("SkelForm").join("keymap.json"))
+    }
+
+    /// Load the user's keymap, or the defaults if they have none.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Result<Self, String> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_from(path: &std::path::Path) -> Result<Self, String> {
+        let err =
+            |err: String| format!("Couldn't read the keymap at {}\n\n{}", path.display(), err);
+        let text = std::fs::read_to_string(path).map_err(|e| err(e.to_string()))?;
+        let json: serde_json::Value =
+            serde_json::from_str(&text).map_err(|e| err(e.to_string()))?;
+        Self::from_json(&json, OperatingSystem::from_target_os()).map_err(err)
+    }
+
+    /// Keep the keymap in the user's config folder.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("There's no config folder to keep the keymap in.")?;
+        self.save_to(&path)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_to(&self, path: &std::path::Path) -> Result<(), String> {
+        let err = |err: std::io::Error| {
+            format!("Couldn't save the keymap to {}\n\n{}", path.display(), err)
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(err)?;
+        }
+        let text = serde_json::to_string_pretty(&self.to_json()).unwrap();
+        std::fs::write(path, text).map_err(err)
+    }
+}
+
+/// Only the modifiers a shortcut is written with, as they're matched regardless of
+/// platform (see `egui::Modifiers::matches_logically`).
+pub fn shortcut_modifiers(pressed: Modifiers) -> Modifiers {
+    let mut modifiers = Modifiers::NONE;
+    for (is_pressed, modifier) in [
+        (pressed.ctrl, Modifiers::CTRL),
+        (pressed.mac_cmd, Modifiers::MAC_CMD),
+        (pressed.alt, Modifiers::ALT),
+        (pressed.shift, Modifiers::SHIFT),
+    ] {
+        if is_pressed {
+            modifiers |= modifier;
+        }
+    }
+    modifiers
+}
+
+/// A shortcut as text, like "Ctrl+Shift+S".
+pub fn format_shortcut(shortcut: KeyboardShortcut) -> String {
+    let names = egui::ModifierNames::NAMES;
+    let mut text = String::new();
+    let m = shortcut.modifiers;
+    for (is_held, name) in [
+        (m.ctrl, names.ctrl),
+        (m.mac_cmd, names.mac_cmd),
+        (m.alt, names.alt),
+        (m.shift, names.shift),
+    ] {
+        if is_held {
+            text += name;
+            text += "+";
+        }
+    }
+    text + shortcut.logical_key.name()
+}
+
+/// Read a shortcut written like `format_shortcut` does.
+pub fn parse_shortcut(text: &str) -> Result<KeyboardShortcut, String> {
+    // the last part is the key, which might itself be a +
+    let (modifier_names, key) = if text == "+" {
+        ("", "+")
+    } else if let Some(rest) = text.strip_suffix("++") {
+        (rest, "+")
+    } else {
+        text.rsplit_once('+').unwrap_or(("", text))
+    };
+
+    let key = Key::from_name(key.trim()).ok_or(format!("'{}' isn't a key.", key))?;
+    let mut modifiers = Modifiers::NONE;
+    for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+        modifiers |= match name.trim() {
+            "Ctrl" => Modifiers::CTRL,
+            "Cmd" => Modifiers::MAC_CMD,
+            "Alt" | "Option" => Modifiers::ALT,
+            "Shift" => Modifiers::SHIFT,
+            other => return Err(format!("'{}' isn't a modifier.", other)),
+        };
+    }
+    Ok(KeyboardShortcut::new(modifiers, key))
+}
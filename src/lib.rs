@@ -1,8 +1,10 @@
+#[cfg(feature = "editor")]
 use shared::*;
+#[cfg(feature = "editor")]
 use wgpu::{BindGroupLayout, InstanceDescriptor};
 
 // native-only imports
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 mod native {
     pub use crate::file_reader::*;
     pub use image::*;
@@ -10,20 +12,22 @@ mod native {
     pub use std::io::Write;
     pub use std::time::Instant;
 }
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 use native::*;
 
 // native-only imports
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "editor"))]
 mod web {
     pub use wasm_bindgen::*;
     pub use web_sys::*;
     pub use web_time::Instant;
 }
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "editor"))]
 use web::*;
 
+#[cfg(feature = "editor")]
 use std::sync::Arc;
+#[cfg(feature = "editor")]
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
@@ -31,17 +35,54 @@ use winit::{
     window::{Theme, Window},
 };
 
+// editor-independent, see Cargo.toml
+pub mod atlas;
+pub mod dragonbones;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod error;
+pub mod export;
+pub mod history;
+pub mod ik;
+pub mod migrate;
+pub mod regions;
+pub mod runtime;
+pub mod spine;
+pub mod transform;
+
+#[cfg(feature = "editor")]
 pub mod armature_window;
+#[cfg(feature = "editor")]
 pub mod bone_panel;
+#[cfg(feature = "editor")]
 pub mod file_reader;
+#[cfg(feature = "editor")]
+pub mod gizmo;
+#[cfg(feature = "editor")]
+pub mod graph_editor;
+#[cfg(feature = "editor")]
+pub mod history_panel;
+#[cfg(feature = "editor")]
 pub mod input;
+#[cfg(feature = "editor")]
 pub mod keyframe_editor;
+#[cfg(feature = "editor")]
+pub mod keymap;
+#[cfg(feature = "editor")]
 pub mod keyframe_panel;
+#[cfg(feature = "editor")]
+pub mod mesh_editor;
+#[cfg(all(feature = "editor", not(target_arch = "wasm32")))]
+pub mod offscreen;
+#[cfg(feature = "editor")]
 pub mod renderer;
+#[cfg(feature = "editor")]
 pub mod shared;
+#[cfg(feature = "editor")]
 pub mod ui;
+#[cfg(feature = "editor")]
 pub mod utils;
 
+#[cfg(feature = "editor")]
 #[derive(Default)]
 pub struct App {
     window: Option<Arc<Window>>,
@@ -54,6 +95,7 @@ pub struct App {
     pub shared: shared::Shared,
 }
 
+#[cfg(feature = "editor")]
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let mut attributes = Window::default_attributes();
@@ -189,6 +231,26 @@ impl ApplicationHandler for App {
             return;
         };
 
+        // every click starts a new step in the history, so that a drag is undone as one
+        if let WindowEvent::MouseInput { .. } = event {
+            self.shared.history.seal();
+        }
+
+        // input edits the armature through the gui as well, so this is before it's taken
+        let pointer_down = gui_state.egui_ctx().input(|i| i.pointer.any_down());
+        self.shared.armature_dirty |= match event {
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::PinchGesture { .. }
+            | WindowEvent::Touch(_)
+            | WindowEvent::Ime(_)
+            | WindowEvent::DroppedFile(_) => true,
+            // dragging, rather than hovering
+            WindowEvent::CursorMoved { .. } => pointer_down,
+            _ => false,
+        };
+
         // Receive gui window event
         if gui_state.on_window_event(window, &event).consumed {
             return;
@@ -220,7 +282,7 @@ impl ApplicationHandler for App {
                 self.shared.window = Vec2::new(self.last_size.0 as f32, self.last_size.1 as f32);
             }
             WindowEvent::CloseRequested => {
-                if self.shared.undo_actions.len() > 0 {
+                if !self.shared.history.undo_steps().is_empty() {
                     self.shared.ui.polar_id = "exiting".to_string();
                     self.shared.ui.polar_headline =
                         "Are you sure you want to quit and discard unsaved changes?".to_string();
@@ -291,6 +353,13 @@ impl ApplicationHandler for App {
                     textures_delta,
                     &mut self.shared,
                 );
+
+                // the first recording is only a snapshot to compare edits with
+                if std::mem::take(&mut self.shared.armature_dirty)
+                    || !self.shared.history.has_snapshot()
+                {
+                    self.shared.record_edits();
+                }
             }
             _ => (),
         }
@@ -316,6 +385,18 @@ impl ApplicationHandler for App {
         generic_bindgroup!(self.shared.highlight_bindgroup, vec![255, 255, 255, 70]);
         generic_bindgroup!(self.shared.gridline_bindgroup, vec![255, 255, 255, 20]);
         generic_bindgroup!(self.shared.point_bindgroup, vec![0, 255, 0, 255]);
+        generic_bindgroup!(self.shared.gizmo_bindgroup, vec![255, 255, 255, 255]);
+
+        if self.shared.reload_textures {
+            self.shared.reload_textures = false;
+            (self.shared.bind_groups, self.shared.ui.texture_images) = utils::load_textures(
+                &self.shared.armature.textures,
+                &self.renderer.as_ref().unwrap().gpu.queue,
+                &self.renderer.as_ref().unwrap().gpu.device,
+                &self.renderer.as_ref().unwrap().bind_group_layout,
+                self.gui_state.as_ref().unwrap().egui_ctx(),
+            );
+        }
 
         #[cfg(not(target_arch = "wasm32"))]
         if self.shared.debug {
@@ -343,6 +424,7 @@ impl ApplicationHandler for App {
     }
 }
 
+#[cfg(feature = "editor")]
 pub struct Renderer {
     gpu: Gpu,
     depth_texture_view: wgpu::TextureView,
@@ -351,6 +433,7 @@ pub struct Renderer {
     bind_group_layout: BindGroupLayout,
 }
 
+#[cfg(feature = "editor")]
 impl Renderer {
     const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
@@ -370,29 +453,7 @@ impl Renderer {
             false,
         );
 
-        let bind_group_layout =
-            gpu.device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                multisampled: false,
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                            count: None,
-                        },
-                    ],
-                    label: Some("texture_bind_group_layout"),
-                });
+        let bind_group_layout = renderer::create_bind_group_layout(&gpu.device);
 
         let scene = Scene::new(&gpu.device, gpu.surface_format, &bind_group_layout);
 
@@ -492,10 +553,15 @@ impl Renderer {
             occlusion_query_set: None,
         });
 
-        render_pass.set_pipeline(&self.scene.pipeline);
+        render_pass.set_pipeline(&self.scene.pipelines[BlendMode::Normal as usize]);
 
         // core rendering logic handled in renderer.rs
-        renderer::render(&mut render_pass, &self.gpu.device, shared);
+        renderer::render(
+            &mut render_pass,
+            &self.gpu.device,
+            &self.scene.pipelines,
+            shared,
+        );
 
         self.egui_renderer.render(
             &mut render_pass.forget_lifetime(),
@@ -507,211 +573,57 @@ impl Renderer {
 
         if shared.recording {
             #[cfg(not(target_arch = "wasm32"))]
-            self.take_screenshot(shared);
-        } else if shared.done_recording {
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                let frames = shared.rendered_frames.clone();
-                let window = shared.window.clone();
-                std::thread::spawn(move || {
-                    Self::export_video(frames, window);
-                });
-                shared.done_recording = false;
-            }
+            self.export_animation(shared);
+            shared.recording = false;
         }
     }
 
+    /// Render the selected animation offscreen with the export settings, writing out
+    /// each frame as it's rendered, in the background.
     #[cfg(not(target_arch = "wasm32"))]
-    fn take_screenshot(&mut self, shared: &mut shared::Shared) {
-        let width = shared.window.x as u32;
-        let height = shared.window.y as u32;
-
-        let capture_texture = self.gpu.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Bgra8Unorm,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
-            label: Some("Capture Texture"),
-            view_formats: &[],
-        });
-
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = self
-            .gpu
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Copy to Buffer Encoder"),
-            });
-
-        {
-            let mut capture_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Capture Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &capture_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.8,
-                            g: 0.8,
-                            b: 0.8,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_texture_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            capture_pass.set_pipeline(&self.scene.pipeline);
-
-            // core rendering logic handled in renderer.rs
-            renderer::render(&mut capture_pass, &self.gpu.device, shared);
-        }
-
-        let buffer_size = (width * height * 4) as u64;
-        let output_buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Readback Buffer"),
-            size: buffer_size,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
-
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(4 * width),
-                    rows_per_image: Some(height),
-                },
-            },
-            wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        shared.rendered_frames.push(RenderedFrame {
-            buffer: output_buffer.clone(),
-            width,
-            height,
-        });
-
-        self.gpu.queue.submit(std::iter::once(encoder.finish()));
-        let buffer_slice = output_buffer.slice(..);
-        buffer_slice.map_async(wgpu::MapMode::Read, |result| {
-            if let Ok(()) = result {
-            } else {
-                panic!("Failed to map buffer for read.");
-            }
+    fn export_animation(&mut self, shared: &mut shared::Shared) {
+        let device = self.gpu.device.clone();
+        let queue = self.gpu.queue.clone();
+        let armature = shared.armature.clone();
+        let anim_idx = shared.ui.anim.selected;
+        let camera = shared.camera.clone();
+        let settings = shared.export.clone();
+        std::thread::spawn(move || {
+            let offscreen = offscreen::Offscreen::new(&device, &queue);
+            match offscreen.render_animation(&armature, anim_idx, &camera, &settings) {
+                Ok(frames) => Self::export_frames(frames, &settings),
+                Err(err) => file_reader::create_temp_file(
+                    TEMP_EXPORT_VID_TEXT,
+                    &(EXPORT_VID_FAILED.to_owned() + &err),
+                ),
+            };
         });
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn export_video(rendered_frames: Vec<RenderedFrame>, window: Vec2) {
-        let width = rendered_frames[0].width.to_string();
-        let height = rendered_frames[0].height.to_string();
-
-        let output_width = width.clone();
-        let output_height = height.clone();
-
-        let mut child = std::process::Command::new("ffmpeg")
-            .args([
-                "-f",
-                "rawvideo",
-                // input resolution
-                "-video_size",
-                &(width + "x" + &height),
-                // fps
-                "-r",
-                "60",
-                "-pixel_format",
-                "rgb24",
-                "-i",
-                "-",
-                // output resolution
-                "-s",
-                &("".to_owned() + &output_width.to_string() + ":" + &output_height.to_string()),
-                // fast preset
-                "-preset",
-                "veryfast",
-                // don't encode audio
-                "-c:a",
-                "copy",
-                "-y",
-                "output.mp4",
-                "-loglevel",
-                "verbose",
-            ])
-            .stdin(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::inherit())
-            .spawn()
-            .unwrap();
-
-        let mut stdin = child.stdin.take().unwrap();
-
-        for i in 0..rendered_frames.len() {
-            let buffer_slice = rendered_frames[i].buffer.slice(..);
-            let view = buffer_slice.get_mapped_range();
-
-            let mut rgb = vec![0u8; (window.x * window.y * 3.) as usize];
-            for (j, chunk) in view.as_ref().chunks_exact(4).enumerate() {
-                let offset = j * 3;
-                rgb[offset + 0] = chunk[2];
-                rgb[offset + 1] = chunk[1];
-                rgb[offset + 2] = chunk[0];
-            }
-
-            let img = <image::ImageBuffer<image::Rgb<u8>, _>>::from_raw(
-                window.x as u32,
-                window.y as u32,
-                rgb,
-            );
-
-            stdin.write_all(img.as_ref().unwrap()).unwrap();
-
-            let frame = i.to_string();
-            let headline = "Exporting... ".to_owned()
-                + &frame.to_owned()
-                + " out of "
-                + &(rendered_frames.len() - 1).to_string()
-                + " frames";
-            if i != rendered_frames.len() - 1 {
+    fn export_frames(
+        frames: impl ExactSizeIterator<Item = image::RgbaImage>,
+        settings: &export::ExportSettings,
+    ) {
+        let total = frames.len();
+        let result = export::export(settings, frames, |done| {
+            if done != total {
+                let headline = format!("Exporting... {} out of {} frames", done, total);
                 file_reader::create_temp_file(TEMP_EXPORT_VID_TEXT, &headline);
             }
-        }
-
-        file_reader::create_temp_file(TEMP_EXPORT_VID_TEXT, EXPORT_VID_DONE);
+        });
 
-        stdin.flush().unwrap();
-        drop(stdin);
-        child.wait().unwrap();
+        match result {
+            Ok(()) => file_reader::create_temp_file(TEMP_EXPORT_VID_TEXT, EXPORT_VID_DONE),
+            Err(err) => file_reader::create_temp_file(
+                TEMP_EXPORT_VID_TEXT,
+                &(EXPORT_VID_FAILED.to_owned() + &err.to_string()),
+            ),
+        }
     }
 }
 
+#[cfg(feature = "editor")]
 pub struct Gpu {
     pub surface: wgpu::Surface<'static>,
     pub device: wgpu::Device,
@@ -720,6 +632,7 @@ pub struct Gpu {
     pub surface_format: wgpu::TextureFormat,
 }
 
+#[cfg(feature = "editor")]
 impl Gpu {
     pub fn aspect_ratio(&self) -> f32 {
         self.surface_config.width as f32 / self.surface_config.height.max(1) as f32
@@ -850,25 +763,51 @@ impl Gpu {
     }
 }
 
-struct Scene {
-    pub pipeline: wgpu::RenderPipeline,
+#[cfg(feature = "editor")]
+pub(crate) struct Scene {
+    /// One for each `BlendMode`, in its order.
+    pub pipelines: Vec<wgpu::RenderPipeline>,
 }
 
+#[cfg(feature = "editor")]
 impl Scene {
     pub fn new(
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
         bind_group_layout: &BindGroupLayout,
     ) -> Self {
-        let pipeline = Self::create_pipeline(device, surface_format, &bind_group_layout);
+        let pipelines = BlendMode::ALL
+            .iter()
+            .map(|blend| Self::create_pipeline(device, surface_format, bind_group_layout, *blend))
+            .collect();
+
+        Self { pipelines }
+    }
 
-        Self { pipeline }
+    /// How colors are mixed for a blend mode. The shader outputs premultiplied alpha.
+    fn blend_state(blend: BlendMode) -> wgpu::BlendState {
+        use wgpu::BlendFactor::*;
+        let color = |src_factor, dst_factor| wgpu::BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        };
+        wgpu::BlendState {
+            color: match blend {
+                BlendMode::Normal => color(One, OneMinusSrcAlpha),
+                BlendMode::Additive => color(One, One),
+                BlendMode::Multiply => color(Dst, OneMinusSrcAlpha),
+                BlendMode::Screen => color(One, OneMinusSrc),
+            },
+            alpha: wgpu::BlendComponent::OVER,
+        }
     }
 
     fn create_pipeline(
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
         bind_group_layout: &BindGroupLayout,
+        blend: BlendMode,
     ) -> wgpu::RenderPipeline {
         let shader_str = &String::from_utf8(include_bytes!("shader.wgsl").to_vec())
             .unwrap()
@@ -884,6 +823,13 @@ impl Scene {
             step_mode: wgpu::VertexStepMode::Vertex,
             attributes: &attributes,
         };
+        // tint of whatever's being drawn, see `renderer::set_color`
+        let color_attributes = wgpu::vertex_attr_array![2 => Float32x4].to_vec();
+        let color_layout = wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Color>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &color_attributes,
+        };
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
@@ -896,12 +842,12 @@ impl Scene {
             vertex: wgpu::VertexState {
                 module: &shader_module,
                 entry_point: Some("vertex_main"),
-                buffers: &[vertex_layout],
+                buffers: &[vertex_layout, color_layout],
                 compilation_options: Default::default(),
             },
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint32),
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
                 front_face: wgpu::FrontFace::Cw,
                 cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
@@ -925,7 +871,7 @@ impl Scene {
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    blend: Some(Self::blend_state(blend)),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
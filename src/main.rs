@@ -3,9 +3,54 @@
 use skelform_lib::shared::*;
 
 #[cfg(not(target_arch = "wasm32"))]
-use skelform_lib::file_reader;
+use skelform_lib::{atlas, export, file_reader, keymap, offscreen, runtime, utils};
+
+const USAGE: &str = "Usage: SkelForm [COMMAND] [FILE] [OPTIONS]
+
+Without a command, the editor is opened, importing FILE if one is given.
+
+Commands:
+  export <file.skf>    Render an animation
+      --armature <name|index> Armature to render (default: 0)
+      --anim <name|index>     Animation to render (default: 0)
+      --out <path>            Output file (default: output.png)
+      --format <format>       png, gif, webp, ffmpeg or a video extension
+                              like mp4 (default: from --out)
+      --fps <fps>             Frames per second (default: the animation's)
+      --start <frame>         First frame (default: 0)
+      --end <frame>           Last frame (default: last keyframe)
+      --size <width>x<height> Resolution (default: 1280x720)
+      --camera <x>,<y>        Camera position (default: where the armature is)
+      --zoom <zoom>           Camera zoom (default: 5)
+      --transparent           Leave the background transparent
+      --software              Render on the CPU, for machines without a GPU
+  pack <file.skf>      Rebuild the texture atlas
+      --out <path>            Output file (default: overwrite the input)
+      --max-size <pixels>     Largest page size
+      --padding <pixels>      Space between textures
+      --extrude <pixels>      Edge pixels repeated around textures
+  info <file.skf>      List armatures, bones, animations and keyframe counts
+  validate <file.skf>  Check for broken ids and indices
+  help                 Show this message";
+
+#[cfg(not(target_arch = "wasm32"))]
+const COMMANDS: [&str; 7] = ["export", "pack", "info", "validate", "help", "--help", "-h"];
 
 fn main() -> Result<(), winit::error::EventLoopError> {
+    // run a command instead of opening the editor, if one was given
+    #[cfg(not(target_arch = "wasm32"))]
+    let open_path = {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if args
+            .first()
+            .is_some_and(|arg| COMMANDS.contains(&arg.as_str()))
+        {
+            std::process::exit(run_command(&args));
+        }
+        // otherwise, it's a file to open (eg. one that was double-clicked)
+        args.into_iter().next()
+    };
+
     // uncomment below to get console panic hook as early as possible for debugging
     //
     // otherwise, it's activated in lib.rs
@@ -27,23 +72,518 @@ fn main() -> Result<(), winit::error::EventLoopError> {
     #[cfg(not(target_arch = "wasm32"))]
     file_reader::del_temp_files();
 
+    // imported on the first frame, as if it was picked from the import dialog
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = open_path {
+        file_reader::create_temp_file(file_reader::TEMP_IMPORT_PATH, &path);
+    }
+
     event_loop.run_app(&mut app)
 }
 
 fn init_shared(shared: &mut Shared) {
     shared.selected_bone_idx = usize::MAX;
     shared.input.mouse_left = -1;
-    shared.input.modifier = -1;
     shared.debug = false;
     shared.camera.zoom = 5.;
+    shared.armature.name = "Armature 1".to_string();
+    shared.armatures = vec![ArmatureSlot::default()];
     shared.ui.anim.selected = usize::MAX;
     shared.ui.anim.timeline_zoom = 1.;
     shared.ui.anim.exported_frame = "".to_string();
     shared.ui.anim.selected_frame = -1;
 
+    #[cfg(not(target_arch = "wasm32"))]
+    match keymap::Keymap::load() {
+        Ok(keymap) => shared.keymap = keymap,
+        Err(err) => shared.ui.modal_headline = err,
+    }
+
     // if this were false, the first click would always
     // be considered non-UI
     shared.input.on_ui = true;
 
     //shared.start_time = Some(std::time::Instant::now());
 }
+
+/// Run a command-line command, returning the exit code.
+#[cfg(not(target_arch = "wasm32"))]
+fn run_command(args: &[String]) -> i32 {
+    let command = args[0].as_str();
+    if matches!(command, "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let Some(path) = args.get(1) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let options = match Options::parse(&args[2..]) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    let root = match utils::read_skf(path) {
+        Ok(root) => root,
+        Err(err) => {
+            eprintln!("Couldn't read {}: {}", path, err);
+            return 1;
+        }
+    };
+    let armatures = root.armatures;
+
+    // broken armatures can't be rendered or saved
+    let broken = armatures.iter().any(|a| !runtime::validate(a).is_empty());
+    if matches!(command, "export" | "pack") && broken {
+        eprintln!("{} has problems, see `SkelForm validate {}`", path, path);
+        return 1;
+    }
+
+    let result = match command {
+        "export" => find(&armatures, |a| &a.name, options.get("armature"), "armature")
+            .and_then(|idx| export_command(&armatures[idx], &options)),
+        "pack" => pack_command(path, &armatures, root.atlas, &options),
+        "info" => {
+            for (i, armature) in armatures.iter().enumerate() {
+                println!("Armature {}: {}", i, armature.name);
+                for line in info(armature) {
+                    println!("{}", line);
+                }
+            }
+            Ok(())
+        }
+        "validate" => validate_command(&armatures),
+        _ => Err(format!("Unknown command '{}'\n\n{}", command, USAGE)),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+/// `--name value` options and `--name` flags.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct Options {
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Options {
+    const FLAGS: [&str; 2] = ["transparent", "software"];
+
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                return Err(format!("Unexpected argument '{}'", arg));
+            };
+            if Self::FLAGS.contains(&name) {
+                options.flags.push(name.to_string());
+                continue;
+            }
+            let Some(value) = args.next() else {
+                return Err(format!("--{} needs a value", name));
+            };
+            options.values.push((name.to_string(), value.clone()));
+        }
+        Ok(options)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    /// Parse an option's value, if it was given.
+    fn parse_value<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.get(name) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid value for --{}: '{}'", name, value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse an option with 2 values split by `separator`, like `1.5,-2`.
+    fn parse_pair<T: std::str::FromStr>(
+        &self,
+        name: &str,
+        separator: char,
+    ) -> Result<Option<(T, T)>, String> {
+        let Some(value) = self.get(name) else {
+            return Ok(None);
+        };
+        let invalid = || format!("Invalid value for --{}: '{}'", name, value);
+        let (a, b) = value.split_once(separator).ok_or_else(invalid)?;
+        Ok(Some((
+            a.trim().parse().map_err(|_| invalid())?,
+            b.trim().parse().map_err(|_| invalid())?,
+        )))
+    }
+
+    /// Parse a resolution in whole pixels, like `1280x720`.
+    fn parse_size(&self, name: &str) -> Result<Option<(u32, u32)>, String> {
+        let size = self.parse_pair(name, 'x')?;
+        if size.is_some_and(|(width, height)| width == 0 || height == 0) {
+            return Err(format!("--{} can't be 0 pixels wide or tall", name));
+        }
+        Ok(size)
+    }
+}
+
+/// Find an item by its name or index (`kind` is what it's called in errors).
+/// Without either, the first is picked.
+#[cfg(not(target_arch = "wasm32"))]
+fn find<T>(
+    items: &[T],
+    name_of: impl Fn(&T) -> &String,
+    value: Option<&str>,
+    kind: &str,
+) -> Result<usize, String> {
+    let idx = match value {
+        None => 0,
+        Some(value) => match value.parse::<usize>() {
+            Ok(idx) => idx,
+            Err(_) => items
+                .iter()
+                .position(|item| name_of(item) == value)
+                .ok_or(format!("There's no {} named '{}'", kind, value))?,
+        },
+    };
+    if idx >= items.len() {
+        return Err(format!("There's no {} {}", kind, idx));
+    }
+    Ok(idx)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export_command(armature: &runtime::Armature, options: &Options) -> Result<(), String> {
+    let anim_idx = find(
+        &armature.animations,
+        |a| &a.name,
+        options.get("anim"),
+        "animation",
+    )?;
+    let anim = &armature.animations[anim_idx];
+
+    let mut settings = export::ExportSettings {
+        fps: anim.fps.max(1) as u32,
+        transparent: options.flag("transparent"),
+        ..Default::default()
+    };
+    if let Some(out) = options.get("out") {
+        settings.path = out.to_string();
+    }
+    settings.format = match options.get("format") {
+        Some(format) => format_of(format).ok_or(format!("Unknown format '{}'", format))?,
+        None => {
+            let path = std::path::Path::new(&settings.path);
+            let ext = path.extension().unwrap_or_default().to_string_lossy();
+            format_of(&ext.to_lowercase()).ok_or(format!(
+                "Unknown format for '{}', pick one with --format",
+                settings.path
+            ))?
+        }
+    };
+    if let Some(fps) = options.parse_value("fps")? {
+        settings.fps = fps;
+    }
+    if let Some(start) = options.parse_value("start")? {
+        settings.start = start;
+    }
+    if let Some(end) = options.parse_value("end")? {
+        settings.end = end;
+    }
+    if let Some((width, height)) = options.parse_size("size")? {
+        settings.width = width;
+        settings.height = height;
+    }
+
+    let mut camera = Camera {
+        pos: armature.pos,
+        zoom: 5.,
+        ..Default::default()
+    };
+    if let Some((x, y)) = options.parse_pair("camera", ',')? {
+        camera.pos = Vec2::new(x, y);
+    }
+    if let Some(zoom) = options.parse_value("zoom")? {
+        camera.zoom = zoom;
+    }
+
+    let offscreen = pollster::block_on(offscreen::Offscreen::headless(options.flag("software")))?;
+    let frames = offscreen.render_animation(armature, anim_idx, &camera, &settings)?;
+    let total = frames.len();
+    export::export(&settings, frames, |done| {
+        print!("\rExporting... {} out of {} frames", done, total);
+    })
+    .map_err(|e| e.to_string())?;
+    println!();
+    Ok(())
+}
+
+/// The export format for a `--format` value or file extension.
+#[cfg(not(target_arch = "wasm32"))]
+fn format_of(name: &str) -> Option<export::ExportFormat> {
+    Some(match name {
+        "png" => export::ExportFormat::PngSequence,
+        "gif" => export::ExportFormat::Gif,
+        "webp" => export::ExportFormat::WebP,
+        "ffmpeg" | "mp4" | "webm" | "mov" | "mkv" | "avi" => export::ExportFormat::Ffmpeg,
+        _ => return None,
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn pack_command(
+    path: &str,
+    armatures: &[runtime::Armature],
+    mut config: atlas::AtlasConfig,
+    options: &Options,
+) -> Result<(), String> {
+    if let Some(max_size) = options.parse_value("max-size")? {
+        config.max_size = max_size;
+    }
+    if let Some(padding) = options.parse_value("padding")? {
+        config.padding = padding;
+    }
+    if let Some(extrude) = options.parse_value("extrude")? {
+        config.extrude = extrude;
+    }
+
+    let out = options.get("out").unwrap_or(path);
+    let pages = utils::write_skf(out, armatures, &config).map_err(|e| e.to_string())?;
+    let textures: usize = armatures.iter().map(|a| a.textures.len()).sum();
+    println!("Packed {} textures into {} pages: {}", textures, pages, out);
+    Ok(())
+}
+
+/// What `info` prints about an armature, a line for each of its parts.
+#[cfg(not(target_arch = "wasm32"))]
+fn info(armature: &runtime::Armature) -> Vec<String> {
+    let mut lines = vec![format!("Bones: {}", armature.bones.len())];
+    for bone in &armature.bones {
+        let parent = match armature.bones.iter().find(|b| b.id == bone.parent_id) {
+            Some(parent) => parent.name.as_str(),
+            None => "-",
+        };
+        lines.push(format!(
+            "  {} (id: {}, parent: {})",
+            bone.name, bone.id, parent
+        ));
+    }
+    lines.push(format!("Slots: {}", armature.slots.len()));
+    for slot in &armature.slots {
+        let bone = match armature.bones.iter().find(|b| b.id == slot.bone_id) {
+            Some(bone) => bone.name.as_str(),
+            None => "-",
+        };
+        let texture = match armature.textures.get(slot.tex_idx as usize) {
+            Some(tex) if slot.tex_idx != -1 => tex.name.as_str(),
+            _ => "-",
+        };
+        lines.push(format!(
+            "  {} (bone: {}, texture: {}, blend: {})",
+            slot.name, bone, texture, slot.blend
+        ));
+    }
+
+    lines.push(format!("Animations: {}", armature.animations.len()));
+    for anim in &armature.animations {
+        let last_frame = anim.keyframes.last().map(|kf| kf.frame).unwrap_or(0);
+        lines.push(format!(
+            "  {} ({} keyframes, {} frames at {} fps)",
+            anim.name,
+            anim.keyframes.len(),
+            last_frame + 1,
+            anim.fps
+        ));
+    }
+
+    lines.push(format!("Textures: {}", armature.textures.len()));
+    for tex in &armature.textures {
+        lines.push(format!(
+            "  {} ({}x{}, {} regions)",
+            tex.name,
+            tex.size.x,
+            tex.size.y,
+            tex.regions.len()
+        ));
+    }
+    lines
+}
+
+/// Everything `validate` finds wrong, naming the armature each problem is in.
+#[cfg(not(target_arch = "wasm32"))]
+fn problems(armatures: &[runtime::Armature]) -> Vec<String> {
+    let mut problems = vec![];
+    for (i, armature) in armatures.iter().enumerate() {
+        for problem in runtime::validate(armature) {
+            problems.push(format!("Armature {} ({}): {}", i, armature.name, problem));
+        }
+    }
+    problems
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn validate_command(armatures: &[runtime::Armature]) -> Result<(), String> {
+    let problems = problems(armatures);
+    if problems.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    Err(format!("{} problems found", problems.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Options::parse(&args)
+    }
+
+    fn armature() -> runtime::Armature {
+        runtime::Armature {
+            name: "Hero".to_string(),
+            bones: vec![
+                Bone {
+                    id: 0,
+                    name: "hip".to_string(),
+                    parent_id: -1,
+                    ..Default::default()
+                },
+                Bone {
+                    id: 1,
+                    name: "knee".to_string(),
+                    parent_id: 0,
+                    ..Default::default()
+                },
+            ],
+            animations: vec![Animation {
+                name: "walk".to_string(),
+                fps: 24,
+                keyframes: vec![Keyframe {
+                    frame: 11,
+                    ..Default::default()
+                }],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn options_are_values_or_flags() {
+        let options = parse(&["--out", "walk.gif", "--transparent", "--fps", "30"]).unwrap();
+        assert_eq!(options.get("out"), Some("walk.gif"));
+        assert_eq!(options.get("fps"), Some("30"));
+        assert_eq!(options.get("zoom"), None);
+        assert!(options.flag("transparent") && !options.flag("software"));
+
+        let err = |args: &[&str]| parse(args).err().unwrap();
+        assert_eq!(err(&["walk.gif"]), "Unexpected argument 'walk.gif'");
+        assert_eq!(err(&["--fps", "30", "--out"]), "--out needs a value");
+    }
+
+    #[test]
+    fn values_are_parsed_if_given() {
+        let options = parse(&["--fps", "30", "--zoom", "big", "--size", "640x480"]).unwrap();
+        assert_eq!(options.parse_value::<u32>("fps"), Ok(Some(30)));
+        assert_eq!(options.parse_value::<u32>("start"), Ok(None));
+        assert_eq!(
+            options.parse_value::<f32>("zoom"),
+            Err("Invalid value for --zoom: 'big'".to_string())
+        );
+        assert_eq!(options.parse_size("size"), Ok(Some((640, 480))));
+        assert_eq!(options.parse_pair::<f32>("camera", ','), Ok(None));
+        assert!(options.parse_pair::<f32>("size", ',').is_err());
+
+        // sizes are in whole pixels, and there has to be at least one
+        for size in ["0.5x2", "-1x2", "640x0", "640x"] {
+            let options = parse(&["--size", size]).unwrap();
+            assert!(options.parse_size("size").is_err(), "{}", size);
+        }
+        let options = parse(&["--size", "0x2"]).unwrap();
+        assert_eq!(
+            options.parse_size("size"),
+            Err("--size can't be 0 pixels wide or tall".to_string())
+        );
+        let options = parse(&["--camera", "0.5,-2"]).unwrap();
+        assert_eq!(options.parse_pair("camera", ','), Ok(Some((0.5, -2.))));
+    }
+
+    #[test]
+    fn formats_are_only_picked_by_name() {
+        assert_eq!(format_of("gif"), Some(export::ExportFormat::Gif));
+        assert_eq!(format_of("png"), Some(export::ExportFormat::PngSequence));
+        assert_eq!(format_of("mp4"), Some(export::ExportFormat::Ffmpeg));
+        assert_eq!(format_of("ffmpeg"), Some(export::ExportFormat::Ffmpeg));
+        assert_eq!(format_of("gfi"), None);
+        assert_eq!(format_of(""), None);
+
+        // before anything is rendered
+        let export = |args: &[&str]| export_command(&armature(), &parse(args).unwrap());
+        assert_eq!(
+            export(&["--format", "gfi"]),
+            Err("Unknown format 'gfi'".to_string())
+        );
+        assert_eq!(
+            export(&["--out", "walk.txt"]),
+            Err("Unknown format for 'walk.txt', pick one with --format".to_string())
+        );
+    }
+
+    #[test]
+    fn info_lists_every_part() {
+        assert_eq!(
+            info(&armature()),
+            [
+                "Bones: 2",
+                "  hip (id: 0, parent: -)",
+                "  knee (id: 1, parent: hip)",
+                "Slots: 0",
+                "Animations: 1",
+                "  walk (1 keyframes, 12 frames at 24 fps)",
+                "Textures: 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_names_the_broken_armature() {
+        let mut broken = armature();
+        broken.name = "Broken".to_string();
+        broken.bones[1].parent_id = 5;
+        assert_eq!(validate_command(&[armature()]), Ok(()));
+        assert_eq!(
+            problems(&[armature(), broken.clone()]),
+            ["Armature 1 (Broken): Bone 'knee' has parent id 5, which doesn't exist"]
+        );
+        assert_eq!(
+            validate_command(&[armature(), broken]),
+            Err("1 problems found".to_string())
+        );
+    }
+}
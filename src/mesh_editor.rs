@@ -0,0 +1,413 @@
+//! Viewport tools for editing the vertices of mesh bones.
+//!
+//! Vertices make up the outline of the mesh (in order), which is triangulated
+//! after every change. While animating, dragging vertices keys their deform instead.
+
+use crate::*;
+
+// how close (in points) the pointer has to be to grab a vertex
+const GRAB_RADIUS: f32 = 8.;
+
+/// The main of this module. Only called if `Shared::editing_mesh` is true.
+pub fn draw(egui_ctx: &egui::Context, shared: &mut Shared) {
+    let idx = shared.selected_bone_idx;
+    if shared.bone_tex_size(shared.armature.bones[idx].id).is_none() {
+        return;
+    }
+
+    let rest = runtime::inherit(&runtime::place(&shared.armature, &shared.armature.bones));
+    let mut bones = shared.armature.bones.clone();
+    if shared.is_animating() {
+        bones = shared.animate(shared.ui.anim.selected);
+    }
+    let posed = runtime::inherit(&runtime::place(&shared.armature, &bones));
+
+    let ppp = egui_ctx.pixels_per_point();
+    let points: Vec<egui::Pos2> = runtime::skin(&rest, &posed, idx)
+        .iter()
+        .map(|pos| (shared.world_to_screen(*pos) / ppp).into())
+        .collect();
+
+    draw_mesh(egui_ctx, shared, &points);
+
+    if egui_ctx.is_pointer_over_area() {
+        return;
+    }
+
+    let Some(pointer) = egui_ctx.input(|i| i.pointer.hover_pos()) else {
+        return;
+    };
+    let hovered = points
+        .iter()
+        .position(|p| p.distance(pointer) < GRAB_RADIUS);
+    if hovered.is_some() {
+        shared.cursor_icon = egui::CursorIcon::Grab;
+    }
+
+    // pointer in world space, and in the bone's space (in texture pixels)
+    let world = shared.screen_to_world(Vec2::new(pointer.x, pointer.y) * ppp);
+    let local = match rest[idx].world.inverse() {
+        Some(inverse) => inverse.apply(world) / HARD_SCALE,
+        None => return,
+    };
+
+    let (pressed, removing, down) = egui_ctx.input(|i| {
+        (
+            i.pointer.primary_pressed(),
+            i.pointer.secondary_pressed()
+                || i.key_pressed(egui::Key::Delete)
+                || i.key_pressed(egui::Key::Backspace),
+            i.pointer.primary_down(),
+        )
+    });
+
+    if !down {
+        shared.ui.dragged_vertex = None;
+    }
+
+    // Clicking on the texture adds a vertex, while clicking outside of it deselects.
+    // The shape of the mesh can't be changed while animating.
+    let animating = shared.is_animating();
+    let on_texture = clamp_to_texture(&rest[idx], selected_tex_size(shared), local) == local;
+    if pressed && hovered.is_none() && (animating || !on_texture) {
+        shared.ui.selected_vertex = None;
+    } else if pressed {
+        shared.history.seal();
+        let vert = match hovered {
+            Some(vert) => vert,
+            None => add_vertex(shared, local),
+        };
+        shared.ui.selected_vertex = Some(vert);
+        shared.ui.dragged_vertex = Some(vert);
+    } else if removing && !animating && !egui_ctx.wants_keyboard_input() {
+        // right clicking removes the hovered vertex, and delete removes the selected one
+        let vert = if egui_ctx.input(|i| i.pointer.secondary_pressed()) {
+            hovered
+        } else {
+            shared.ui.selected_vertex
+        };
+        if let Some(vert) = vert {
+            shared.history.seal();
+            remove_vertex(shared, vert);
+        }
+    }
+
+    if let Some(vert) = shared.ui.dragged_vertex {
+        shared.cursor_icon = egui::CursorIcon::Grabbing;
+        if animating {
+            deform_vertex(shared, vert, world, &rest, &posed);
+        } else {
+            move_vertex(shared, vert, local);
+        }
+    }
+}
+
+fn draw_mesh(egui_ctx: &egui::Context, shared: &Shared, points: &[egui::Pos2]) {
+    let painter = egui_ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        "mesh_editor".into(),
+    ));
+    let bone = shared.selected_bone().unwrap();
+
+    for tri in bone.indices.chunks_exact(3) {
+        let tri = [
+            points[tri[0] as usize],
+            points[tri[1] as usize],
+            points[tri[2] as usize],
+            points[tri[0] as usize],
+        ];
+        painter.add(egui::Shape::line(
+            tri.to_vec(),
+            egui::Stroke::new(1., ui::COLOR_BORDER_HOVERED),
+        ));
+    }
+
+    if points.len() > 1 {
+        painter.add(egui::Shape::closed_line(
+            points.to_vec(),
+            egui::Stroke::new(2., egui::Color32::WHITE),
+        ));
+    }
+
+    for (i, point) in points.iter().enumerate() {
+        let mut fill = egui::Color32::WHITE;
+        if shared.ui.selected_vertex == Some(i) {
+            fill = ui::COLOR_ACCENT;
+        }
+        painter.circle(
+            *point,
+            4.,
+            fill,
+            egui::Stroke::new(1.5, egui::Color32::WHITE),
+        );
+    }
+}
+
+/// Turn a bone into a mesh, starting with the corners of its texture.
+pub fn make_mesh(bone: &mut Bone, tex_size: Vec2) {
+    let (left, right, top, bottom) = bounds(bone, tex_size);
+    let corners = [
+        Vec2::new(left, top),
+        Vec2::new(left, bottom),
+        Vec2::new(right, bottom),
+        Vec2::new(right, top),
+    ];
+
+    bone.vertices = corners
+        .iter()
+        .map(|pos| Vertex {
+            pos: *pos,
+            uv: uv(bone, tex_size, *pos),
+        })
+        .collect();
+    bone.weights = vec![vec![]; bone.vertices.len()];
+    bone.indices = triangulate(&corners);
+    bone.is_mesh = true;
+}
+
+/// Turn a mesh back into a regular bone, drawn as a rect.
+pub fn remove_mesh(bone: &mut Bone) {
+    bone.vertices = vec![];
+    bone.indices = vec![];
+    bone.weights = vec![];
+    bone.is_mesh = false;
+}
+
+/// Add a vertex to the outline, on the edge closest to it. Returns the new vertex's index.
+fn add_vertex(shared: &mut Shared, pos: Vec2) -> usize {
+    let tex_size = selected_tex_size(shared);
+    let bone = shared.selected_bone_mut().unwrap();
+    let pos = clamp_to_texture(bone, tex_size, pos);
+
+    let mut closest = 0;
+    let mut closest_dist = f32::MAX;
+    for i in 0..bone.vertices.len() {
+        let a = bone.vertices[i].pos;
+        let b = bone.vertices[(i + 1) % bone.vertices.len()].pos;
+        let dist = dist_to_segment(pos, a, b);
+        if dist < closest_dist {
+            closest = i;
+            closest_dist = dist;
+        }
+    }
+
+    let idx = closest + 1;
+    let vert = Vertex {
+        pos,
+        uv: uv(bone, tex_size, pos),
+    };
+    bone.vertices.insert(idx, vert);
+    bone.weights.resize(bone.vertices.len() - 1, vec![]);
+    bone.weights.insert(idx, vec![]);
+    retriangulate(bone);
+
+    let id = bone.id;
+    for_each_deform(shared, id, |deform| {
+        if idx <= deform.len() {
+            deform.insert(idx, Vec2::ZERO);
+        }
+    });
+    idx
+}
+
+fn move_vertex(shared: &mut Shared, idx: usize, pos: Vec2) {
+    let tex_size = selected_tex_size(shared);
+    let bone = shared.selected_bone_mut().unwrap();
+    if idx >= bone.vertices.len() {
+        return;
+    }
+
+    let pos = clamp_to_texture(bone, tex_size, pos);
+    bone.vertices[idx] = Vertex {
+        pos,
+        uv: uv(bone, tex_size, pos),
+    };
+    retriangulate(bone);
+}
+
+fn remove_vertex(shared: &mut Shared, idx: usize) {
+    let bone = shared.selected_bone_mut().unwrap();
+
+    // a mesh needs at least one triangle
+    if bone.vertices.len() <= 3 || idx >= bone.vertices.len() {
+        return;
+    }
+
+    bone.vertices.remove(idx);
+    if idx < bone.weights.len() {
+        bone.weights.remove(idx);
+    }
+    retriangulate(bone);
+
+    let id = bone.id;
+    for_each_deform(shared, id, |deform| {
+        if idx < deform.len() {
+            deform.remove(idx);
+        }
+    });
+    shared.ui.selected_vertex = None;
+    shared.ui.dragged_vertex = None;
+}
+
+/// Offset a vertex at the selected frame, so that it lands on `pos` (in world space).
+fn deform_vertex(shared: &mut Shared, vert: usize, pos: Vec2, rest: &[Bone], posed: &[Bone]) {
+    let idx = shared.selected_bone_idx;
+    if vert >= shared.armature.bones[idx].vertices.len() {
+        return;
+    }
+
+    // undo skinning to find where the vertex should be in the bone
+    let Some(inverse) = runtime::vertex_transform(rest, posed, idx, vert).inverse() else {
+        return;
+    };
+    let local = inverse.apply(pos) / HARD_SCALE;
+
+    let id = shared.armature.bones[idx].id;
+    let count = shared.armature.bones[idx].vertices.len();
+    let frame = shared.ui.anim.selected_frame;
+
+    // keep the other vertices where they currently are
+    let anim = shared.selected_animation().unwrap();
+    let mut deform = runtime::sample_deform(anim, id, frame as f32).unwrap_or_default();
+    deform.resize(count, Vec2::ZERO);
+    deform[vert] = local - shared.armature.bones[idx].vertices[vert].pos;
+
+    // like other elements, the first keyframe starts from the original mesh
+    let mut frames = vec![frame];
+    if frame != 0 {
+        frames.push(0);
+    }
+
+    let keyframes = &mut shared.selected_animation_mut().unwrap().keyframes;
+    for f in frames {
+        let exists = keyframes
+            .iter()
+            .any(|kf| kf.frame == f && kf.bone_id == id && kf.element == AnimElement::Deform);
+        if !exists {
+            keyframes.push(Keyframe {
+                frame: f,
+                bone_id: id,
+                element: AnimElement::Deform,
+                deform: vec![Vec2::ZERO; count],
+                ..Default::default()
+            });
+        }
+    }
+    for kf in keyframes.iter_mut() {
+        if kf.frame == frame && kf.bone_id == id && kf.element == AnimElement::Deform {
+            kf.deform = deform.clone();
+        }
+    }
+
+    shared.sort_keyframes();
+}
+
+/// Run `f` on the deform of every keyframe of a bone, in all animations.
+fn for_each_deform(shared: &mut Shared, id: i32, mut f: impl FnMut(&mut Vec<Vec2>)) {
+    for anim in &mut shared.armature.animations {
+        for kf in &mut anim.keyframes {
+            if kf.bone_id == id && kf.element == AnimElement::Deform {
+                f(&mut kf.deform);
+            }
+        }
+    }
+}
+
+fn retriangulate(bone: &mut Bone) {
+    let outline: Vec<Vec2> = bone.vertices.iter().map(|v| v.pos).collect();
+    bone.indices = triangulate(&outline);
+}
+
+/// Triangulate a polygon by ear clipping. Returns indices into `outline`.
+///
+/// Self-intersecting outlines can't be fully clipped, so whatever is left is fanned.
+pub fn triangulate(outline: &[Vec2]) -> Vec<u32> {
+    if outline.len() < 3 {
+        return vec![];
+    }
+
+    // ears are found going counter-clockwise
+    let mut remaining: Vec<usize> = (0..outline.len()).collect();
+    let mut area = 0.;
+    for i in 0..outline.len() {
+        area += utils::cross(outline[i], outline[(i + 1) % outline.len()]);
+    }
+    if area < 0. {
+        remaining.reverse();
+    }
+
+    let mut indices: Vec<u32> = vec![];
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|i| {
+            let a = remaining[(i + len - 1) % len];
+            let b = remaining[*i];
+            let c = remaining[(i + 1) % len];
+
+            // reflex corners can't be ears
+            if utils::cross(outline[b] - outline[a], outline[c] - outline[b]) <= 0. {
+                return false;
+            }
+
+            !remaining.iter().any(|j| {
+                ![a, b, c].contains(j)
+                    && utils::in_triangle(outline[*j], outline[a], outline[b], outline[c])
+            })
+        });
+
+        let Some(ear) = ear else {
+            break;
+        };
+        indices.extend([
+            remaining[(ear + len - 1) % len] as u32,
+            remaining[ear] as u32,
+            remaining[(ear + 1) % len] as u32,
+        ]);
+        remaining.remove(ear);
+    }
+
+    for i in 1..remaining.len() - 1 {
+        indices.extend([
+            remaining[0] as u32,
+            remaining[i] as u32,
+            remaining[i + 1] as u32,
+        ]);
+    }
+
+    indices
+}
+
+/// Size of the texture (or region of it) shown by the selected bone.
+fn selected_tex_size(shared: &Shared) -> Vec2 {
+    shared.bone_tex_size(shared.selected_bone().unwrap().id).unwrap()
+}
+
+/// Left, right, top and bottom of a bone's texture, relative to the bone.
+fn bounds(bone: &Bone, tex_size: Vec2) -> (f32, f32, f32, f32) {
+    (
+        -tex_size.x * bone.pivot.x,
+        tex_size.x * (1. - bone.pivot.x),
+        tex_size.y * bone.pivot.y,
+        -tex_size.y * (1. - bone.pivot.y),
+    )
+}
+
+fn clamp_to_texture(bone: &Bone, tex_size: Vec2, pos: Vec2) -> Vec2 {
+    let (left, right, top, bottom) = bounds(bone, tex_size);
+    Vec2::new(pos.x.clamp(left, right), pos.y.clamp(bottom, top))
+}
+
+fn uv(bone: &Bone, tex_size: Vec2, pos: Vec2) -> Vec2 {
+    let (left, _, top, _) = bounds(bone, tex_size);
+    Vec2::new((pos.x - left) / tex_size.x, (top - pos.y) / tex_size.y)
+}
+
+fn dist_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len = ab.x * ab.x + ab.y * ab.y;
+    if len == 0. {
+        return (point - a).length();
+    }
+    let t = (((point - a).x * ab.x + (point - a).y * ab.y) / len).clamp(0., 1.);
+    (point - (a + ab * t)).length()
+}
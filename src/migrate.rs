@@ -0,0 +1,206 @@
+//! Upgrading armature JSON saved by older versions of the format.
+//!
+//! Every change to the format that old files would load wrong with gets a migration
+//! here, which edits the raw JSON of one version into the next. Files are upgraded one
+//! version at a time until they're current, before being deserialized into a `Root`.
+
+use serde_json::{json, Value};
+
+/// `MIGRATIONS[n]` upgrades a file from version `n` to `n + 1`.
+const MIGRATIONS: [fn(&mut Value); 2] = [v0_to_v1, v1_to_v2];
+
+/// Version of the format written by this build.
+pub const FORMAT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Version of an armature's JSON. Files from before versioning are version 0.
+pub fn version(root: &Value) -> u32 {
+    root["version"].as_u64().unwrap_or(0) as u32
+}
+
+/// Upgrade an armature's JSON to `FORMAT_VERSION`, returning the version it was at.
+///
+/// Files from a newer version than this build knows of are refused, as there's no
+/// telling what would be lost in them.
+pub fn migrate(root: &mut Value) -> Result<u32, String> {
+    if !root.is_object() {
+        return Err("The armature data isn't a JSON object.".to_string());
+    }
+
+    let from = version(root);
+    if from > FORMAT_VERSION {
+        return Err(format!(
+            "This file is from a newer version of SkelForm (format version {}, \
+             but only up to {} is supported).",
+            from, FORMAT_VERSION
+        ));
+    }
+
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(root);
+    }
+    root["version"] = FORMAT_VERSION.into();
+    Ok(from)
+}
+
+/// Every object in `array`, if it's an array.
+fn objects(array: &mut Value) -> impl Iterator<Item = &mut Value> {
+    array
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter(|value| value.is_object())
+}
+
+/// Unversioned files, from before meshes were triangulated.
+fn v0_to_v1(root: &mut Value) {
+    // order of `AnimElement` when these files were written
+    const ELEMENTS: [&str; 8] = [
+        "PositionX",
+        "PositionY",
+        "Rotation",
+        "ScaleX",
+        "ScaleY",
+        "PivotX",
+        "PivotY",
+        "Zindex",
+    ];
+
+    for armature in objects(&mut root["armatures"]) {
+        // keyframes written by runtimes (or by hand) may only have the element's index
+        for anim in objects(&mut armature["animations"]) {
+            for kf in objects(&mut anim["keyframes"]) {
+                if kf.get("element").is_some() {
+                    continue;
+                }
+                let element_id = kf["element_id"].as_u64().unwrap_or(0) as usize;
+                if let Some(element) = ELEMENTS.get(element_id) {
+                    kf["element"] = (*element).into();
+                }
+            }
+        }
+
+        // meshes used to be flagged without any triangles, and their vertices were
+        // the (screen space) corners of the texture, so draw those as plain rects
+        for bone in objects(&mut armature["bones"]) {
+            let has_indices = bone["indices"].as_array().is_some_and(|i| !i.is_empty());
+            if !has_indices {
+                bone["is_mesh"] = false.into();
+                bone["vertices"] = Value::Array(vec![]);
+            }
+        }
+    }
+}
+
+/// Version 1, from before slots. Bones showed a texture themselves, and were drawn
+/// in order of a z-index that could be keyed.
+fn v1_to_v2(root: &mut Value) {
+    for armature in objects(&mut root["armatures"]) {
+        // every bone with a texture gets a slot for it
+        let mut drawn: Vec<(i64, f64, Value)> = vec![];
+        for bone in objects(&mut armature["bones"]) {
+            let fields = bone.as_object_mut().unwrap();
+            let mut take = |key: &str| fields.remove(key).unwrap_or(Value::Null);
+            let tex_idx = take("tex_idx").as_i64().unwrap_or(-1);
+            let region_idx = take("region_idx").as_i64().unwrap_or(-1);
+            let zindex = take("zindex").as_f64().unwrap_or(0.);
+            if tex_idx == -1 {
+                continue;
+            }
+            let id = bone["id"].as_i64().unwrap_or(0);
+            let slot = json!({
+                "name": bone["name"].as_str().unwrap_or(""),
+                "bone_id": id,
+                "tex_idx": tex_idx,
+                "region_idx": region_idx,
+            });
+            drawn.push((id, zindex, slot));
+        }
+
+        // ties were drawn in the order of the bones, which a stable sort keeps
+        drawn.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        for anim in objects(&mut armature["animations"]) {
+            zindex_to_draw_order(anim, &drawn);
+        }
+        armature["slots"] = drawn.into_iter().map(|(_, _, slot)| slot).collect();
+    }
+}
+
+/// Replace an animation's z-index keyframes with draw order keyframes wherever
+/// they changed which slots are in front. `drawn` is each slot's bone id and
+/// z-index, in the setup draw order.
+fn zindex_to_draw_order(anim: &mut Value, drawn: &[(i64, f64, Value)]) {
+    let Some(keyframes) = anim["keyframes"].as_array_mut() else {
+        return;
+    };
+
+    let is_zindex = |kf: &Value| kf["element"] == "Zindex";
+    let zindex_keys: Vec<Value> = keyframes.iter().filter(|kf| is_zindex(kf)).cloned().collect();
+    keyframes.retain(|kf| !is_zindex(kf));
+    if zindex_keys.is_empty() {
+        return;
+    }
+
+    // (frame, value, stepped) of a bone's keys, in order
+    let keys_of = |bone_id: i64| -> Vec<(i64, f64, bool)> {
+        let mut keys: Vec<(i64, f64, bool)> = zindex_keys
+            .iter()
+            .filter(|kf| kf["bone_id"].as_i64() == Some(bone_id))
+            .map(|kf| {
+                let frame = kf["frame"].as_i64().unwrap_or(0);
+                (frame, kf["value"].as_f64().unwrap_or(0.), kf["transition"] == "Stepped")
+            })
+            .collect();
+        keys.sort_by_key(|key| key.0);
+        keys
+    };
+    let slot_keys: Vec<Vec<(i64, f64, bool)>> = drawn.iter().map(|d| keys_of(d.0)).collect();
+
+    // Orders only change where z-indices cross, so easings are treated as linear.
+    // Before and after a bone's keys, their value is held.
+    let offset_at = |keys: &[(i64, f64, bool)], frame: i64| -> f64 {
+        let next = keys.iter().position(|key| key.0 > frame);
+        match next {
+            None => keys.last().map_or(0., |key| key.1),
+            Some(0) => keys[0].1,
+            Some(n) => {
+                let (prev, next) = (keys[n - 1], keys[n]);
+                if next.2 {
+                    return prev.1;
+                }
+                let progress = (frame - prev.0) as f64 / (next.0 - prev.0) as f64;
+                prev.1 + (next.1 - prev.1) * progress
+            }
+        }
+    };
+
+    let frames = zindex_keys.iter().map(|kf| kf["frame"].as_i64().unwrap_or(0));
+    let first = frames.clone().min().unwrap().min(0);
+    let last = frames.max().unwrap();
+
+    let mut prev_order: Vec<usize> = (0..drawn.len()).collect();
+    for frame in first..=last {
+        let zindex: Vec<f64> = drawn
+            .iter()
+            .zip(&slot_keys)
+            .map(|(d, keys)| d.1 + offset_at(keys, frame))
+            .collect();
+        let mut order: Vec<usize> = (0..drawn.len()).collect();
+        order.sort_by(|a, b| zindex[*a].total_cmp(&zindex[*b]));
+        if order == prev_order {
+            continue;
+        }
+
+        keyframes.push(json!({
+            "frame": frame,
+            "bone_id": -1,
+            "element": "DrawOrder",
+            "element_id": 7,
+            "value": 0.0,
+            "draw_order": order,
+        }));
+        prev_order = order;
+    }
+
+    keyframes.sort_by_key(|kf| kf["frame"].as_i64().unwrap_or(0));
+}
@@ -0,0 +1,308 @@
+//! Rendering armatures to images, without a window or any of the editor's overlays.
+
+use wgpu::{BindGroup, BindGroupLayout, Device, Queue};
+
+use crate::*;
+
+// readback rows have to be padded to this many bytes
+const ROW_ALIGNMENT: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+pub struct Offscreen {
+    device: Device,
+    queue: Queue,
+    bind_group_layout: BindGroupLayout,
+    scene: Scene,
+}
+
+impl Offscreen {
+    // same channel order as the exported images, so frames don't need swizzling
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    /// Render with an existing device (eg. the editor's).
+    pub fn new(device: &Device, queue: &Queue) -> Offscreen {
+        let bind_group_layout = renderer::create_bind_group_layout(device);
+        let scene = Scene::new(device, Self::FORMAT, &bind_group_layout);
+        Offscreen {
+            device: device.clone(),
+            queue: queue.clone(),
+            bind_group_layout,
+            scene,
+        }
+    }
+
+    /// Render without a window, on whatever adapter is available.
+    ///
+    /// If `software` is true, the fallback (CPU) adapter is used instead.
+    pub async fn headless(software: bool) -> Result<Offscreen, String> {
+        let instance = wgpu::Instance::new(&InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: software,
+            })
+            .await
+            .ok_or("No graphics adapter available.")?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Offscreen Device"),
+                    memory_hints: wgpu::MemoryHints::default(),
+                    required_features: wgpu::Features::default(),
+                    required_limits: wgpu::Limits::downlevel_defaults()
+                        .using_resolution(adapter.limits()),
+                },
+                None,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(Offscreen::new(&device, &queue))
+    }
+
+    /// Upload an armature's textures, for `render_frame`.
+    pub fn bind_groups(&self, textures: &[Texture]) -> Vec<BindGroup> {
+        textures
+            .iter()
+            .map(|tex| {
+                renderer::create_texture_bind_group(
+                    tex.pixels.to_vec(),
+                    tex.size,
+                    &self.queue,
+                    &self.device,
+                    &self.bind_group_layout,
+                )
+            })
+            .collect()
+    }
+
+    /// Largest width and height this device can render frames at.
+    pub fn max_size(&self) -> u32 {
+        self.device.limits().max_texture_dimension_2d
+    }
+
+    fn check_size(&self, settings: &export::ExportSettings) -> Result<(), String> {
+        let max = self.max_size();
+        if settings.width > max || settings.height > max {
+            return Err(format!(
+                "{}x{} is too big to render, frames can be at most {}x{} on this device.",
+                settings.width, settings.height, max, max
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render an animation frame by frame, from the settings' start to end frame (inclusive).
+    ///
+    /// Frames are sampled at the settings' fps, which may differ from the animation's.
+    /// Each one is only rendered once it's taken from the iterator, so they can be
+    /// exported as they come instead of all being kept around.
+    ///
+    /// Fails if the settings' resolution is bigger than the device can render.
+    pub fn render_animation<'a>(
+        &'a self,
+        armature: &'a Armature,
+        anim_idx: usize,
+        camera: &'a Camera,
+        settings: &'a export::ExportSettings,
+    ) -> Result<impl ExactSizeIterator<Item = image::RgbaImage> + 'a, String> {
+        self.check_size(settings)?;
+
+        // how many animation frames pass between each exported one, and how many are exported
+        let (step, count) = match armature.animations.get(anim_idx) {
+            Some(anim) => {
+                let last_frame = anim.keyframes.last().map(|kf| kf.frame).unwrap_or(0);
+                let end = if settings.end == -1 {
+                    last_frame
+                } else {
+                    settings.end
+                };
+                let step = anim.fps.max(1) as f32 / settings.fps.max(1) as f32;
+                let count = ((end - settings.start).max(0) as f32 / step).floor() as usize + 1;
+                (step, count)
+            }
+            None => (1., 0),
+        };
+
+        let bind_groups = self.bind_groups(&armature.textures);
+        Ok((0..count).map(move |i| {
+            let frame = settings.start as f32 + i as f32 * step;
+            let bones = runtime::pose(armature, anim_idx, frame);
+            let slots = runtime::slots_at(armature, anim_idx, frame);
+            let order = runtime::draw_order(armature, anim_idx, frame);
+            self.draw(
+                armature,
+                &bones,
+                &slots,
+                &order,
+                &bind_groups,
+                camera,
+                settings,
+            )
+        }))
+    }
+
+    /// Render posed bones (see `runtime::pose`) at the settings' resolution, with
+    /// `slots` (see `runtime::slots_at`) drawn in `order` (see `runtime::draw_order`).
+    ///
+    /// Fails if the settings' resolution is bigger than the device can render.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_frame(
+        &self,
+        armature: &Armature,
+        bones: &[Bone],
+        slots: &[Slot],
+        order: &[usize],
+        bind_groups: &[BindGroup],
+        camera: &Camera,
+        settings: &export::ExportSettings,
+    ) -> Result<image::RgbaImage, String> {
+        self.check_size(settings)?;
+        Ok(self.draw(armature, bones, slots, order, bind_groups, camera, settings))
+    }
+
+    // `render_frame`, with the size already checked
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &self,
+        armature: &Armature,
+        bones: &[Bone],
+        slots: &[Slot],
+        order: &[usize],
+        bind_groups: &[BindGroup],
+        camera: &Camera,
+        settings: &export::ExportSettings,
+    ) -> image::RgbaImage {
+        let width = settings.width.max(1);
+        let height = settings.height.max(1);
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let target = self.device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            label: Some("Offscreen Texture"),
+            view_formats: &[],
+        });
+        let depth = self.device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Offscreen Depth Texture"),
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = depth.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let background = if settings.transparent {
+            wgpu::Color::TRANSPARENT
+        } else {
+            wgpu::Color {
+                r: 0.8,
+                g: 0.8,
+                b: 0.8,
+                a: 1.0,
+            }
+        };
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Offscreen Encoder"),
+            });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Offscreen Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(background),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            renderer::draw_bones(
+                &mut render_pass,
+                &self.device,
+                &self.scene.pipelines,
+                armature,
+                bones,
+                slots,
+                order,
+                bind_groups,
+                camera,
+                width as f32 / height as f32,
+            );
+        }
+
+        let padded_row = (width * 4).div_ceil(ROW_ALIGNMENT) * ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: (padded_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &target,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in slice.get_mapped_range().chunks_exact(padded_row as usize) {
+            pixels.extend_from_slice(&row[..(width * 4) as usize]);
+        }
+        buffer.unmap();
+
+        // blending over a transparent background leaves colors multiplied by their alpha
+        if settings.transparent {
+            for pixel in pixels.chunks_exact_mut(4) {
+                let alpha = pixel[3] as f32 / 255.;
+                if alpha > 0. {
+                    for c in &mut pixel[..3] {
+                        *c = (*c as f32 / alpha).min(255.) as u8;
+                    }
+                }
+            }
+        }
+
+        image::RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+}
@@ -0,0 +1,79 @@
+//! Generating texture regions from sprite sheets.
+
+use crate::runtime::{Region, Vec2};
+
+/// Split a texture into equally sized cells, row by row.
+pub fn grid(tex_size: Vec2, columns: u32, rows: u32, name: &str) -> Vec<Region> {
+    let columns = columns.max(1);
+    let rows = rows.max(1);
+    let cell = Vec2::new(
+        (tex_size.x / columns as f32).floor(),
+        (tex_size.y / rows as f32).floor(),
+    );
+
+    let mut regions = vec![];
+    for y in 0..rows {
+        for x in 0..columns {
+            regions.push(Region {
+                name: format!("{}_{}", name, regions.len()),
+                offset: Vec2::new(x as f32 * cell.x, y as f32 * cell.y),
+                size: cell,
+            });
+        }
+    }
+    regions
+}
+
+/// Find the bounding box of every group of connected non-transparent pixels,
+/// sorted from top-left to bottom-right.
+///
+/// `pixels` is RGBA. Diagonal neighbours count as connected.
+pub fn alpha_islands(pixels: &[u8], tex_size: Vec2, name: &str) -> Vec<Region> {
+    let width = tex_size.x as usize;
+    let height = tex_size.y as usize;
+    let solid = |x: usize, y: usize| pixels.get((y * width + x) * 4 + 3).is_some_and(|a| *a > 0);
+
+    let mut visited = vec![false; width * height];
+    let mut boxes: Vec<(usize, usize, usize, usize)> = vec![];
+    let mut stack: Vec<(usize, usize)> = vec![];
+
+    for start_y in 0..height {
+        for start_x in 0..width {
+            if visited[start_y * width + start_x] || !solid(start_x, start_y) {
+                continue;
+            }
+
+            // flood fill this island, growing its box
+            let (mut left, mut top, mut right, mut bottom) = (start_x, start_y, start_x, start_y);
+            visited[start_y * width + start_x] = true;
+            stack.push((start_x, start_y));
+            while let Some((x, y)) = stack.pop() {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x);
+                bottom = bottom.max(y);
+
+                for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+                    for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                        if !visited[ny * width + nx] && solid(nx, ny) {
+                            visited[ny * width + nx] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+            }
+            boxes.push((left, top, right, bottom));
+        }
+    }
+
+    boxes.sort_by_key(|(left, top, _, _)| (*top, *left));
+    boxes
+        .iter()
+        .enumerate()
+        .map(|(i, (left, top, right, bottom))| Region {
+            name: format!("{}_{}", name, i),
+            offset: Vec2::new(*left as f32, *top as f32),
+            size: Vec2::new((right - left + 1) as f32, (bottom - top + 1) as f32),
+        })
+        .collect()
+}
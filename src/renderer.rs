@@ -1,11 +1,33 @@
 //! Core rendering logic, abstracted from the rest of WGPU.
 
 use crate::*;
-use wgpu::{BindGroup, BindGroupLayout, Device, Queue, RenderPass};
+use wgpu::{BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline};
 use winit::keyboard::KeyCode;
 
+// gizmo colors
+const GIZMO_X: Color = Color::new(0.9, 0.3, 0.3, 1.);
+const GIZMO_Y: Color = Color::new(0.4, 0.85, 0.35, 1.);
+const GIZMO_CENTER: Color = Color::new(0.4, 0.6, 0.95, 1.);
+const GIZMO_ACTIVE: Color = Color::new(1., 0.85, 0.25, 1.);
+
+/// A slot's geometry, ready to be drawn.
+#[derive(Clone, Default)]
+struct SlotMesh {
+    verts: Vec<Vertex>,
+    indices: Vec<u32>,
+    // world positions of mesh vertices, for hovering (rects use the bone's transform instead)
+    world: Vec<Vec2>,
+    /// Index of the slot's bone.
+    bone_idx: usize,
+}
+
 /// The `main` of this module.
-pub fn render(render_pass: &mut RenderPass, device: &Device, shared: &mut Shared) {
+pub fn render(
+    render_pass: &mut RenderPass,
+    device: &Device,
+    pipelines: &[RenderPipeline],
+    shared: &mut Shared,
+) {
     let mut bones = shared.armature.bones.clone();
     if shared.is_animating() {
         bones = shared.animate(shared.ui.anim.selected);
@@ -18,10 +40,10 @@ pub fn render(render_pass: &mut RenderPass, device: &Device, shared: &mut Shared
 
     // For rendering purposes, bones need to have many of their attributes manipulated.
     // This is easier to do with a separate copy of them.
-    let mut temp_bones: Vec<Bone> = vec![];
-    for b in &mut bones {
-        temp_bones.push(b.clone());
-    }
+    let temp_bones: Vec<Bone> = runtime::inherit(&runtime::place(&shared.armature, &bones));
+
+    // nothing but slots is tinted
+    set_color(render_pass, device, Color::WHITE);
 
     // drawing gridlines
     if shared.gridline_bindgroup != None {
@@ -80,96 +102,73 @@ pub fn render(render_pass: &mut RenderPass, device: &Device, shared: &mut Shared
         }
     }
 
-    // using while loop to prevent borrow issues
-    for i in 0..temp_bones.len() {
-        // get parent bone
-        let mut p = Bone::default();
-        p.scale.x = 1.;
-        p.scale.y = 1.;
-        for b in &temp_bones {
-            if b.id == temp_bones[i].parent_id {
-                p = b.clone();
-            }
-        }
-
-        temp_bones[i].rot += p.rot;
-        temp_bones[i].scale *= p.scale;
-
-        // adjust bone's position based on parent's scale
-        temp_bones[i].pos *= p.scale;
-
-        // rotate such that it will orbit the parent once it's position is inherited
-        temp_bones[i].pos = utils::rotate(&temp_bones[i].pos, p.rot);
-
-        // inherit position from parent
-        temp_bones[i].pos += p.pos;
-
-        if temp_bones[i].tex_idx == -1 {
+    // the project's other armatures, behind the one being edited
+    for (i, slot) in shared.armatures.iter().enumerate() {
+        if i == shared.armature_idx {
             continue;
         }
-
-        let tex = &shared.armature.textures[temp_bones[i].tex_idx as usize];
-
-        let temp_verts: [Vertex; 4] = [
-            Vertex {
-                pos: Vec2::new(tex.size.x * temp_bones[i].scale.x, 0.),
-                uv: Vec2::new(1., 0.),
-            },
-            Vertex {
-                pos: Vec2::new(0., tex.size.y * -temp_bones[i].scale.y),
-                uv: Vec2::new(0., 1.),
-            },
-            Vertex {
-                pos: Vec2::ZERO,
-                uv: Vec2::new(0., 0.),
-            },
-            Vertex {
-                pos: Vec2::new(
-                    tex.size.x * temp_bones[i].scale.x,
-                    tex.size.y * -temp_bones[i].scale.y,
-                ),
-                uv: Vec2::new(1., 1.),
-            },
-        ];
-
-        // generate the vertices to be used later
-        let final_verts = rect_verts(
-            temp_verts,
-            Some(&temp_bones[i]),
-            &shared.camera.pos,
-            shared.camera.zoom,
-            Some(&shared.armature.textures[temp_bones[i].tex_idx as usize]),
+        let bones = runtime::inherit(&runtime::place(&slot.armature, &slot.armature.bones));
+        let order: Vec<usize> = (0..slot.armature.slots.len()).collect();
+        draw_bones(
+            render_pass,
+            device,
+            pipelines,
+            &slot.armature,
+            &bones,
+            &slot.armature.slots,
+            &order,
+            &slot.bind_groups,
+            &shared.camera,
             shared.window.x / shared.window.y,
-            0.005,
         );
-
-        shared.armature.bones[i].vertices = final_verts;
     }
 
-    let mut hovered_bone = -1;
-    let mut hovered_bone_verts: Vec<Vertex> = vec![];
+    // meshes are skinned from where they were weighted, which is the armature without animations
+    let rest_bones = runtime::inherit(&runtime::place(&shared.armature, &shared.armature.bones));
+    let slots = shared.slots();
+    let meshes = slot_meshes(
+        &temp_bones,
+        &rest_bones,
+        &slots,
+        &shared.armature.textures,
+        &shared.camera.pos,
+        shared.camera.zoom,
+        shared.window.x / shared.window.y,
+    );
+    let order = shared.draw_order();
+
+    let mut hovered_slot = usize::MAX;
     let can_hover = !shared.input.on_ui
         && shared.ui.polar_id == ""
         && !shared.ui.image_modal
-        && !shared.editing_bone;
+        && !shared.editing_bone
+        && !shared.editing_mesh();
 
-    // sort bones by z-index for drawing
-    temp_bones.sort_by(|a, b| a.zindex.total_cmp(&b.zindex));
+    // the gizmo is grabbed before the slots underneath it
+    let mouse_world = shared.screen_to_world(shared.input.mouse);
+    let gizmo = gizmo::Gizmo::new(shared);
+    let mut gizmo_hovered = None;
+    if can_hover {
+        gizmo_hovered = gizmo
+            .as_ref()
+            .and_then(|g| g.handle_at(&shared.edit_mode, mouse_world));
+    }
 
-    // Check for the bone being hovered on.
-    // This has to be in reverse (for now) since bones are rendered in ascending order of the array,
+    // Check for the slot being hovered on.
+    // This is done in reverse since slots are rendered in ascending order of the array,
     // so it visually makes sense to click the one that shows in front.
-    if can_hover {
-        for i in 0..temp_bones.len() {
-            if shared.find_bone(temp_bones[i].id).unwrap().vertices.len() == 0 {
+    if can_hover && gizmo_hovered.is_none() {
+        for &i in order.iter().rev() {
+            if meshes[i].verts.is_empty() {
                 continue;
             }
+            let bone = &temp_bones[meshes[i].bone_idx];
 
             // Check if this bone is a child of the selected bone.
             // If so, ignore.
             if shared.selected_bone() != None {
                 let mut ignore = false;
-                let mut parent = shared.find_bone(temp_bones[i].parent_id);
+                let mut parent = shared.find_bone(bone.parent_id);
                 while parent != None {
                     if parent.unwrap().id == shared.selected_bone().unwrap().id {
                         ignore = true;
@@ -182,23 +181,31 @@ pub fn render(render_pass: &mut RenderPass, device: &Device, shared: &mut Shared
                 }
             }
 
-            let is_in_box: bool;
-            (hovered_bone_verts, is_in_box) = utils::in_bounding_box(
-                &shared.input.mouse,
-                &shared.find_bone(temp_bones[i].id).unwrap().vertices,
-                &shared.window,
-            );
-            if is_in_box {
-                // highlight bone for selection if not already selected
-                hovered_bone = i as i32;
+            let slot = &slots[i];
+            let tex = &shared.armature.textures[slot.tex_idx as usize];
+            let tex_size = tex.region_size(slot.region_idx);
+            let hovered = if meshes[i].world.is_empty() {
+                utils::in_bone(mouse_world, bone, tex_size, HARD_SCALE)
+            } else {
+                utils::in_mesh(mouse_world, &meshes[i].world, &meshes[i].indices)
+            };
+            if hovered {
+                // highlight slot for selection if its bone isn't already selected
+                hovered_slot = i;
 
                 // select if left clicked
-                if selected_id != temp_bones[i].id && shared.input.mouse_left == 0 {
-                    for (bi, bone) in shared.armature.bones.iter().enumerate() {
-                        if bone.id == temp_bones[i].id {
-                            shared.select_bone(bi);
-                            break;
-                        }
+                if shared.input.mouse_left == 0 {
+                    let idx = meshes[i].bone_idx;
+                    if shared.input.modifiers.command {
+                        shared.toggle_selection(idx);
+                    } else if shared.input.modifiers.shift || shared.is_selected(bone.id) {
+                        // clicking on a selected bone keeps the others, to drag them along
+                        shared.add_to_selection(idx);
+                    } else {
+                        shared.select_bone(idx);
+                    }
+                    if selected_id != bone.id && shared.selected_bone_idx == idx {
+                        shared.selected_slot_idx = i;
                     }
                 }
                 break;
@@ -206,65 +213,116 @@ pub fn render(render_pass: &mut RenderPass, device: &Device, shared: &mut Shared
         }
     }
 
-    // finally, draw the bones
-    for (i, b) in temp_bones.iter().enumerate() {
-        if b.tex_idx == -1 || shared.find_bone(temp_bones[i].id).unwrap().vertices.len() == 0 {
-            if b.id == selected_id {
-                draw_point(shared, render_pass, device, b);
-            }
+    // finally, draw the slots
+    let mut blend = BlendMode::Normal;
+    for &i in &order {
+        if meshes[i].verts.is_empty() {
             continue;
         }
+        let slot = &slots[i];
 
         // draw the hovering highlight section
-        if selected_id != b.id
-            && hovered_bone as usize == i
-            && can_hover
-            && shared.selected_bone_idx != i
+        if hovered_slot == i && !shared.is_selected(temp_bones[meshes[i].bone_idx].id) && can_hover
         {
+            render_pass.set_pipeline(&pipelines[BlendMode::Normal as usize]);
+            blend = BlendMode::Normal;
             render_pass.set_bind_group(0, &shared.highlight_bindgroup, &[]);
-            render_pass.set_vertex_buffer(0, vertex_buffer(&hovered_bone_verts, device).slice(..));
-            render_pass.set_index_buffer(
-                index_buffer([0, 1, 2, 3, 0, 1].to_vec(), &device).slice(..),
-                wgpu::IndexFormat::Uint32,
-            );
-            render_pass.draw_indexed(0..6, 0, 0..1);
+            draw_mesh(render_pass, device, &meshes[i]);
         }
 
-        // draw bone
-        render_pass.set_bind_group(0, &shared.bind_groups[b.tex_idx as usize], &[]);
-        render_pass.set_vertex_buffer(
-            0,
-            vertex_buffer(
-                &shared.find_bone(temp_bones[i].id).unwrap().vertices,
-                device,
-            )
-            .slice(..),
-        );
-        render_pass.set_index_buffer(
-            index_buffer(RECT_VERT_INDICES.to_vec(), &device).slice(..),
-            wgpu::IndexFormat::Uint32,
-        );
-        render_pass.draw_indexed(0..6, 0, 0..1);
-
-        if b.id == selected_id {
-            draw_point(shared, render_pass, device, b);
+        if slot.blend != blend {
+            render_pass.set_pipeline(&pipelines[slot.blend as usize]);
+            blend = slot.blend;
         }
+        render_pass.set_bind_group(0, &shared.bind_groups[slot.tex_idx as usize], &[]);
+        set_color(render_pass, device, slot.color);
+        draw_mesh(render_pass, device, &meshes[i]);
+        set_color(render_pass, device, Color::WHITE);
+    }
+    render_pass.set_pipeline(&pipelines[BlendMode::Normal as usize]);
+
+    // the selected bones' origins are always shown, in front of everything
+    for b in temp_bones.iter().filter(|b| shared.is_selected(b.id)) {
+        draw_point(shared, render_pass, device, b.world.translation());
+    }
+
+    if let Some(gizmo) = &gizmo {
+        draw_gizmo(shared, render_pass, device, gizmo, shared.gizmo_handle.or(gizmo_hovered));
+    }
+
+    if shared.input.right_clicked && can_hover {
+        shared.cursor = mouse_world;
+    }
+    if shared.pivot == shared::Pivot::Cursor {
+        let size = Vec2::new(0.2, 0.2);
+        render_pass.set_bind_group(0, &shared.highlight_bindgroup, &[]);
+        draw_rect(shared, render_pass, device, shared.cursor - size, shared.cursor + size);
+        draw_point(shared, render_pass, device, shared.cursor);
     }
 
     // if mouse_left is lower than this, it's considered a click
     let click_threshold = 10;
+    let modifiers = shared.input.modifiers;
+
+    if shared.input.mouse_left == 0 {
+        if let Some(handle) = gizmo_hovered {
+            shared.gizmo_handle = Some(handle);
+            shared.history.seal();
+            shared.start_transform(mouse_world, handle.axis());
+            shared.editing_bone = true;
+        }
+    }
+
+    // Box select by dragging from an empty spot while holding shift (to add bones whose
+    // origins are inside) or ctrl (to remove them).
+    if shared.input.mouse_left == 0
+        && hovered_slot == usize::MAX
+        && gizmo_hovered.is_none()
+        && can_hover
+        && (modifiers.shift || modifiers.command)
+    {
+        shared.box_select = Some(mouse_world);
+    }
+    if let Some(start) = shared.box_select {
+        let min = Vec2::new(start.x.min(mouse_world.x), start.y.min(mouse_world.y));
+        let max = Vec2::new(start.x.max(mouse_world.x), start.y.max(mouse_world.y));
+        if shared.input.mouse_left != -1 {
+            render_pass.set_bind_group(0, &shared.highlight_bindgroup, &[]);
+            draw_rect(shared, render_pass, device, min, max);
+            return;
+        }
+
+        shared.box_select = None;
+        for (idx, bone) in temp_bones.iter().enumerate() {
+            let origin = bone.world.translation();
+            if origin.x < min.x || origin.x > max.x || origin.y < min.y || origin.y > max.y {
+                continue;
+            }
+            if modifiers.command {
+                shared.unselect_bone(idx);
+            } else {
+                shared.add_to_selection(idx);
+            }
+        }
+        return;
+    }
 
     if shared.input.mouse_left == -1 {
-        // deselect bone if clicking outside
-        if hovered_bone == -1
-            && shared.input.mouse_left_prev <= click_threshold
+        let clicked = shared.input.mouse_left_prev <= click_threshold
             && shared.input.mouse_left_prev != -1
             && can_hover
-        {
-            shared.selected_bone_idx = usize::MAX;
+            && !modifiers.shift
+            && !modifiers.command;
+        if clicked && hovered_slot == usize::MAX {
+            // deselect bone if clicking outside
+            shared.unselect_everything();
+        } else if clicked && shared.selected_bones.len() > 1 {
+            // clicking (rather than dragging) a selected bone selects only it
+            shared.select_bone(meshes[hovered_slot].bone_idx);
         }
 
         shared.editing_bone = false;
+        shared.gizmo_handle = None;
         return;
     }
 
@@ -286,90 +344,360 @@ pub fn render(render_pass: &mut RenderPass, device: &Device, shared: &mut Shared
         return;
     }
 
-    // editing bone
+    // editing bones
     if shared.input.on_ui || shared.ui.polar_id != "" {
         shared.editing_bone = false;
-    } else if shared.selected_bone_idx != usize::MAX && shared.input.mouse_left > click_threshold {
+    } else if shared.selected_bone_idx != usize::MAX
+        && (shared.input.mouse_left > click_threshold || shared.gizmo_handle.is_some())
+        && !shared.editing_mesh()
+    {
+        // dragging anywhere but the gizmo transforms freely
         if !shared.editing_bone {
-            if shared.is_animating() {
-                shared.undo_actions.push(Action {
-                    action: ActionEnum::Animation,
-                    action_type: ActionType::Edited,
-                    id: shared.ui.anim.selected as i32,
-                    animation: shared.armature.animations[shared.ui.anim.selected].clone(),
-                    ..Default::default()
-                });
-            } else {
-                shared.save_edited_bone();
-            }
+            shared.history.seal();
+            shared.start_transform(mouse_world, None);
         }
 
         shared.editing_bone = true;
         shared.cursor_icon = egui::CursorIcon::Crosshair;
-        match shared.edit_mode {
-            shared::EditMode::Move => {
-                let mut pos = bones[shared.selected_bone_idx].pos;
-                if shared.is_animating() {
-                    pos = bones[shared.selected_bone_idx].pos
-                        - shared.armature.bones[shared.selected_bone_idx].pos;
-                }
-                let pos = shared.move_with_mouse(&pos, true);
-                shared.edit_bone(&AnimElement::PositionX, pos.x, false);
-                shared.edit_bone(&AnimElement::PositionY, pos.y, false);
-            }
-            shared::EditMode::Rotate => {
-                let rot = (shared.input.mouse.x / shared.window.x) * std::f32::consts::PI * 2.;
-                shared.edit_bone(&AnimElement::Rotation, rot, false);
-            }
-            shared::EditMode::Scale => {
-                let scale = (shared.input.mouse / shared.window) * 2.;
-                shared.edit_bone(&AnimElement::ScaleX, scale.x, false);
-                shared.edit_bone(&AnimElement::ScaleY, scale.y, false);
-            }
-        };
+        // vertices are edited in mesh_editor
+        shared.transform_selection(mouse_world, shared.input.modifiers.command);
     }
 }
 
-fn draw_point(shared: &Shared, render_pass: &mut RenderPass, device: &Device, bone: &Bone) {
-    if shared.point_bindgroup != None {
-        render_pass.set_bind_group(0, &shared.point_bindgroup, &[]);
-        let point_size = 0.1;
-        let temp_point_verts: [Vertex; 4] = [
+/// Build the geometry of every slot with a texture, in screen space. Slots without
+/// one (or without a bone) get an empty mesh.
+///
+/// `bones` and `rest` must be in world space (see `runtime::inherit`).
+fn slot_meshes(
+    bones: &[Bone],
+    rest: &[Bone],
+    slots: &[Slot],
+    textures: &[Texture],
+    camera: &Vec2,
+    zoom: f32,
+    aspect_ratio: f32,
+) -> Vec<SlotMesh> {
+    let mut meshes: Vec<SlotMesh> = vec![SlotMesh::default(); slots.len()];
+
+    for (s, slot) in slots.iter().enumerate() {
+        let Some(i) = bones.iter().position(|b| b.id == slot.bone_id) else {
+            continue;
+        };
+        if slot.tex_idx == -1 {
+            continue;
+        }
+
+        let tex = &textures[slot.tex_idx as usize];
+        let uvs = tex.region_uvs(slot.region_idx);
+
+        if bones[i].is_mesh && !bones[i].vertices.is_empty() {
+            let world = runtime::skin(rest, bones, i);
+            let mut verts = bones[i].vertices.clone();
+            for (v, pos) in verts.iter_mut().zip(&world) {
+                v.pos = *pos;
+            }
+            meshes[s] = SlotMesh {
+                verts: rect_verts(
+                    verts,
+                    uvs,
+                    None,
+                    camera,
+                    zoom,
+                    aspect_ratio,
+                    1.,
+                ),
+                indices: bones[i].indices.clone(),
+                world,
+                bone_idx: i,
+            };
+            continue;
+        }
+
+        // texture corners relative to the bone, which sits on the pivot
+        let size = tex.region_size(slot.region_idx);
+        let pivot = bones[i].pivot;
+        let left = -size.x * pivot.x;
+        let right = size.x * (1. - pivot.x);
+        let top = size.y * pivot.y;
+        let bottom = -size.y * (1. - pivot.y);
+
+        let temp_verts: Vec<Vertex> = vec![
             Vertex {
-                pos: Vec2::new(-point_size, point_size) + bone.pos,
+                pos: Vec2::new(right, top),
                 uv: Vec2::new(1., 0.),
             },
             Vertex {
-                pos: Vec2::new(point_size, point_size) + bone.pos,
+                pos: Vec2::new(left, bottom),
                 uv: Vec2::new(0., 1.),
             },
             Vertex {
-                pos: Vec2::new(-point_size, -point_size) + bone.pos,
+                pos: Vec2::new(left, top),
                 uv: Vec2::new(0., 0.),
             },
             Vertex {
-                pos: Vec2::new(point_size, -point_size) + bone.pos,
+                pos: Vec2::new(right, bottom),
                 uv: Vec2::new(1., 1.),
             },
         ];
 
-        let point_verts = rect_verts(
-            temp_point_verts,
-            None,
-            &shared.camera.pos,
-            shared.camera.zoom,
-            None,
-            shared.window.x / shared.window.y,
-            1.,
+        // generate the vertices to be used later
+        let final_verts = rect_verts(
+            temp_verts,
+            uvs,
+            Some(&bones[i].world),
+            camera,
+            zoom,
+            aspect_ratio,
+            HARD_SCALE,
         );
 
-        render_pass.set_vertex_buffer(0, vertex_buffer(&point_verts.to_vec(), device).slice(..));
-        render_pass.set_index_buffer(
-            index_buffer(RECT_VERT_INDICES.to_vec(), &device).slice(..),
-            wgpu::IndexFormat::Uint32,
-        );
-        render_pass.draw_indexed(0..6, 0, 0..1);
+        meshes[s] = SlotMesh {
+            verts: final_verts,
+            indices: RECT_VERT_INDICES.to_vec(),
+            world: vec![],
+            bone_idx: i,
+        };
     }
+
+    meshes
+}
+
+/// Draw an armature's slots in `order` (see `runtime::draw_order`), without any of
+/// the editor's overlays.
+///
+/// `bones` are the armature's bones posed in world space (see `runtime::pose`), and
+/// `slots` show what's attached to them at the same frame (see `runtime::slots_at`).
+/// `pipelines` are indexed by `BlendMode`. The normal one is left set afterwards.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_bones(
+    render_pass: &mut RenderPass,
+    device: &Device,
+    pipelines: &[RenderPipeline],
+    armature: &Armature,
+    bones: &[Bone],
+    slots: &[Slot],
+    order: &[usize],
+    bind_groups: &[BindGroup],
+    camera: &Camera,
+    aspect_ratio: f32,
+) {
+    let rest = runtime::inherit(&runtime::place(armature, &armature.bones));
+    let meshes = slot_meshes(
+        bones,
+        &rest,
+        slots,
+        &armature.textures,
+        &camera.pos,
+        camera.zoom,
+        aspect_ratio,
+    );
+
+    for &i in order {
+        if meshes[i].verts.is_empty() {
+            continue;
+        }
+        let slot = &slots[i];
+        render_pass.set_pipeline(&pipelines[slot.blend as usize]);
+        render_pass.set_bind_group(0, &bind_groups[slot.tex_idx as usize], &[]);
+        set_color(render_pass, device, slot.color);
+        draw_mesh(render_pass, device, &meshes[i]);
+    }
+
+    render_pass.set_pipeline(&pipelines[BlendMode::Normal as usize]);
+    set_color(render_pass, device, Color::WHITE);
+}
+
+fn draw_mesh(render_pass: &mut RenderPass, device: &Device, mesh: &SlotMesh) {
+    render_pass.set_vertex_buffer(0, vertex_buffer(&mesh.verts, device).slice(..));
+    render_pass.set_index_buffer(
+        index_buffer(mesh.indices.clone(), device).slice(..),
+        wgpu::IndexFormat::Uint32,
+    );
+    render_pass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
+}
+
+/// Tint everything drawn after this (see `Slot::color`). The pipelines always read
+/// a color, so one must be set before drawing anything.
+pub fn set_color(render_pass: &mut RenderPass, device: &Device, color: Color) {
+    let buffer = wgpu::util::DeviceExt::create_buffer_init(
+        device,
+        &wgpu::util::BufferInitDescriptor {
+            label: Some("color Buffer"),
+            contents: bytemuck::bytes_of(&color),
+            usage: wgpu::BufferUsages::VERTEX,
+        },
+    );
+    render_pass.set_vertex_buffer(1, buffer.slice(..));
+}
+
+fn draw_point(shared: &Shared, render_pass: &mut RenderPass, device: &Device, origin: Vec2) {
+    if shared.point_bindgroup != None {
+        render_pass.set_bind_group(0, &shared.point_bindgroup, &[]);
+        let point_size = Vec2::new(0.1, 0.1);
+        draw_rect(shared, render_pass, device, origin - point_size, origin + point_size);
+    }
+}
+
+/// Draw a rectangle between 2 corners in world space, with the bind group that's set.
+fn draw_rect(
+    shared: &Shared,
+    render_pass: &mut RenderPass,
+    device: &Device,
+    min: Vec2,
+    max: Vec2,
+) {
+    let corners = [
+        max,
+        Vec2::new(min.x, max.y),
+        min,
+        Vec2::new(max.x, min.y),
+    ];
+    draw_quad(shared, render_pass, device, corners);
+}
+
+/// Draw a line between 2 points in world space, with the bind group that's set.
+fn draw_line(
+    shared: &Shared,
+    render_pass: &mut RenderPass,
+    device: &Device,
+    from: Vec2,
+    to: Vec2,
+    width: f32,
+) {
+    let dir = to - from;
+    let normal = Vec2::new(-dir.y, dir.x) / dir.length() * (width / 2.);
+    let corners = [from + normal, to + normal, to - normal, from - normal];
+    draw_quad(shared, render_pass, device, corners);
+}
+
+/// Draw a quad from its corners in world space (going around it), with the bind group
+/// that's set. The first corner gets the top-right of the texture.
+fn draw_quad(shared: &Shared, render_pass: &mut RenderPass, device: &Device, corners: [Vec2; 4]) {
+    // in the order of RECT_VERT_INDICES
+    let temp_verts: Vec<Vertex> = vec![
+        Vertex {
+            pos: corners[0],
+            uv: Vec2::new(1., 0.),
+        },
+        Vertex {
+            pos: corners[2],
+            uv: Vec2::new(0., 1.),
+        },
+        Vertex {
+            pos: corners[1],
+            uv: Vec2::new(0., 0.),
+        },
+        Vertex {
+            pos: corners[3],
+            uv: Vec2::new(1., 1.),
+        },
+    ];
+
+    let verts = rect_verts(
+        temp_verts,
+        (Vec2::ZERO, Vec2::new(1., 1.)),
+        None,
+        &shared.camera.pos,
+        shared.camera.zoom,
+        shared.window.x / shared.window.y,
+        1.,
+    );
+
+    render_pass.set_vertex_buffer(0, vertex_buffer(&verts, device).slice(..));
+    render_pass.set_index_buffer(
+        index_buffer(RECT_VERT_INDICES.to_vec(), device).slice(..),
+        wgpu::IndexFormat::Uint32,
+    );
+    render_pass.draw_indexed(0..6, 0, 0..1);
+}
+
+/// Draw the selection's gizmo for the edit mode, with the `active` handle (hovered or
+/// dragged) highlighted.
+fn draw_gizmo(
+    shared: &Shared,
+    render_pass: &mut RenderPass,
+    device: &Device,
+    gizmo: &gizmo::Gizmo,
+    active: Option<gizmo::Handle>,
+) {
+    use gizmo::{Axis, Handle};
+
+    render_pass.set_bind_group(0, &shared.gizmo_bindgroup, &[]);
+    let tint = |handle: Handle, color: Color| {
+        if active == Some(handle) {
+            GIZMO_ACTIVE
+        } else {
+            color
+        }
+    };
+    let (origin, size) = (gizmo.origin, gizmo.size);
+    let width = size * 0.03;
+
+    // a square along the gizmo's axes
+    let square = |center: Vec2, half: f32| {
+        let (x, y) = (gizmo.x_axis * half, gizmo.y_axis * half);
+        [center + x + y, center - x + y, center - x - y, center + x - y]
+    };
+
+    if shared.edit_mode == shared::EditMode::Rotate {
+        set_color(render_pass, device, tint(Handle::Ring, GIZMO_CENTER));
+        let segments = 48;
+        let point = |i: i32| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            origin + Vec2::new(angle.cos(), angle.sin()) * size
+        };
+        for i in 0..segments {
+            draw_line(shared, render_pass, device, point(i), point(i + 1), width);
+        }
+        set_color(render_pass, device, Color::WHITE);
+        return;
+    }
+
+    for (axis, color) in [(Axis::X, GIZMO_X), (Axis::Y, GIZMO_Y)] {
+        let dir = gizmo.axis(axis);
+        let end = origin + dir * size;
+        set_color(render_pass, device, tint(Handle::Axis(axis), color));
+        if shared.edit_mode == shared::EditMode::Move {
+            // arrows for moving
+            let base = origin + dir * (size * 0.8);
+            let side = Vec2::new(-dir.y, dir.x) * (size * 0.08);
+            draw_line(shared, render_pass, device, origin, base, width);
+            draw_quad(shared, render_pass, device, [end, base + side, base, base - side]);
+        } else {
+            // boxes for scaling
+            draw_line(shared, render_pass, device, origin, end, width);
+            draw_quad(shared, render_pass, device, square(end, size * 0.06));
+        }
+    }
+
+    set_color(render_pass, device, tint(Handle::Center, GIZMO_CENTER));
+    draw_quad(shared, render_pass, device, square(origin, size * 0.06));
+    set_color(render_pass, device, Color::WHITE);
+}
+
+/// Layout of the bind groups made by `create_texture_bind_group`.
+pub fn create_bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("texture_bind_group_layout"),
+    })
 }
 
 /// Get bind group of a texture.
@@ -469,34 +797,28 @@ fn vertex_buffer(vertices: &Vec<Vertex>, device: &Device) -> wgpu::Buffer {
 
 /// Generate and return the vertices of a bone
 ///
-/// Accounts for texture size and aspect ratio
+/// Accounts for the bone's world transform and aspect ratio.
+/// UVs are mapped from 0..1 to the texture region between `uvs` (top-left and bottom-right).
 fn rect_verts(
-    mut verts: [Vertex; 4],
-    bone: Option<&Bone>,
+    mut verts: Vec<Vertex>,
+    uvs: (Vec2, Vec2),
+    transform: Option<&Transform>,
     camera: &Vec2,
     zoom: f32,
-    tex: Option<&Texture>,
     aspect_ratio: f32,
     hard_scale: f32,
 ) -> Vec<Vertex> {
     for v in &mut verts {
-        v.pos *= hard_scale;
-
-        if let Some(bone) = bone {
-            let pivot_offset = tex.unwrap().size * bone.pivot * hard_scale;
-            v.pos.x -= pivot_offset.x;
-            v.pos.y += pivot_offset.y;
-
-            let rev_scale = Vec2::new(1. - bone.scale.x, 1. - bone.scale.y);
-            let scale_offset = tex.unwrap().size * rev_scale * bone.pivot * hard_scale;
-            v.pos.x += scale_offset.x;
-            v.pos.y -= scale_offset.y;
+        v.uv = Vec2::new(
+            uvs.0.x + v.uv.x * (uvs.1.x - uvs.0.x),
+            uvs.0.y + v.uv.y * (uvs.1.y - uvs.0.y),
+        );
 
-            // rotate verts
-            v.pos = utils::rotate(&v.pos, bone.rot);
+        v.pos *= hard_scale;
 
-            // move verts with bone
-            v.pos += bone.pos;
+        // bring verts to world space
+        if let Some(transform) = transform {
+            v.pos = transform.apply(v.pos);
         }
 
         // offset bone with camera
@@ -509,7 +831,7 @@ fn rect_verts(
         v.pos.x /= aspect_ratio;
     }
 
-    verts.to_vec()
+    verts
 }
 
 pub fn draw_horizontal_line(
@@ -0,0 +1,1519 @@
+//! Headless armature data and animation evaluation.
+//!
+//! Nothing here depends on the editor (egui, winit or wgpu), so it can be used
+//! on its own with `--no-default-features` to sample poses in other programs.
+
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Deref, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
+    sync::Arc,
+};
+
+use tween::Tweener;
+
+use crate::ik;
+
+pub use crate::transform::Transform;
+
+/// World units per texture pixel.
+pub const HARD_SCALE: f32 = 0.005;
+
+#[repr(C)]
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    Default,
+    Copy,
+    Clone,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl tween::TweenValue for Vec2 {
+    fn scale(self, scale: f32) -> Self {
+        self * scale
+    }
+}
+
+impl Vec2 {
+    pub const ZERO: Self = Self::new(0., 0.);
+
+    pub const fn new(x: f32, y: f32) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    pub fn equal_to(&self, other: Vec2) -> bool {
+        self.x != other.x || self.y != other.y
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn dot(&self, other: Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// For f32 values that need to be passed as Vec2.
+    pub fn single(value: f32) -> Vec2 {
+        Vec2::new(value, 0.)
+    }
+}
+
+impl MulAssign for Vec2 {
+    fn mul_assign(&mut self, other: Vec2) {
+        self.x *= other.x;
+        self.y *= other.y;
+    }
+}
+
+impl MulAssign<f32> for Vec2 {
+    fn mul_assign(&mut self, other: f32) {
+        self.x *= other;
+        self.y *= other;
+    }
+}
+
+impl DivAssign for Vec2 {
+    fn div_assign(&mut self, other: Vec2) {
+        self.x /= other.x;
+        self.y /= other.y;
+    }
+}
+
+impl DivAssign<f32> for Vec2 {
+    fn div_assign(&mut self, other: f32) {
+        self.x /= other;
+        self.y /= other;
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl Div for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn div(self, rhs: Self) -> Self {
+        Self {
+            x: self.x / rhs.x,
+            y: self.y / rhs.y,
+        }
+    }
+}
+
+impl Div<f32> for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn div(self, rhs: f32) -> Self {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs,
+        }
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: f32) -> Self {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+
+impl Mul for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: Vec2) -> Self {
+        Self {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+        }
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl Sub<f32> for Vec2 {
+    type Output = Self;
+    #[inline(always)]
+    fn sub(self, rhs: f32) -> Self {
+        Self {
+            x: self.x - rhs,
+            y: self.y - rhs,
+        }
+    }
+}
+
+impl PartialEq for Vec2 {
+    fn eq(&self, other: &Vec2) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimal_places = 3;
+
+        let mut p = 0;
+        let mut dp = 1.;
+        while p < decimal_places {
+            dp *= 10.;
+            p += 1;
+        }
+
+        write!(
+            f,
+            "{}, {}",
+            (self.x * dp).trunc() / dp,
+            (self.y * dp).trunc() / dp
+        )
+    }
+}
+
+/// Mesh vertices are in texture pixels, relative to their bone.
+#[repr(C)]
+#[derive(
+    PartialEq, serde::Serialize, serde::Deserialize, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable,
+)]
+pub struct Vertex {
+    pub pos: Vec2,
+    pub uv: Vec2,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default)]
+pub struct Bone {
+    #[serde(default)]
+    pub id: i32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_neg_one")]
+    pub parent_id: i32,
+
+    #[serde(default)]
+    pub vertices: Vec<Vertex>,
+    /// Triangles of a mesh, as indices into `vertices`.
+    #[serde(default)]
+    pub indices: Vec<u32>,
+    /// Influences of other bones on each vertex, parallel to `vertices`.
+    /// Vertices without any only follow this bone.
+    #[serde(default)]
+    pub weights: Vec<Vec<BoneWeight>>,
+
+    #[serde(default)]
+    pub is_mesh: bool,
+
+    /// Local-to-world transform. Only filled in by `inherit`.
+    #[serde(skip)]
+    pub world: Transform,
+
+    #[serde(default)]
+    pub rot: f32,
+    #[serde(default)]
+    pub scale: Vec2,
+    #[serde(default)]
+    pub pos: Vec2,
+    /// Where the bone sits on its slots' textures. (0, 0) is the top-left corner,
+    /// and (1, 1) the bottom-right.
+    #[serde(default)]
+    pub pivot: Vec2,
+}
+
+impl Bone {
+    /// Transform relative to the parent, without inheritance.
+    pub fn local_transform(&self) -> Transform {
+        Transform::from_trs(self.pos, self.rot, self.scale)
+    }
+}
+
+/// How much a bone pulls a mesh vertex along with it.
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub struct BoneWeight {
+    pub bone_id: i32,
+    pub weight: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq)]
+pub struct Armature {
+    #[serde(default)]
+    pub name: String,
+    /// Where the armature is placed in the scene. Its root bones are relative to this.
+    #[serde(default)]
+    pub pos: Vec2,
+
+    #[serde(default)]
+    pub bones: Vec<Bone>,
+    /// What's drawn on the bones, from back to front. This is the draw order unless
+    /// an animation changes it (see `draw_order`).
+    #[serde(default)]
+    pub slots: Vec<Slot>,
+    #[serde(default)]
+    pub animations: Vec<Animation>,
+
+    #[serde(default)]
+    pub textures: Vec<Texture>,
+
+    #[serde(default)]
+    pub ik_constraints: Vec<IkConstraint>,
+}
+
+/// Something drawn on a bone, following its transform, pivot and mesh.
+///
+/// A bone can have any number of slots, such as an outline and a fill drawn at
+/// different depths.
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Slot {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_neg_one")]
+    pub bone_id: i32,
+    /// Texture shown by this slot, or -1 for none.
+    #[serde(default = "default_neg_one")]
+    pub tex_idx: i32,
+    /// Region of the texture to show, or -1 for all of it
+    #[serde(default = "default_neg_one")]
+    pub region_idx: i32,
+    /// Multiplied with the texture.
+    #[serde(default)]
+    pub color: Color,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+impl Default for Slot {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            bone_id: -1,
+            tex_idx: -1,
+            region_idx: -1,
+            color: Color::default(),
+            blend: BlendMode::default(),
+        }
+    }
+}
+
+/// RGBA color, each from 0 to 1.
+#[repr(C)]
+#[derive(
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Self = Self::new(1., 1., 1., 1.);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+/// How a slot is mixed with what's drawn behind it.
+#[derive(PartialEq, Eq, serde::Serialize, serde::Deserialize, Clone, Copy, Default, Debug)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Additive,
+    Multiply,
+    Screen,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 4] = [
+        BlendMode::Normal,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+}
+
+impl fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Rotates a chain of bones so that the end of it reaches a target bone.
+///
+/// Chains of 2 are solved analytically, and any other length with CCD.
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct IkConstraint {
+    /// End of the chain, which reaches for the target.
+    #[serde(default = "default_neg_one")]
+    pub bone_id: i32,
+    #[serde(default = "default_neg_one")]
+    pub target_id: i32,
+    /// How many parents of `bone_id` are rotated.
+    #[serde(default = "default_two")]
+    pub chain_length: i32,
+    /// Which side a chain of 2 bends towards.
+    #[serde(default = "default_true")]
+    pub bend_positive: bool,
+    /// 0 leaves the chain as animated, 1 fully reaches the target.
+    /// Can be keyed with `AnimElement::IkMix` on `bone_id`.
+    #[serde(default = "default_one")]
+    pub mix: f32,
+}
+
+impl Default for IkConstraint {
+    fn default() -> Self {
+        Self {
+            bone_id: -1,
+            target_id: -1,
+            chain_length: 2,
+            bend_positive: true,
+            mix: 1.,
+        }
+    }
+}
+
+// used for the json
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct Root {
+    /// Version of the format (see `migrate`). Always current once loaded.
+    #[serde(default)]
+    pub version: u32,
+    /// Size of the first texture page
+    pub texture_size: Vec2,
+    pub armatures: Vec<Armature>,
+    #[serde(default)]
+    pub atlas: crate::atlas::AtlasConfig,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq)]
+pub struct Texture {
+    #[serde(default)]
+    pub offset: Vec2,
+    #[serde(default)]
+    pub size: Vec2,
+    #[serde(default)]
+    pub name: String,
+    /// Index of the texture page (image) this is in
+    #[serde(default)]
+    pub page: usize,
+    /// Named parts of the texture, so that one image (eg. a sprite sheet) can be
+    /// shared by many bones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<Region>,
+    #[serde(skip)]
+    pub pixels: Pixels,
+}
+
+/// RGBA pixels of a texture, shared by all of its copies (such as the undo history's).
+///
+/// Pixels are never edited in place, so they're only equal to the ones they were cloned
+/// from, which is quick to tell. To compare what they show, compare them as slices.
+#[derive(Clone, Default, Debug)]
+pub struct Pixels(Arc<[u8]>);
+
+impl PartialEq for Pixels {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || (self.is_empty() && other.is_empty())
+    }
+}
+
+impl Deref for Pixels {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Pixels {
+    fn from(pixels: Vec<u8>) -> Self {
+        Pixels(pixels.into())
+    }
+}
+
+impl Texture {
+    /// Size of a region in pixels, or of the whole texture if there's no such region.
+    pub fn region_size(&self, region_idx: i32) -> Vec2 {
+        match self.regions.get(region_idx as usize) {
+            Some(region) if region_idx >= 0 => region.size,
+            _ => self.size,
+        }
+    }
+
+    /// Top-left and bottom-right UVs of a region, or of the whole texture if there's
+    /// no such region.
+    pub fn region_uvs(&self, region_idx: i32) -> (Vec2, Vec2) {
+        let region = match self.regions.get(region_idx as usize) {
+            Some(region) if region_idx >= 0 => region,
+            _ => return (Vec2::ZERO, Vec2::new(1., 1.)),
+        };
+        (
+            Vec2::new(region.offset.x / self.size.x, region.offset.y / self.size.y),
+            Vec2::new(
+                (region.offset.x + region.size.x) / self.size.x,
+                (region.offset.y + region.size.y) / self.size.y,
+            ),
+        )
+    }
+}
+
+/// Sub-rectangle of a texture, in pixels from its top-left.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct Region {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub offset: Vec2,
+    #[serde(default)]
+    pub size: Vec2,
+}
+
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct Animation {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub fps: i32,
+    #[serde(default)]
+    pub keyframes: Vec<Keyframe>,
+}
+
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct Keyframe {
+    #[serde(default)]
+    pub frame: i32,
+    //#[serde(default)]
+    //pub bones: Vec<AnimBone>,
+    #[serde(default)]
+    pub bone_id: i32,
+    #[serde(default)]
+    pub element: AnimElement,
+
+    // Only used in runtimes. Represents the element's index in the enum.
+    #[serde(default)]
+    pub element_id: i32,
+
+    #[serde(default)]
+    pub value: f32,
+
+    #[serde(default)]
+    pub transition: Transition,
+
+    /// Only used by `Transition::Bezier`.
+    #[serde(default)]
+    pub handles: BezierHandles,
+
+    /// Vertex offsets of a mesh, for `AnimElement::Deform`. `value` is unused.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deform: Vec<Vec2>,
+
+    /// Indices of the armature's slots from back to front, for `AnimElement::DrawOrder`.
+    /// `value` is unused.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub draw_order: Vec<usize>,
+
+    /// What a slot shows from this frame on, for `AnimElement::Attachment`. `value` is unused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<Attachment>,
+
+    #[serde(skip)]
+    pub label_top: f32,
+}
+
+/// A texture (or region of it) swapped into a slot by a keyframe.
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Attachment {
+    /// Index into `Armature::slots`.
+    #[serde(default)]
+    pub slot_idx: usize,
+    /// Texture to show, or -1 for none.
+    #[serde(default = "default_neg_one")]
+    pub tex_idx: i32,
+    /// Region of the texture to show, or -1 for all of it
+    #[serde(default = "default_neg_one")]
+    pub region_idx: i32,
+}
+
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub enum Transition {
+    #[default]
+    Linear,
+    /// Holds the previous value until the next keyframe is reached.
+    Stepped,
+    SineIn,
+    SineOut,
+    SineInOut,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// Custom curve, shaped by the keyframe's `handles`.
+    Bezier,
+}
+
+impl Transition {
+    /// Every transition, in the order they're listed in the editor.
+    #[rustfmt::skip]
+    pub const ALL: [Transition; 33] = [
+        Transition::Linear, Transition::Stepped,
+        Transition::SineIn, Transition::SineOut, Transition::SineInOut,
+        Transition::QuadIn, Transition::QuadOut, Transition::QuadInOut,
+        Transition::CubicIn, Transition::CubicOut, Transition::CubicInOut,
+        Transition::QuartIn, Transition::QuartOut, Transition::QuartInOut,
+        Transition::QuintIn, Transition::QuintOut, Transition::QuintInOut,
+        Transition::ExpoIn, Transition::ExpoOut, Transition::ExpoInOut,
+        Transition::CircIn, Transition::CircOut, Transition::CircInOut,
+        Transition::BackIn, Transition::BackOut, Transition::BackInOut,
+        Transition::ElasticIn, Transition::ElasticOut, Transition::ElasticInOut,
+        Transition::BounceIn, Transition::BounceOut, Transition::BounceInOut,
+        Transition::Bezier,
+    ];
+
+    /// A bezier curve close to this transition, for formats that only have those.
+    ///
+    /// `None` for ones that a single curve can't follow (stepped, elastic and bounce),
+    /// and for `Bezier` itself, whose curve is in the keyframe's handles.
+    #[rustfmt::skip]
+    pub fn approximate_bezier(&self) -> Option<BezierHandles> {
+        let (x1, y1, x2, y2) = match self {
+            Transition::Linear       => (0.,   0.,    1.,   1.),
+            Transition::SineIn       => (0.12, 0.,    0.39, 0.),
+            Transition::SineOut      => (0.61, 1.,    0.88, 1.),
+            Transition::SineInOut    => (0.37, 0.,    0.63, 1.),
+            Transition::QuadIn       => (0.11, 0.,    0.5,  0.),
+            Transition::QuadOut      => (0.5,  1.,    0.89, 1.),
+            Transition::QuadInOut    => (0.45, 0.,    0.55, 1.),
+            Transition::CubicIn      => (0.32, 0.,    0.67, 0.),
+            Transition::CubicOut     => (0.33, 1.,    0.68, 1.),
+            Transition::CubicInOut   => (0.65, 0.,    0.35, 1.),
+            Transition::QuartIn      => (0.5,  0.,    0.75, 0.),
+            Transition::QuartOut     => (0.25, 1.,    0.5,  1.),
+            Transition::QuartInOut   => (0.76, 0.,    0.24, 1.),
+            Transition::QuintIn      => (0.64, 0.,    0.78, 0.),
+            Transition::QuintOut     => (0.22, 1.,    0.36, 1.),
+            Transition::QuintInOut   => (0.83, 0.,    0.17, 1.),
+            Transition::ExpoIn       => (0.7,  0.,    0.84, 0.),
+            Transition::ExpoOut      => (0.16, 1.,    0.3,  1.),
+            Transition::ExpoInOut    => (0.87, 0.,    0.13, 1.),
+            Transition::CircIn       => (0.55, 0.,    1.,   0.45),
+            Transition::CircOut      => (0.,   0.55,  0.45, 1.),
+            Transition::CircInOut    => (0.85, 0.,    0.15, 1.),
+            Transition::BackIn       => (0.36, 0.,    0.66, -0.56),
+            Transition::BackOut      => (0.34, 1.56,  0.64, 1.),
+            Transition::BackInOut    => (0.68, -0.6,  0.32, 1.6),
+            _ => return None,
+        };
+        Some(BezierHandles {
+            start: Vec2::new(x1, y1),
+            end: Vec2::new(x2, y2),
+        })
+    }
+}
+
+/// Control points of a cubic bezier curve, going from (0, 0) to (1, 1).
+///
+/// X is time and Y is progress between the two keyframes. X is kept within 0 to 1,
+/// but Y can go past it to overshoot.
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub struct BezierHandles {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Default for BezierHandles {
+    // ease in and out
+    fn default() -> Self {
+        Self {
+            start: Vec2::new(0.42, 0.),
+            end: Vec2::new(0.58, 1.),
+        }
+    }
+}
+
+impl BezierHandles {
+    /// Get the progress (Y) of the curve at a point in time (X).
+    pub fn progress(&self, time: f32) -> f32 {
+        let time = time.clamp(0., 1.);
+        let x1 = self.start.x.clamp(0., 1.);
+        let x2 = self.end.x.clamp(0., 1.);
+
+        // one axis of the curve at `t`
+        let bezier = |a: f32, b: f32, t: f32| {
+            let u = 1. - t;
+            3. * u * u * t * a + 3. * u * t * t * b + t * t * t
+        };
+
+        // X only goes forward, so bisect until it lands on `time`
+        let mut lo = 0.;
+        let mut hi = 1.;
+        let mut t = time;
+        for _ in 0..32 {
+            let x = bezier(x1, x2, t);
+            if (x - time).abs() < 1e-5 {
+                break;
+            }
+            if x < time {
+                lo = t;
+            } else {
+                hi = t;
+            }
+            t = (lo + hi) / 2.;
+        }
+
+        bezier(self.start.y, self.end.y, t)
+    }
+}
+
+// this allows getting the element name as a string
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(
+    Eq, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize, Clone, Default, Debug,
+)]
+pub enum AnimElement {
+    #[default]
+    PositionX,
+    PositionY,
+    Rotation,
+    ScaleX,
+    ScaleY,
+    PivotX,
+    PivotY,
+    /// Order the armature's slots are drawn in, from `Keyframe::draw_order`.
+    /// Keyed on the whole armature, so `bone_id` is -1.
+    DrawOrder,
+    /// Mix of the IK constraint ending at this bone.
+    IkMix,
+    /// Vertex offsets of a mesh bone. Stored in `Keyframe::deform` rather than `value`.
+    Deform,
+    /// Texture shown by one of the bone's slots, from `Keyframe::attachment`.
+    Attachment,
+}
+
+impl AnimElement {
+    pub fn default_of(element: &AnimElement) -> f32 {
+        match *element {
+            AnimElement::ScaleX | AnimElement::ScaleY | AnimElement::IkMix => 1.,
+            _ => 0.,
+        }
+    }
+
+    /// Whether keyframes of this element are a number in `value`, rather than
+    /// something only they hold (like a deform or draw order).
+    pub fn has_value(&self) -> bool {
+        !matches!(
+            self,
+            AnimElement::Deform | AnimElement::DrawOrder | AnimElement::Attachment
+        )
+    }
+}
+
+// this allows getting the element name as a string
+impl fmt::Display for AnimElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+fn default_neg_one() -> i32 {
+    -1
+}
+
+fn default_two() -> i32 {
+    2
+}
+
+fn default_one() -> f32 {
+    1.
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Find anything that would break an armature when it's loaded, such as references to
+/// bones and textures that don't exist. Returns a description of each problem.
+pub fn validate(armature: &Armature) -> Vec<String> {
+    let mut problems: Vec<String> = vec![];
+    let bone_exists = |id: i32| armature.bones.iter().any(|b| b.id == id);
+
+    for (i, bone) in armature.bones.iter().enumerate() {
+        if armature.bones[..i].iter().any(|b| b.id == bone.id) {
+            problems.push(format!("Bone id {} is used more than once", bone.id));
+        }
+        if bone.parent_id == bone.id {
+            problems.push(format!("Bone '{}' is its own parent", bone.name));
+        } else if bone.parent_id != -1 && !bone_exists(bone.parent_id) {
+            problems.push(format!(
+                "Bone '{}' has parent id {}, which doesn't exist",
+                bone.name, bone.parent_id
+            ));
+        }
+
+        if bone.is_mesh {
+            let verts = bone.vertices.len() as u32;
+            if bone.indices.len() % 3 != 0 || bone.indices.iter().any(|idx| *idx >= verts) {
+                problems.push(format!(
+                    "Mesh of bone '{}' has invalid triangles",
+                    bone.name
+                ));
+            }
+        }
+    }
+
+    for slot in &armature.slots {
+        if !bone_exists(slot.bone_id) {
+            problems.push(format!(
+                "Slot '{}' is on bone id {}, which doesn't exist",
+                slot.name, slot.bone_id
+            ));
+        }
+
+        let textures = armature.textures.len() as i32;
+        if slot.tex_idx < -1 || slot.tex_idx >= textures {
+            problems.push(format!(
+                "Slot '{}' has texture index {}, but there are {} textures",
+                slot.name, slot.tex_idx, textures
+            ));
+        } else if slot.tex_idx != -1 {
+            let regions = armature.textures[slot.tex_idx as usize].regions.len() as i32;
+            if slot.region_idx < -1 || slot.region_idx >= regions {
+                problems.push(format!(
+                    "Slot '{}' has region index {}, but its texture has {} regions",
+                    slot.name, slot.region_idx, regions
+                ));
+            }
+        }
+    }
+
+    for anim in &armature.animations {
+        for kf in &anim.keyframes {
+            if kf.element == AnimElement::DrawOrder {
+                if !is_draw_order(&kf.draw_order, armature.slots.len()) {
+                    problems.push(format!(
+                        "Animation '{}' has a draw order on frame {} that doesn't have every slot once",
+                        anim.name, kf.frame
+                    ));
+                }
+            } else if !bone_exists(kf.bone_id) {
+                problems.push(format!(
+                    "Animation '{}' has a keyframe on frame {} for bone id {}, which doesn't exist",
+                    anim.name, kf.frame, kf.bone_id
+                ));
+            }
+
+            if kf.element != AnimElement::Attachment {
+                continue;
+            }
+            let Some(attachment) = &kf.attachment else {
+                problems.push(format!(
+                    "Animation '{}' has an attachment on frame {} without a slot",
+                    anim.name, kf.frame
+                ));
+                continue;
+            };
+            if attachment.slot_idx >= armature.slots.len() {
+                problems.push(format!(
+                    "Animation '{}' has an attachment on frame {} for slot {}, which doesn't exist",
+                    anim.name, kf.frame, attachment.slot_idx
+                ));
+            }
+            let texture = armature.textures.get(attachment.tex_idx as usize);
+            let regions = texture.map_or(0, |tex| tex.regions.len() as i32);
+            if attachment.tex_idx != -1 && texture.is_none() {
+                problems.push(format!(
+                    "Animation '{}' has an attachment on frame {} of texture {}, which doesn't exist",
+                    anim.name, kf.frame, attachment.tex_idx
+                ));
+            } else if attachment.region_idx < -1 || attachment.region_idx >= regions {
+                problems.push(format!(
+                    "Animation '{}' has an attachment on frame {} of region {}, which doesn't exist",
+                    anim.name, kf.frame, attachment.region_idx
+                ));
+            }
+        }
+    }
+
+    for ik in &armature.ik_constraints {
+        if !bone_exists(ik.bone_id) {
+            problems.push(format!(
+                "IK constraint is on bone id {}, which doesn't exist",
+                ik.bone_id
+            ));
+        }
+        if ik.target_id != -1 && !bone_exists(ik.target_id) {
+            problems.push(format!(
+                "IK constraint targets bone id {}, which doesn't exist",
+                ik.target_id
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Convert seconds into a (fractional) frame of this animation.
+pub fn time_to_frame(anim: &Animation, seconds: f32) -> f32 {
+    seconds * anim.fps as f32
+}
+
+/// Interpolate all bones of an armature at a (fractional) frame of an animation.
+///
+/// IK constraints are solved after the keyframes are applied.
+/// Returned bones are still in local space. See `inherit` to bring them to world space.
+/// What the slots show is animated separately, see `slots_at`.
+pub fn animate(armature: &Armature, anim_idx: usize, frame: f32) -> Vec<Bone> {
+    let mut bones = armature.bones.clone();
+
+    // constraints still apply if this animation doesn't exist or has no keyframes
+    let anim = armature.animations.get(anim_idx);
+    if let Some(anim) = anim.filter(|a| !a.keyframes.is_empty()) {
+        for b in &mut bones {
+            macro_rules! interpolate {
+                ($element:expr, $default:expr) => {
+                    sample(anim, b.id, $element, $default, frame)
+                };
+            }
+
+            // interpolate!
+            #[rustfmt::skip]
+            {
+                b.pos.x   += interpolate!(AnimElement::PositionX, 0.);
+                b.pos.y   += interpolate!(AnimElement::PositionY, 0.);
+                b.rot     += interpolate!(AnimElement::Rotation,  0.);
+                b.scale.x *= interpolate!(AnimElement::ScaleX,    1.);
+                b.scale.y *= interpolate!(AnimElement::ScaleY,    1.);
+                b.pivot.x += interpolate!(AnimElement::PivotX,    0.);
+                b.pivot.y += interpolate!(AnimElement::PivotY,    0.);
+            };
+
+            if let Some(deform) = sample_deform(anim, b.id, frame) {
+                for (vert, offset) in b.vertices.iter_mut().zip(deform) {
+                    vert.pos += offset;
+                }
+            }
+        }
+    }
+
+    ik::solve(&armature.ik_constraints, anim, frame, &mut bones);
+
+    bones
+}
+
+/// Indices of the armature's slots from back to front, at a frame of an animation.
+///
+/// Draw orders don't interpolate, so this is the last one keyed at or before `frame`,
+/// or the order of `Armature::slots` if there's none (or it's missing slots).
+pub fn draw_order(armature: &Armature, anim_idx: usize, frame: f32) -> Vec<usize> {
+    let keyed = armature.animations.get(anim_idx).and_then(|anim| {
+        anim.keyframes
+            .iter()
+            .take_while(|kf| kf.frame as f32 <= frame)
+            .filter(|kf| kf.element == AnimElement::DrawOrder)
+            .last()
+    });
+    match keyed {
+        Some(kf) if is_draw_order(&kf.draw_order, armature.slots.len()) => kf.draw_order.clone(),
+        _ => (0..armature.slots.len()).collect(),
+    }
+}
+
+/// The armature's slots at a frame of an animation, showing what was attached to them.
+///
+/// Attachments step rather than interpolate, so each slot shows the last one keyed at
+/// or before `frame`, or its own texture if there's none.
+pub fn slots_at(armature: &Armature, anim_idx: usize, frame: f32) -> Vec<Slot> {
+    let mut slots = armature.slots.clone();
+    let Some(anim) = armature.animations.get(anim_idx) else {
+        return slots;
+    };
+    let keyed = anim
+        .keyframes
+        .iter()
+        .take_while(|kf| kf.frame as f32 <= frame)
+        .filter(|kf| kf.element == AnimElement::Attachment)
+        .filter_map(|kf| kf.attachment.as_ref());
+    for attachment in keyed {
+        if let Some(slot) = slots.get_mut(attachment.slot_idx) {
+            slot.tex_idx = attachment.tex_idx;
+            slot.region_idx = attachment.region_idx;
+        }
+    }
+    slots
+}
+
+/// Whether `order` has every slot index below `slots` exactly once.
+fn is_draw_order(order: &[usize], slots: usize) -> bool {
+    let mut seen = vec![false; slots];
+    order.len() == slots
+        && order
+            .iter()
+            .all(|idx| *idx < slots && !std::mem::replace(&mut seen[*idx], true))
+}
+
+/// Interpolate a single element of a bone at a (fractional) frame.
+///
+/// `default` is used if the bone has no keyframes for this element.
+pub fn sample(
+    anim: &Animation,
+    bone_id: i32,
+    element: AnimElement,
+    default: f32,
+    frame: f32,
+) -> f32 {
+    let (prev, next, total_frames, current_frame, transition, handles) =
+        find_connecting_frames(anim, bone_id, element, default, frame);
+    tween(
+        &transition,
+        &handles,
+        prev,
+        next,
+        total_frames,
+        current_frame,
+    )
+}
+
+/// Interpolate the vertex offsets of a mesh bone at a (fractional) frame.
+///
+/// Returns `None` if the bone has no deform keyframes.
+pub fn sample_deform(anim: &Animation, bone_id: i32, frame: f32) -> Option<Vec<Vec2>> {
+    let mut prev: Option<&Keyframe> = None;
+    let mut next: Option<&Keyframe> = None;
+    for kf in &anim.keyframes {
+        if kf.bone_id != bone_id || kf.element != AnimElement::Deform {
+            continue;
+        }
+        if kf.frame as f32 <= frame {
+            prev = Some(kf);
+        } else if next.is_none() {
+            next = Some(kf);
+        }
+    }
+
+    let (prev, next) = match (prev, next) {
+        (Some(prev), Some(next)) => (prev, next),
+        (Some(kf), None) | (None, Some(kf)) => return Some(kf.deform.clone()),
+        (None, None) => return None,
+    };
+
+    let progress = tween(
+        &next.transition,
+        &next.handles,
+        0.,
+        1.,
+        (next.frame - prev.frame) as f32,
+        frame - prev.frame as f32,
+    );
+
+    // meshes might have gained vertices since being keyed
+    let len = prev.deform.len().max(next.deform.len());
+    let offsets = (0..len).map(|i| {
+        let from = prev.deform.get(i).copied().unwrap_or_default();
+        let to = next.deform.get(i).copied().unwrap_or_default();
+        from + (to - from) * progress
+    });
+    Some(offsets.collect())
+}
+
+/// Interpolate between two values with a transition.
+#[rustfmt::skip]
+pub fn tween(
+    transition: &Transition,
+    handles: &BezierHandles,
+    prev: f32,
+    next: f32,
+    total_frames: f32,
+    current_frame: f32,
+) -> f32 {
+    macro_rules! tween {
+        ($func:ident) => {
+            Tweener::$func(prev, next, total_frames).move_to(current_frame)
+        };
+    }
+
+    match transition {
+        Transition::Linear       => tween!(linear),
+        Transition::Stepped      => if current_frame < total_frames { prev } else { next },
+        Transition::SineIn       => tween!(sine_in),
+        Transition::SineOut      => tween!(sine_out),
+        Transition::SineInOut    => tween!(sine_in_out),
+        Transition::QuadIn       => tween!(quad_in),
+        Transition::QuadOut      => tween!(quad_out),
+        Transition::QuadInOut    => tween!(quad_in_out),
+        Transition::CubicIn      => tween!(cubic_in),
+        Transition::CubicOut     => tween!(cubic_out),
+        Transition::CubicInOut   => tween!(cubic_in_out),
+        Transition::QuartIn      => tween!(quart_in),
+        Transition::QuartOut     => tween!(quart_out),
+        Transition::QuartInOut   => tween!(quart_in_out),
+        Transition::QuintIn      => tween!(quint_in),
+        Transition::QuintOut     => tween!(quint_out),
+        Transition::QuintInOut   => tween!(quint_in_out),
+        Transition::ExpoIn       => tween!(expo_in),
+        Transition::ExpoOut      => tween!(expo_out),
+        Transition::ExpoInOut    => tween!(expo_in_out),
+        Transition::CircIn       => tween!(circ_in),
+        Transition::CircOut      => tween!(circ_out),
+        Transition::CircInOut    => tween!(circ_in_out),
+        Transition::BackIn       => tween!(back_in),
+        Transition::BackOut      => tween!(back_out),
+        Transition::BackInOut    => tween!(back_in_out),
+        Transition::ElasticIn    => tween!(elastic_in),
+        Transition::ElasticOut   => tween!(elastic_out),
+        Transition::ElasticInOut => tween!(elastic_in_out),
+        Transition::BounceIn     => tween!(bounce_in),
+        Transition::BounceOut    => tween!(bounce_out),
+        Transition::BounceInOut  => tween!(bounce_in_out),
+        Transition::Bezier       => prev + (next - prev) * handles.progress(current_frame / total_frames),
+    }
+}
+
+/// Find the keyframes surrounding `frame` for this bone's element.
+///
+/// Returns the previous and next values, the frames between them, how far into
+/// them `frame` is, and the transition (and its handles) to use.
+pub fn find_connecting_frames(
+    anim: &Animation,
+    bone_id: i32,
+    element: AnimElement,
+    default: f32,
+    frame: f32,
+) -> (f32, f32, f32, f32, Transition, BezierHandles) {
+    let mut prev: Option<f32> = None;
+    let mut next: Option<f32> = None;
+    let mut start_frame = 0;
+    let mut end_frame = 0;
+    let mut transition: Transition = Transition::Linear;
+    let mut handles = BezierHandles::default();
+
+    // get most previous frame with this element
+    for kf in &anim.keyframes {
+        if kf.frame as f32 > frame {
+            break;
+        }
+
+        if kf.bone_id != bone_id || kf.element != element {
+            continue;
+        }
+
+        prev = Some(kf.value);
+        start_frame = kf.frame;
+    }
+
+    // get first next frame with this element
+    for kf in anim.keyframes.iter().rev() {
+        if (kf.frame as f32) < frame {
+            break;
+        }
+
+        if kf.bone_id != bone_id || kf.element != element {
+            continue;
+        }
+
+        next = Some(kf.value);
+        end_frame = kf.frame;
+        transition = kf.transition.clone();
+        handles = kf.handles;
+    }
+
+    // ensure prev and next are pointing somewhere
+    if prev.is_none() {
+        if next.is_some() {
+            prev = next
+        } else {
+            prev = Some(default)
+        }
+    }
+    if next.is_none() {
+        if prev.is_some() {
+            next = prev;
+        } else {
+            next = Some(default);
+        }
+    }
+
+    let mut total_frames = end_frame - start_frame;
+    // Tweener doesn't accept 0 duration
+    if total_frames == 0 {
+        total_frames = 1;
+    }
+
+    let current_frame = frame - start_frame as f32;
+
+    (
+        prev.unwrap(),
+        next.unwrap(),
+        total_frames as f32,
+        current_frame,
+        transition,
+        handles,
+    )
+}
+
+/// Inherit parent transforms, bringing bones from local to world space.
+///
+/// Each bone's `world` is set, and its position, rotation and scale are
+/// replaced with the ones decomposed from it.
+pub fn inherit(bones: &[Bone]) -> Vec<Bone> {
+    let mut world_bones = bones.to_vec();
+
+    for (b, world) in world_bones.iter_mut().zip(world_transforms(bones)) {
+        b.world = world;
+        b.pos = world.translation();
+        b.rot = world.rotation();
+        b.scale = world.scale();
+    }
+
+    world_bones
+}
+
+/// Get the local-to-world transform of every bone, in the same order as `bones`.
+///
+/// Parent chains can be of any depth, and parents don't need to come before
+/// their children.
+pub fn world_transforms(bones: &[Bone]) -> Vec<Transform> {
+    let mut world: Vec<Option<Transform>> = vec![None; bones.len()];
+    for i in 0..bones.len() {
+        solve_world(bones, i, &mut world, 0);
+    }
+    world.into_iter().map(|t| t.unwrap()).collect()
+}
+
+fn solve_world(
+    bones: &[Bone],
+    idx: usize,
+    world: &mut [Option<Transform>],
+    depth: usize,
+) -> Transform {
+    if let Some(transform) = world[idx] {
+        return transform;
+    }
+
+    let local = bones[idx].local_transform();
+    let parent_idx = bones.iter().position(|b| b.id == bones[idx].parent_id);
+
+    // a chain can't be longer than the bones themselves, so going past that means it's cyclic
+    let transform = match parent_idx {
+        Some(p) if p != idx && depth < bones.len() => {
+            solve_world(bones, p, world, depth + 1) * local
+        }
+        _ => local,
+    };
+
+    world[idx] = Some(transform);
+    transform
+}
+
+/// Sample an animation and return its bones in world space.
+///
+/// Shorthand for `animate`, `place` and `inherit`.
+pub fn pose(armature: &Armature, anim_idx: usize, frame: f32) -> Vec<Bone> {
+    inherit(&place(armature, &animate(armature, anim_idx, frame)))
+}
+
+/// Move an armature's bones to where it's placed in the scene (see `Armature::pos`),
+/// by offsetting its root bones.
+pub fn place(armature: &Armature, bones: &[Bone]) -> Vec<Bone> {
+    let mut placed = bones.to_vec();
+    for bone in &mut placed {
+        if bone.parent_id == -1 {
+            bone.pos += armature.pos;
+        }
+    }
+    placed
+}
+
+/// Get the world positions of a mesh bone's vertices, skinned by their weights.
+///
+/// `rest` and `posed` are both in world space (see `inherit`), with `rest` being the
+/// armature as it was weighted. Weights are relative to each other, and don't need
+/// to add up to 1.
+pub fn skin(rest: &[Bone], posed: &[Bone], idx: usize) -> Vec<Vec2> {
+    posed[idx]
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(i, vert)| vertex_transform(rest, posed, idx, i).apply(vert.pos * HARD_SCALE))
+        .collect()
+}
+
+/// Get the transform that brings a mesh vertex to world space, blended from its weights.
+///
+/// See `skin`.
+pub fn vertex_transform(rest: &[Bone], posed: &[Bone], idx: usize, vert: usize) -> Transform {
+    let weights = posed[idx].weights.get(vert).map_or(&[][..], |w| &w[..]);
+
+    let mut total = 0.;
+    let mut blended = Transform::IDENTITY * 0.;
+    for weight in weights {
+        let influence = rest.iter().zip(posed).find(|(b, _)| b.id == weight.bone_id);
+        let Some((rest_bone, posed_bone)) = influence else {
+            continue;
+        };
+        let Some(unbind) = rest_bone.world.inverse() else {
+            continue;
+        };
+
+        // move along with the influence, from where it was when weighted
+        blended = blended + posed_bone.world * unbind * rest[idx].world * weight.weight;
+        total += weight.weight;
+    }
+
+    if total <= 0. {
+        return posed[idx].world;
+    }
+    blended * (1. / total)
+}
+
+/// Rotate a point via rotation matrix.
+pub fn rotate(point: &Vec2, rot: f32) -> Vec2 {
+    Vec2 {
+        x: point.x * rot.cos() - point.y * rot.sin(),
+        y: point.x * rot.sin() + point.y * rot.cos(),
+    }
+}
+
+/// Keyframe helpers for the tests of the runtime and of the formats converted from it.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    /// Frame, bone, element, value and transition of a key.
+    pub(crate) type Key = (i32, i32, AnimElement, f32, Transition);
+
+    /// Every key of an animation, in a stable order.
+    pub(crate) fn keys_of(anim: &Animation) -> Vec<Key> {
+        let mut keys: Vec<Key> = anim
+            .keyframes
+            .iter()
+            .map(|kf| {
+                let (element, transition) = (kf.element.clone(), kf.transition.clone());
+                (kf.frame, kf.bone_id, element, kf.value, transition)
+            })
+            .collect();
+        keys.sort_by(|a, b| (a.0, a.1, &a.2).cmp(&(b.0, b.1, &b.2)));
+        keys
+    }
+
+    /// Check an animation's keys (see `keys_of`), with some leeway for their values.
+    pub(crate) fn assert_keys(anim: &Animation, expected: &[Key]) {
+        let keys = keys_of(anim);
+        assert_eq!(keys.len(), expected.len(), "{:?}", keys);
+        for (key, expected) in keys.iter().zip(expected) {
+            let close = (key.3 - expected.3).abs() < 0.0001;
+            assert!(key.0 == expected.0 && key.1 == expected.1 && key.2 == expected.2);
+            assert!(
+                close && key.4 == expected.4,
+                "{:?} isn't {:?}",
+                key,
+                expected
+            );
+        }
+    }
+
+    pub(crate) fn assert_near(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 0.0001, "{} isn't {}", a, b);
+    }
+
+    pub(crate) fn key(bone_id: i32, element: AnimElement, frame: i32, value: f32) -> Keyframe {
+        Keyframe {
+            frame,
+            bone_id,
+            element,
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{self, key};
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn bone(id: i32, parent_id: i32, pos: Vec2, rot: f32, scale: Vec2) -> Bone {
+        Bone {
+            id,
+            parent_id,
+            pos,
+            rot,
+            scale,
+            ..Default::default()
+        }
+    }
+
+    // one bone moved from x = 2 at frame 10 to 6 at frame 20, at 10 fps
+    fn moving() -> Armature {
+        let keyframes = vec![
+            key(0, AnimElement::PositionX, 10, 2.),
+            key(0, AnimElement::PositionX, 20, 6.),
+        ];
+        Armature {
+            bones: vec![bone(0, -1, Vec2::new(1., 0.), 0., Vec2::new(1., 1.))],
+            animations: vec![Animation {
+                name: "Move".to_string(),
+                fps: 10,
+                keyframes,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 0.0001, "{} isn't {}", a, b);
+    }
+
+    #[test]
+    fn seconds_are_frames_at_the_animations_fps() {
+        let armature = moving();
+        assert_eq!(time_to_frame(&armature.animations[0], 1.5), 15.);
+    }
+
+    #[test]
+    fn keys_interpolate_between_each_other() {
+        let anim = &moving().animations[0];
+        let (prev, next, total, current, transition, _) =
+            find_connecting_frames(anim, 0, AnimElement::PositionX, 0., 12.);
+        assert_eq!((prev, next, total, current), (2., 6., 10., 2.));
+        assert_eq!(transition, Transition::Linear);
+
+        assert_near(sample(anim, 0, AnimElement::PositionX, 0., 15.), 4.);
+        assert_near(sample(anim, 0, AnimElement::PositionX, 0., 20.), 6.);
+
+        // offsets the bone rather than replacing it
+        assert_near(animate(&moving(), 0, 12.5)[0].pos.x, 4.);
+
+        let mut stepped = moving();
+        stepped.animations[0].keyframes[1].transition = Transition::Stepped;
+        assert_near(animate(&stepped, 0, 19.)[0].pos.x, 3.);
+    }
+
+    #[test]
+    fn keys_hold_before_the_first_and_after_the_last() {
+        let armature = moving();
+        let anim = &armature.animations[0];
+        assert_near(sample(anim, 0, AnimElement::PositionX, 0., 0.), 2.);
+        assert_near(sample(anim, 0, AnimElement::PositionX, 0., 35.), 6.);
+
+        // elements without keys are left as they are
+        assert_eq!(sample(anim, 0, AnimElement::ScaleX, 1., 15.), 1.);
+        assert_eq!(sample(anim, 1, AnimElement::PositionX, 0., 15.), 0.);
+
+        // and so is everything when there's no such animation
+        assert!(animate(&armature, 3, 15.) == armature.bones);
+    }
+
+    #[test]
+    fn poses_inherit_rotated_and_scaled_parents() {
+        // a quarter turn keyed on the root, squashing the chain under it
+        let armature = Armature {
+            pos: Vec2::new(0.5, 0.),
+            bones: vec![
+                bone(2, 1, Vec2::new(1., 0.), 0., Vec2::new(1., 1.)),
+                bone(0, -1, Vec2::new(1., 0.), 0., Vec2::new(2., 1.)),
+                bone(1, 0, Vec2::new(1., 1.), FRAC_PI_2, Vec2::new(1., 1.)),
+            ],
+            animations: vec![Animation {
+                fps: 10,
+                keyframes: vec![key(0, AnimElement::Rotation, 0, FRAC_PI_2)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let posed = pose(&armature, 0, 0.);
+        let expected = [
+            // grandchild, listed before its parents
+            (Vec2::new(-0.5, 2.), PI, Vec2::new(1., 2.)),
+            (Vec2::new(1.5, 0.), FRAC_PI_2, Vec2::new(2., 1.)),
+            (Vec2::new(0.5, 2.), PI, Vec2::new(1., 2.)),
+        ];
+        for (bone, (pos, rot, scale)) in posed.iter().zip(expected) {
+            testing::assert_near(bone.pos, pos);
+            // the same angle, however it's wrapped
+            let x_axis = Vec2::new(1., 0.);
+            testing::assert_near(rotate(&x_axis, bone.rot), rotate(&x_axis, rot));
+            testing::assert_near(bone.scale, scale);
+            assert!(bone.world.apply(Vec2::ZERO) == bone.pos);
+        }
+    }
+}
@@ -1,39 +1,16 @@
 //! Easily-accessible and frequently-shared data.
 
-use std::{
-    fmt,
-    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
-};
-
 pub const RECT_VERT_INDICES: [u32; 6] = [0, 1, 2, 0, 3, 1];
 pub const NEW_BONE_NAME: &str = "New Bone";
 
-use tween::Tweener;
+// armature data lives in the runtime so it can be used without the editor
+use crate::history::History;
+use crate::{atlas, export, gizmo, keymap, runtime};
+pub use crate::runtime::*;
+
 use wgpu::BindGroup;
 use winit::keyboard::KeyCode;
 
-#[repr(C)]
-#[derive(
-    Debug,
-    serde::Serialize,
-    serde::Deserialize,
-    Default,
-    Copy,
-    Clone,
-    bytemuck::Pod,
-    bytemuck::Zeroable,
-)]
-pub struct Vec2 {
-    pub x: f32,
-    pub y: f32,
-}
-
-impl tween::TweenValue for Vec2 {
-    fn scale(self, scale: f32) -> Self {
-        self * scale
-    }
-}
-
 impl From<egui::Pos2> for Vec2 {
     fn from(pos: egui::Pos2) -> Vec2 {
         Vec2::new(pos.x, pos.y)
@@ -58,176 +35,6 @@ impl Into<egui::Vec2> for Vec2 {
     }
 }
 
-impl Vec2 {
-    pub const ZERO: Self = Self::new(0., 0.);
-
-    pub const fn new(x: f32, y: f32) -> Vec2 {
-        Vec2 { x, y }
-    }
-
-    pub fn equal_to(self: &Self, other: Vec2) -> bool {
-        return self.x != other.x || self.y != other.y;
-    }
-
-    /// For f32 values that need to be passed as Vec2.
-    pub fn single(value: f32) -> Vec2 {
-        return Vec2::new(value, 0.);
-    }
-}
-
-impl MulAssign for Vec2 {
-    fn mul_assign(&mut self, other: Vec2) {
-        self.x *= other.x;
-        self.y *= other.y;
-    }
-}
-
-impl MulAssign<f32> for Vec2 {
-    fn mul_assign(&mut self, other: f32) {
-        self.x *= other;
-        self.y *= other;
-    }
-}
-
-impl DivAssign for Vec2 {
-    fn div_assign(&mut self, other: Vec2) {
-        self.x /= other.x;
-        self.y /= other.y;
-    }
-}
-
-impl DivAssign<f32> for Vec2 {
-    fn div_assign(&mut self, other: f32) {
-        self.x /= other;
-        self.y /= other;
-    }
-}
-
-impl AddAssign for Vec2 {
-    fn add_assign(&mut self, other: Vec2) {
-        self.x += other.x;
-        self.y += other.y;
-    }
-}
-
-impl SubAssign for Vec2 {
-    fn sub_assign(&mut self, other: Vec2) {
-        self.x -= other.x;
-        self.y -= other.y;
-    }
-}
-
-impl Add for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn add(self, rhs: Self) -> Self {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
-}
-
-impl Div for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn div(self, rhs: Self) -> Self {
-        Self {
-            x: self.x / rhs.x,
-            y: self.y / rhs.y,
-        }
-    }
-}
-
-impl Div<f32> for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn div(self, rhs: f32) -> Self {
-        Self {
-            x: self.x / rhs,
-            y: self.y / rhs,
-        }
-    }
-}
-
-impl Mul<f32> for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn mul(self, rhs: f32) -> Self {
-        Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-        }
-    }
-}
-
-impl Mul for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn mul(self, rhs: Vec2) -> Self {
-        Self {
-            x: self.x * rhs.x,
-            y: self.y * rhs.y,
-        }
-    }
-}
-
-impl Sub for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn sub(self, rhs: Self) -> Self {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
-}
-
-impl Sub<f32> for Vec2 {
-    type Output = Self;
-    #[inline(always)]
-    fn sub(self, rhs: f32) -> Self {
-        Self {
-            x: self.x - rhs,
-            y: self.y - rhs,
-        }
-    }
-}
-
-impl PartialEq for Vec2 {
-    fn eq(&self, other: &Vec2) -> bool {
-        return self.x == other.x && self.y == other.y;
-    }
-}
-impl fmt::Display for Vec2 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let decimal_places = 3;
-
-        let mut p = 0;
-        let mut dp = 1.;
-        while p < decimal_places {
-            dp *= 10.;
-            p += 1;
-        }
-
-        write!(
-            f,
-            "{}, {}",
-            (self.x * dp).trunc() / dp,
-            (self.y * dp).trunc() / dp
-        )
-    }
-}
-
-#[repr(C)]
-#[derive(
-    PartialEq, serde::Serialize, serde::Deserialize, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable,
-)]
-pub struct Vertex {
-    pub pos: Vec2,
-    pub uv: Vec2,
-}
-
 #[derive(Clone, Default)]
 pub struct Camera {
     pub pos: Vec2,
@@ -238,8 +45,6 @@ pub struct Camera {
 /// Input-related fields.
 #[derive(Clone, Default)]
 pub struct InputStates {
-    pub modifier: i32,
-
     // mouse stuff
     pub initial_points: Vec<Vec2>,
     pub mouse_left: i32,
@@ -254,6 +59,9 @@ pub struct InputStates {
     pub on_ui: bool,
 
     pub pressed: Vec<KeyCode>,
+    /// Held modifier keys, as egui sees them (so `command` is Ctrl, or Cmd on Mac).
+    pub modifiers: egui::Modifiers,
+    pub right_clicked: bool,
 }
 
 impl InputStates {
@@ -291,6 +99,22 @@ pub struct Ui {
     pub edit_value: Option<String>,
 
     pub image_modal: bool,
+    pub atlas_modal: bool,
+    /// If the history panel is shown.
+    pub history_open: bool,
+    pub keymap_modal: bool,
+    /// Action waiting for a key to be pressed, to be bound to it.
+    pub rebinding: Option<keymap::Action>,
+    /// Shown in the keymap modal after a rebind took a shortcut from another action.
+    pub keymap_note: String,
+    pub export_modal: bool,
+
+    // columns and rows for splitting a texture into regions
+    pub region_grid: (u32, u32),
+
+    // vertex of the selected mesh bone
+    pub selected_vertex: Option<usize>,
+    pub dragged_vertex: Option<usize>,
 
     pub texture_images: Vec<egui::TextureHandle>,
 
@@ -349,179 +173,25 @@ pub struct UiAnim {
     pub loops: i32,
 
     pub bottom_bar_top: f32,
-}
-
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default)]
-pub struct Bone {
-    #[serde(default)]
-    pub id: i32,
-    #[serde(default)]
-    pub name: String,
-    #[serde(default = "default_neg_one")]
-    pub parent_id: i32,
-    #[serde(default = "default_neg_one")]
-    pub tex_idx: i32,
-
-    #[serde(default)]
-    pub vertices: Vec<Vertex>,
-
-    #[serde(default)]
-    pub is_mesh: bool,
 
-    /// used to properly offset bone's movement to counteract it's parent
-    #[serde(skip)]
-    pub parent_rot: f32,
-
-    #[serde(default)]
-    pub rot: f32,
-    #[serde(default)]
-    pub scale: Vec2,
-    #[serde(default)]
-    pub pos: Vec2,
-    #[serde(default)]
-    pub pivot: Vec2,
-    #[serde(default)]
-    pub zindex: f32,
+    // graph (function curve) mode of the timeline
+    pub graph: bool,
+    pub graph_min: f32,
+    pub graph_max: f32,
+    pub graph_hidden: Vec<(i32, AnimElement)>,
+    pub graph_dragged: Option<usize>,
+    pub graph_framing: Option<GraphFraming>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
-pub struct Armature {
-    #[serde(default)]
-    pub bones: Vec<Bone>,
-    #[serde(default)]
-    pub animations: Vec<Animation>,
-
-    #[serde(default)]
-    pub textures: Vec<Texture>,
+#[derive(Clone, PartialEq)]
+pub enum GraphFraming {
+    All,
+    Selection,
 }
 
-// used for the json
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
-pub struct Root {
-    pub texture_size: Vec2,
-    pub armatures: Vec<Armature>,
-}
-
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
-pub struct Texture {
-    #[serde(default)]
-    pub offset: Vec2,
-    #[serde(default)]
-    pub size: Vec2,
-    #[serde(default)]
-    pub name: String,    
-    #[serde(skip)]
-    pub pixels: Vec<u8>,
-}
-
-#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default)]
-pub struct Animation {
-    #[serde(default)]
-    pub name: String,
-    #[serde(default)]
-    pub fps: i32,
-    #[serde(default)]
-    pub keyframes: Vec<Keyframe>,
-}
-
-#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default)]
-pub struct Keyframe {
-    #[serde(default)]
-    pub frame: i32,
-    //#[serde(default)]
-    //pub bones: Vec<AnimBone>,
-    #[serde(default)]
-    pub bone_id: i32,
-    #[serde(default)]
-    pub element: AnimElement,
-
-    // Only used in runtimes. Represents the element's index in the enum.
-    #[serde(default)]
-    pub element_id: i32,
-
-    #[serde(default)]
-    pub value: f32,
-
-    #[serde(default)]
-    pub transition: Transition,
+/// Icon (in anim_icons.png) of each `AnimElement`'s keyframes.
+pub const ANIM_ICON_ID: [usize; 11] = [0, 0, 1, 2, 2, 3, 3, 2, 2, 2, 4];
 
-    #[serde(skip)]
-    pub label_top: f32,
-}
-
-#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
-pub enum Transition {
-    #[default]
-    Linear,
-    SineIn,
-    SineOut,
-}
-
-// this allows getting the element name as a string
-impl fmt::Display for Transition {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-#[derive(
-    Eq, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize, Clone, Default, Debug,
-)]
-pub enum AnimElement {
-    #[default]
-    PositionX,
-    PositionY,
-    Rotation,
-    ScaleX,
-    ScaleY,
-    PivotX,
-    PivotY,
-    Zindex,
-}
-
-pub const ANIM_ICON_ID: [usize; 7] = [0, 0, 1, 2, 2, 3, 3];
-
-#[derive(Default, Clone)]
-pub enum ActionEnum {
-    #[default]
-    Bone,
-    Animation,
-    Keyframe,
-}
-#[derive(Default, PartialEq, Clone)]
-pub enum ActionType {
-    #[default]
-    Created,
-    Edited,
-}
-
-#[derive(Default, Clone)]
-pub struct Action {
-    pub action: ActionEnum,
-    pub action_type: ActionType,
-
-    pub id: i32,
-    pub animation: Animation,
-    pub bone: Bone,
-}
-
-impl AnimElement {
-    pub fn default_of(element: &AnimElement) -> f32 {
-        match *element {
-            AnimElement::ScaleX => {
-                return 1.;
-            }
-            _ => 0.,
-        }
-    }
-}
-
-// this allows getting the element name as a string
-impl fmt::Display for AnimElement {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
 #[derive(Default, Debug)]
 pub struct BoneTops {
     pub tops: Vec<BoneTop>,
@@ -561,7 +231,63 @@ pub enum EditMode {
     #[default]
     Move,
     Rotate,
-    Scale
+    Scale,
+    /// Edit the vertices of mesh bones. See `mesh_editor`.
+    Mesh,
+}
+
+/// What the selected bones are rotated and scaled around.
+#[derive(Default, PartialEq, Clone, Copy, Debug)]
+pub enum Pivot {
+    /// The average of the bones' origins.
+    #[default]
+    SelectionCenter,
+    /// Every bone's own origin.
+    IndividualOrigins,
+    /// The 2D cursor, placed by right clicking (see `Shared::cursor`).
+    Cursor,
+}
+
+impl Pivot {
+    pub const ALL: [Pivot; 3] = [Pivot::SelectionCenter, Pivot::IndividualOrigins, Pivot::Cursor];
+}
+
+impl std::fmt::Display for Pivot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Pivot::SelectionCenter => write!(f, "Selection Center"),
+            Pivot::IndividualOrigins => write!(f, "Individual Origins"),
+            Pivot::Cursor => write!(f, "2D Cursor"),
+        }
+    }
+}
+
+/// A bone as it was when the selection started being transformed.
+#[derive(Clone, Default)]
+pub struct TransformedBone {
+    pub idx: usize,
+    /// World position of the bone's origin.
+    pub origin: Vec2,
+    // as shown (ie. animated, if animating)
+    pub pos: Vec2,
+    pub rot: f32,
+    pub scale: Vec2,
+    /// Brings world directions into the parent's space.
+    pub parent_inverse: Transform,
+}
+
+/// The selected bones being moved, rotated or scaled together.
+///
+/// See `Shared::start_transform`.
+#[derive(Clone, Default)]
+pub struct SelectionTransform {
+    /// World position the mouse is dragged from.
+    pub from: Vec2,
+    /// World position the bones are rotated and scaled around.
+    pub pivot: Vec2,
+    /// Axis that moving and scaling is kept to, and its world direction.
+    pub axis: Option<(gizmo::Axis, Vec2)>,
+    pub bones: Vec<TransformedBone>,
 }
 
 #[derive(Default, PartialEq, Debug)]
@@ -570,18 +296,33 @@ pub struct BoneTop {
     pub element: AnimElement,
     pub height: f32,
 }
-#[derive(Clone)]
-pub struct RenderedFrame {
-    pub buffer: wgpu::Buffer,
-    pub width: u32,
-    pub height: u32,
+/// An armature of the project, with everything that's kept separately for each one.
+///
+/// See `Shared::switch_armature`.
+#[derive(Default)]
+pub struct ArmatureSlot {
+    pub armature: Armature,
+    pub bind_groups: Vec<BindGroup>,
+    pub texture_images: Vec<egui::TextureHandle>,
+    pub history: History,
 }
+
 #[derive(Default)]
 pub struct Shared {
     pub window: Vec2,
+    /// The active bone, edited in the bone panel. It's always one of `selected_bones`.
     pub selected_bone_idx: usize,
+    /// Ids of every selected bone, in the order they were selected.
+    pub selected_bones: Vec<i32>,
+    /// Slot edited in the bone panel, if it's on the selected bone (see `selected_slot`).
+    pub selected_slot_idx: usize,
+    /// The armature being edited.
     pub armature: Armature,
     pub bind_groups: Vec<BindGroup>,
+    /// Every armature of the project, in order. The slot at `armature_idx` is empty,
+    /// as it's what `armature` (and the rest of `Shared`) is holding while it's edited.
+    pub armatures: Vec<ArmatureSlot>,
+    pub armature_idx: usize,
     pub camera: Camera,
     pub input: InputStates,
     pub egui_ctx: egui::Context,
@@ -590,22 +331,41 @@ pub struct Shared {
     pub editing_bone: bool,
 
     pub frame: i32,
+    // export the selected animation on the next render
     pub recording: bool,
-    pub done_recording: bool,
-    // mainly used for video, but can also be used for screenshots
-    pub rendered_frames: Vec<RenderedFrame>,
 
-    pub undo_actions: Vec<Action>,
-    pub redo_actions: Vec<Action>,
+    /// Undo and redo of the armature being edited.
+    pub history: History,
+    /// The armature might have been edited since the history last looked, by input or a
+    /// file being read. Nothing else edits it, so there's no need to look otherwise.
+    pub armature_dirty: bool,
+    /// Textures were changed by undoing or redoing, so their bind groups and images
+    /// need making again.
+    pub reload_textures: bool,
 
     // should be enum but too lazy atm
     pub edit_mode: EditMode,
+    pub pivot: Pivot,
+    /// World position of the 2D cursor.
+    pub cursor: Vec2,
+    /// The selected bones being dragged, if they are.
+    pub transform: Option<SelectionTransform>,
+    /// World position where a box selection was started, if one is being dragged.
+    pub box_select: Option<Vec2>,
 
     pub highlight_bindgroup: Option<BindGroup>,
     pub gridline_bindgroup: Option<BindGroup>,
     pub point_bindgroup: Option<BindGroup>,
+    /// Plain white, to be tinted (see `renderer::set_color`).
+    pub gizmo_bindgroup: Option<BindGroup>,
+    /// Part of the gizmo being dragged, if any.
+    pub gizmo_handle: Option<gizmo::Handle>,
 
     pub save_path: String,
+    /// how textures are packed on save
+    pub atlas: atlas::AtlasConfig,
+    pub keymap: keymap::Keymap,
+    pub export: export::ExportSettings,
 
     /// triggers debug stuff. Set in main.rs
     pub debug: bool,
@@ -652,11 +412,264 @@ impl Shared {
 
     pub fn unselect_everything(&mut self) {
         self.selected_bone_idx = usize::MAX;
+        self.selected_bones.clear();
     }
 
+    /// Select only this bone.
     pub fn select_bone(&mut self, idx: usize) {
         self.unselect_everything();
+        self.add_to_selection(idx);
+    }
+
+    /// Select a bone along with the ones already selected, and make it the active one.
+    pub fn add_to_selection(&mut self, idx: usize) {
+        let id = self.armature.bones[idx].id;
+        if !self.is_selected(id) {
+            self.selected_bones.push(id);
+        }
+        if self.selected_bone_idx == idx {
+            return;
+        }
         self.selected_bone_idx = idx;
+        self.ui.selected_vertex = None;
+
+        let first_slot = self.armature.slots.iter().position(|s| s.bone_id == id);
+        self.selected_slot_idx = first_slot.unwrap_or(usize::MAX);
+    }
+
+    /// Unselect a bone. If it was the active one, the last bone selected before it
+    /// takes over.
+    pub fn unselect_bone(&mut self, idx: usize) {
+        let id = self.armature.bones[idx].id;
+        self.selected_bones.retain(|selected| *selected != id);
+        if self.selected_bone_idx != idx {
+            return;
+        }
+        self.selected_bone_idx = usize::MAX;
+        if let Some(last) = self.selected_bone_idxs().last() {
+            self.add_to_selection(*last);
+        }
+    }
+
+    pub fn toggle_selection(&mut self, idx: usize) {
+        if self.is_selected(self.armature.bones[idx].id) {
+            self.unselect_bone(idx);
+        } else {
+            self.add_to_selection(idx);
+        }
+    }
+
+    pub fn is_selected(&self, id: i32) -> bool {
+        self.selected_bones.contains(&id)
+    }
+
+    /// Indices of the selected bones, in the order they were selected.
+    pub fn selected_bone_idxs(&self) -> Vec<usize> {
+        self.selected_bones
+            .iter()
+            .filter_map(|id| self.armature.bones.iter().position(|b| b.id == *id))
+            .collect()
+    }
+
+    /// The selected bone's slot being edited, if it has any.
+    pub fn selected_slot(&self) -> Option<&Slot> {
+        let id = self.selected_bone()?.id;
+        self.armature
+            .slots
+            .get(self.selected_slot_idx)
+            .filter(|s| s.bone_id == id)
+    }
+
+    pub fn selected_slot_mut(&mut self) -> Option<&mut Slot> {
+        let id = self.selected_bone()?.id;
+        self.armature
+            .slots
+            .get_mut(self.selected_slot_idx)
+            .filter(|s| s.bone_id == id)
+    }
+
+    /// Add a slot to a bone, drawn in front of everything else. Returns its index.
+    pub fn add_slot(&mut self, bone_id: i32) -> usize {
+        let bone_name = self.find_bone(bone_id).unwrap().name.clone();
+        let taken = |name: &String| self.armature.slots.iter().any(|s| s.name == *name);
+        let mut name = bone_name.clone();
+        let mut n = 2;
+        while taken(&name) {
+            name = format!("{} {}", bone_name, n);
+            n += 1;
+        }
+
+        let idx = self.armature.slots.len();
+        self.armature.slots.push(Slot {
+            name,
+            bone_id,
+            ..Default::default()
+        });
+
+        // keyed draw orders need every slot
+        for anim in &mut self.armature.animations {
+            for kf in &mut anim.keyframes {
+                if kf.element == AnimElement::DrawOrder {
+                    kf.draw_order.push(idx);
+                }
+            }
+        }
+        idx
+    }
+
+    pub fn remove_slot(&mut self, idx: usize) {
+        self.armature.slots.remove(idx);
+        for anim in &mut self.armature.animations {
+            anim.keyframes
+                .retain(|kf| kf.attachment.as_ref().is_none_or(|a| a.slot_idx != idx));
+            for kf in &mut anim.keyframes {
+                if let Some(attachment) = &mut kf.attachment {
+                    if attachment.slot_idx > idx {
+                        attachment.slot_idx -= 1;
+                    }
+                }
+                if kf.element != AnimElement::DrawOrder {
+                    continue;
+                }
+                kf.draw_order.retain(|i| *i != idx);
+                for i in &mut kf.draw_order {
+                    if *i > idx {
+                        *i -= 1;
+                    }
+                }
+            }
+        }
+        if self.selected_slot_idx != usize::MAX && self.selected_slot_idx >= idx {
+            self.selected_slot_idx = self.selected_slot_idx.wrapping_sub(1);
+        }
+    }
+
+    /// The selected slot, or a new one on the selected bone if it has none. For giving
+    /// the bone a texture.
+    pub fn selected_slot_or_new(&mut self) -> &mut Slot {
+        if self.selected_slot().is_none() {
+            let id = self.selected_bone().unwrap().id;
+            self.selected_slot_idx = self.add_slot(id);
+        }
+        self.selected_slot_mut().unwrap()
+    }
+
+    /// Size of the texture (or region of it) shown by a bone, which its mesh is made
+    /// to fit. That's its selected slot's, or else its first one with a texture.
+    pub fn bone_tex_size(&self, bone_id: i32) -> Option<Vec2> {
+        let textured = |s: &&Slot| s.bone_id == bone_id && s.tex_idx != -1;
+        let slot = self
+            .selected_slot()
+            .filter(textured)
+            .or_else(|| self.armature.slots.iter().find(textured))?;
+        let tex = &self.armature.textures[slot.tex_idx as usize];
+        Some(tex.region_size(slot.region_idx))
+    }
+
+    /// Order the slots are drawn in at the selected frame, from back to front.
+    pub fn draw_order(&self) -> Vec<usize> {
+        if !self.is_animating() {
+            return (0..self.armature.slots.len()).collect();
+        }
+        runtime::draw_order(
+            &self.armature,
+            self.ui.anim.selected,
+            self.ui.anim.selected_frame as f32,
+        )
+    }
+
+    /// Change the order slots are drawn in. While animating, it's keyed on the
+    /// selected frame instead.
+    pub fn set_draw_order(&mut self, order: Vec<usize>) {
+        if !self.is_animating() {
+            let mut slots: Vec<Option<Slot>> =
+                self.armature.slots.drain(..).map(Some).collect();
+            self.armature.slots = order.iter().map(|i| slots[*i].take().unwrap()).collect();
+
+            // keep pointing at the same slots
+            let new_idx = |old: usize| order.iter().position(|i| *i == old).unwrap();
+            for anim in &mut self.armature.animations {
+                for kf in &mut anim.keyframes {
+                    for i in &mut kf.draw_order {
+                        *i = new_idx(*i);
+                    }
+                    if let Some(attachment) = &mut kf.attachment {
+                        attachment.slot_idx = new_idx(attachment.slot_idx);
+                    }
+                }
+            }
+            if self.selected_slot_idx < order.len() {
+                self.selected_slot_idx = new_idx(self.selected_slot_idx);
+            }
+            return;
+        }
+
+        let frame = self.ui.anim.selected_frame;
+        self.check_if_in_keyframe(-1, frame, AnimElement::DrawOrder);
+        let anim = self.selected_animation_mut().unwrap();
+        let kf = anim
+            .keyframes
+            .iter_mut()
+            .find(|kf| kf.frame == frame && kf.element == AnimElement::DrawOrder)
+            .unwrap();
+        kf.draw_order = order;
+        self.sort_keyframes();
+    }
+
+    /// The slots at the selected frame, showing whatever's attached to them then.
+    pub fn slots(&self) -> Vec<Slot> {
+        if !self.is_animating() {
+            return self.armature.slots.clone();
+        }
+        runtime::slots_at(
+            &self.armature,
+            self.ui.anim.selected,
+            self.ui.anim.selected_frame as f32,
+        )
+    }
+
+    /// Show a texture (or region of it) in the selected slot, making one if the bone
+    /// has none. While animating, it's keyed on the selected frame instead.
+    pub fn set_attachment(&mut self, tex_idx: i32, region_idx: i32) {
+        let is_animating = self.is_animating();
+        let slot = self.selected_slot_or_new();
+        if !is_animating {
+            slot.tex_idx = tex_idx;
+            slot.region_idx = region_idx;
+            return;
+        }
+        let bone_id = slot.bone_id;
+        let slot_idx = self.selected_slot_idx;
+
+        let frame = self.ui.anim.selected_frame;
+        let attachment = Attachment {
+            slot_idx,
+            tex_idx,
+            region_idx,
+        };
+        let keyframes = &mut self.selected_animation_mut().unwrap().keyframes;
+        let keyed = keyframes.iter_mut().find(|kf| {
+            kf.frame == frame && kf.attachment.as_ref().is_some_and(|a| a.slot_idx == slot_idx)
+        });
+        match keyed {
+            Some(kf) => kf.attachment = Some(attachment),
+            None => keyframes.push(Keyframe {
+                frame,
+                bone_id,
+                element: AnimElement::Attachment,
+                element_id: AnimElement::Attachment as i32,
+                attachment: Some(attachment),
+                ..Default::default()
+            }),
+        }
+        self.sort_keyframes();
+    }
+
+    /// If the vertices of the selected bone are being edited, instead of the bone itself.
+    pub fn editing_mesh(&self) -> bool {
+        self.edit_mode == EditMode::Mesh
+            && self.selected_bone().is_some()
+            && self.selected_bone().unwrap().is_mesh
     }
 
     pub fn select_frame(&mut self, idx: i32) {
@@ -743,6 +756,9 @@ impl Shared {
                 break;
             }
         }
+        while let Some(slot) = self.armature.slots.iter().position(|s| s.bone_id == id) {
+            self.remove_slot(slot);
+        }
     }
 
     pub fn find_bone_mut(&mut self, id: i32) -> Option<&mut Bone> {
@@ -754,178 +770,243 @@ impl Shared {
         None
     }
 
-    pub fn animate(&self, _anim_idx: usize) -> Vec<Bone> {
-        let mut bones = self.armature.bones.clone();
+    pub fn animate(&self, anim_idx: usize) -> Vec<Bone> {
+        runtime::animate(
+            &self.armature,
+            anim_idx,
+            self.ui.anim.selected_frame as f32,
+        )
+    }
 
-        // ignore if this animation has no keyframes
-        let kf_len = self.selected_animation().unwrap().keyframes.len();
-        if kf_len == 0 {
-            return bones;
-        }
-
-        for b in &mut bones {
-            macro_rules! interpolate {
-                ($element:expr, $default:expr) => {{
-                    let (prev, next, total_frames, current_frame, transition) = self
-                        .find_connecting_frames(
-                            b.id,
-                            $element,
-                            $default,
-                            self.ui.anim.selected_frame,
-                        );
-                    match (transition) {
-                        Transition::SineIn => {
-                            Tweener::sine_in(prev, next, total_frames).move_to(current_frame)
-                        }
-                        Transition::SineOut => {
-                            Tweener::sine_out(prev, next, total_frames).move_to(current_frame)
-                        }
-                        _ => Tweener::linear(prev, next, total_frames).move_to(current_frame),
-                    }
-                }};
-            }
+    pub fn get_mouse_world(&mut self) -> Vec2 {
+        // get mouse in world space
+        let mut mouse_world = crate::utils::screen_to_world_space(self.input.mouse, self.window);
+        mouse_world.x *= self.window.x / self.window.y;
+        mouse_world
+    }
 
-            // interpolate!
-            #[rustfmt::skip] 
-            {
-                b.pos.x   += interpolate!(AnimElement::PositionX, 0.);
-                b.pos.y   += interpolate!(AnimElement::PositionY, 0.);
-                b.rot     += interpolate!(AnimElement::Rotation,  0.);
-                b.scale.x *= interpolate!(AnimElement::ScaleX,    1.);
-                b.scale.y *= interpolate!(AnimElement::ScaleY,    1.);
-                b.pivot.x += interpolate!(AnimElement::PivotX,    0.);
-                b.pivot.y += interpolate!(AnimElement::PivotY,    0.);
-                b.zindex  += interpolate!(AnimElement::Zindex,    0.);
-            };
-        }
+    /// Convert a screen position (in physical pixels) to world space.
+    pub fn screen_to_world(&self, pos: Vec2) -> Vec2 {
+        let mut world = crate::utils::screen_to_world_space(pos, self.window);
+        world.x *= self.window.x / self.window.y;
+        world * self.camera.zoom + self.camera.pos
+    }
 
-        bones
+    /// Convert a world position to screen space (in physical pixels).
+    pub fn world_to_screen(&self, pos: Vec2) -> Vec2 {
+        let mut ndc = (pos - self.camera.pos) / self.camera.zoom;
+        ndc.x /= self.window.x / self.window.y;
+        Vec2::new(
+            (ndc.x + 1.) / 2. * self.window.x,
+            (1. - ndc.y) / 2. * self.window.y,
+        )
     }
 
-    pub fn find_connecting_frames(
-        &self,
-        bone_id: i32,
-        element: AnimElement,
-        default: f32,
-        frame: i32,
-    ) -> (f32, f32, i32, i32, Transition) {
-        let mut prev: Option<f32> = None;
-        let mut next: Option<f32> = None;
-        let mut start_frame = 0;
-        let mut end_frame = 0;
-        let mut transition: Transition = Transition::Linear;
-
-        // get most previous frame with this element
-        for (i, kf) in self.selected_animation().unwrap().keyframes.iter().enumerate() {
-            if self.selected_animation().unwrap().keyframes[i].frame > frame {
-                break;
-            }
+    /// Get a bone's world transform as it's currently shown (ie. animated, if animating).
+    pub fn world_transform(&self, id: i32) -> Transform {
+        let (bones, world) = self.shown_pose();
+        match bones.iter().position(|b| b.id == id) {
+            Some(idx) => world[idx],
+            None => Transform::IDENTITY,
+        }
+    }
 
-            if kf.bone_id != bone_id || kf.element != element {
-                continue;
-            }
+    // bones as they're currently shown, along with their world transforms
+    fn shown_pose(&self) -> (Vec<Bone>, Vec<Transform>) {
+        let mut bones = self.armature.bones.clone();
+        if self.is_animating() {
+            bones = self.animate(self.ui.anim.selected);
+        }
+        let world = runtime::world_transforms(&runtime::place(&self.armature, &bones));
+        (bones, world)
+    }
 
-            prev = Some(kf.value);
-            start_frame = kf.frame;
+    /// Directions of the active bone's axes in world space, as it's shown.
+    pub fn active_axes(&self) -> (Vec2, Vec2) {
+        let world = self.world_transform(self.selected_bone().map_or(-1, |b| b.id));
+        let x = Vec2::new(world.a, world.b);
+        let y = Vec2::new(world.c, world.d);
+        if x.length() == 0. || y.length() == 0. {
+            return (Vec2::new(1., 0.), Vec2::new(0., 1.));
         }
+        (x / x.length(), y / y.length())
+    }
 
-        // get first next frame with this element
-        for (i, kf) in self.selected_animation().unwrap().keyframes.iter().enumerate().rev() {
-            if self.selected_animation().unwrap().keyframes[i].frame < frame {
-                break;
-            }
+    /// Start moving, rotating or scaling the selected bones, with the mouse being
+    /// dragged from `from` (in world space). See `transform_selection`.
+    ///
+    /// With an `axis`, moving and scaling is kept to that axis of the active bone.
+    pub fn start_transform(&mut self, from: Vec2, axis: Option<gizmo::Axis>) {
+        self.transform = Some(self.selection_transform(from, axis));
+    }
 
-            if kf.bone_id != bone_id || kf.element != element {
+    /// The selected bones as they are now, for `start_transform`.
+    ///
+    /// Bones under another selected bone are left to follow their parent.
+    pub fn selection_transform(
+        &self,
+        from: Vec2,
+        axis: Option<gizmo::Axis>,
+    ) -> SelectionTransform {
+        let (bones, world) = self.shown_pose();
+        let selected = self.selected_bone_idxs();
+        let mut transformed: Vec<TransformedBone> = vec![];
+        for &idx in &selected {
+            let mut parent = bones.iter().position(|b| b.id == bones[idx].parent_id);
+            let parent_idx = parent;
+            while let Some(p) = parent {
+                if selected.contains(&p) {
+                    break;
+                }
+                parent = bones.iter().position(|b| b.id == bones[p].parent_id);
+            }
+            if parent.is_some() {
                 continue;
             }
 
-            next = Some(kf.value);
-            end_frame = kf.frame;
-            transition = kf.transition.clone();
+            let parent_world = parent_idx.map_or(Transform::IDENTITY, |p| world[p]);
+            transformed.push(TransformedBone {
+                idx,
+                origin: world[idx].translation(),
+                pos: bones[idx].pos,
+                rot: bones[idx].rot,
+                scale: bones[idx].scale,
+                parent_inverse: parent_world.inverse().unwrap_or(Transform::IDENTITY),
+            });
         }
 
-        // ensure prev and next are pointing somewhere
-        if prev == None {
-            if next != None {
-                prev = next
-            } else {
-                prev = Some(default)
-            }
-        }
-        if next == None {
-            if prev != None {
-                next = prev;
-            } else {
-                next = Some(default);
+        let mut pivot = self.cursor;
+        if self.pivot != Pivot::Cursor && !transformed.is_empty() {
+            // individual origins still measure the mouse from the center
+            pivot = Vec2::ZERO;
+            for bone in &transformed {
+                pivot += bone.origin;
             }
+            pivot /= transformed.len() as f32;
         }
 
-        let mut total_frames = end_frame - start_frame;
-        // Tweener doesn't accept 0 duration
-        if total_frames == 0 {
-            total_frames = 1;
+        let (x_axis, y_axis) = self.active_axes();
+        SelectionTransform {
+            from,
+            pivot,
+            axis: axis.map(|axis| match axis {
+                gizmo::Axis::X => (axis, x_axis),
+                gizmo::Axis::Y => (axis, y_axis),
+            }),
+            bones: transformed,
         }
-
-        let current_frame = frame - start_frame;
-
-        (
-            prev.unwrap(),
-            next.unwrap(),
-            total_frames,
-            current_frame,
-            transition,
-        )
     }
 
-    pub fn get_mouse_world(&mut self) -> Vec2 {
-        // get mouse in world space
-        let mut mouse_world = crate::utils::screen_to_world_space(self.input.mouse, self.window);
-        mouse_world.x *= self.window.x / self.window.y;
-        mouse_world
-    }
-
-    pub fn move_with_mouse(&mut self, value: &Vec2, counter_parent: bool) -> Vec2 {
-        let mut mouse = self.get_mouse_world();
-
-        // Counter-act parent's rotation so that translation is global.
-        // Only used in bone translation.
-        if counter_parent {
-            let parent_id = self.selected_bone().unwrap().parent_id;
-            if let Some(parent) = self.find_bone(parent_id) {
-                mouse = crate::utils::rotate(&mouse, -parent.rot);
+    /// Move, rotate or scale (following `edit_mode`) the bones of `start_transform`, as
+    /// if the mouse was dragged to `to` (in world space).
+    ///
+    /// Rotating goes by the mouse's angle around the pivot, and scaling by its distance
+    /// from it. If `snap`, changes are rounded to the increments in `gizmo`. While
+    /// animating, every bone is keyed on the selected frame.
+    pub fn transform_selection(&mut self, to: Vec2, snap: bool) {
+        let Some(transform) = self.transform.clone() else {
+            return;
+        };
+        let from = transform.from - transform.pivot;
+        let towards = to - transform.pivot;
+
+        let mut moved = to - transform.from;
+        if let Some((_, direction)) = transform.axis {
+            let mut along = moved.dot(direction);
+            if snap {
+                along = gizmo::snap(along, gizmo::SNAP_MOVE);
             }
+            moved = direction * along;
+        } else if snap {
+            moved = Vec2::new(
+                gizmo::snap(moved.x, gizmo::SNAP_MOVE),
+                gizmo::snap(moved.y, gizmo::SNAP_MOVE),
+            );
         }
 
-        // Upon immediately clicking, track initial values to allow 'dragging'
-        if self.input.initial_points.len() == 0 {
-            let initial = mouse * self.camera.zoom;
-            self.input.initial_points.push(*value - initial);
+        let mut angle = towards.y.atan2(towards.x) - from.y.atan2(from.x);
+        if angle > std::f32::consts::PI {
+            angle -= std::f32::consts::TAU;
+        } else if angle < -std::f32::consts::PI {
+            angle += std::f32::consts::TAU;
+        }
+        if snap {
+            angle = gizmo::snap(angle, gizmo::SNAP_ROTATE);
         }
+        let rotation = Transform::from_trs(Vec2::ZERO, angle, Vec2::new(1., 1.));
 
-        (mouse * self.camera.zoom) + self.input.initial_points[0]
-    }
+        // scaling along an axis goes by the distance along it
+        let (mut from_length, mut towards_length) = (from.length(), towards.length());
+        if let Some((_, direction)) = transform.axis {
+            (from_length, towards_length) = (from.dot(direction), towards.dot(direction));
+        }
+        let mut factor = 1.;
+        if from_length != 0. {
+            factor = towards_length / from_length;
+        }
+        if snap {
+            factor = gizmo::snap(factor, gizmo::SNAP_SCALE);
+        }
+        let factors = match transform.axis {
+            Some((gizmo::Axis::X, _)) => Vec2::new(factor, 1.),
+            Some((gizmo::Axis::Y, _)) => Vec2::new(1., factor),
+            None => Vec2::new(factor, factor),
+        };
 
-    pub fn save_edited_bone(&mut self) {
-        self.undo_actions.push(Action {
-            action: ActionEnum::Bone,
-            action_type: ActionType::Edited,
-            bone: self.selected_bone().unwrap().clone(),
-            id: self.selected_bone().unwrap().id,
-            ..Default::default()
-        });
+        for bone in &transform.bones {
+            let around = bone.origin - transform.pivot;
+            let scaled = match transform.axis {
+                Some((_, dir)) => around + dir * (around.dot(dir) * (factor - 1.)),
+                None => around * factor,
+            };
+            let origin = match self.edit_mode {
+                EditMode::Move => bone.origin + moved,
+                EditMode::Mesh => return,
+                _ if self.pivot == Pivot::IndividualOrigins => bone.origin,
+                EditMode::Rotate => transform.pivot + rotation.apply_vector(around),
+                EditMode::Scale => transform.pivot + scaled,
+            };
+            if origin != bone.origin {
+                let pos = bone.pos + bone.parent_inverse.apply_vector(origin - bone.origin);
+                self.edit_bone_at(bone.idx, &AnimElement::PositionX, pos.x, true);
+                self.edit_bone_at(bone.idx, &AnimElement::PositionY, pos.y, true);
+            }
 
-        if self.is_animating() {
-            self.undo_actions.push(Action {
-                action: ActionEnum::Animation,
-                action_type: ActionType::Edited,
-                id: self.ui.anim.selected as i32,
-                animation: self.selected_animation().unwrap().clone(),
-                ..Default::default()
-            });                   
+            match self.edit_mode {
+                EditMode::Rotate => {
+                    // a mirrored parent turns its children the other way
+                    let mut rot = bone.rot + angle;
+                    if bone.parent_inverse.determinant() < 0. {
+                        rot = bone.rot - angle;
+                    }
+                    self.edit_bone_at(bone.idx, &AnimElement::Rotation, rot, true);
+                }
+                EditMode::Scale => {
+                    let scale = bone.scale * factors;
+                    self.edit_bone_at(bone.idx, &AnimElement::ScaleX, scale.x, true);
+                    self.edit_bone_at(bone.idx, &AnimElement::ScaleY, scale.y, true);
+                }
+                _ => {}
+            }
         }
     }
 
-    pub fn edit_bone(&mut self, element: &AnimElement, mut value: f32, overwrite: bool) {
+    /// Edit the selected bone. See `edit_bone_at`.
+    pub fn edit_bone(&mut self, element: &AnimElement, value: f32, overwrite: bool) {
+        self.edit_bone_at(self.selected_bone_idx, element, value, overwrite);
+    }
+
+    /// Set a bone's value, or key it on the selected frame if animating.
+    ///
+    /// If `overwrite`, `value` is what the bone will be shown with, rather than what's
+    /// keyed on top of the armature.
+    pub fn edit_bone_at(
+        &mut self,
+        idx: usize,
+        element: &AnimElement,
+        mut value: f32,
+        overwrite: bool,
+    ) {
         let og_value: f32;
         let is_animating = self.is_animating();
 
@@ -938,13 +1019,17 @@ impl Shared {
                     // if overwriting, modify the value such that it will return to the current field's value on animating
                     match(element) {
                         AnimElement::ScaleX | AnimElement::ScaleY=> value /= $field,
+                        // keyed as-is, rather than on top of the field
+                        AnimElement::IkMix => {},
                         _ => value -= $field
                     }
                 }
             };
         }
 
-        let bone_mut = self.selected_bone_mut().unwrap();
+        let id = self.armature.bones[idx].id;
+        let ik = self.armature.ik_constraints.iter_mut().find(|c| c.bone_id == id);
+        let bone_mut = &mut self.armature.bones[idx];
 
         #[rustfmt::skip]
         match element {
@@ -955,7 +1040,9 @@ impl Shared {
             AnimElement::ScaleY =>    { edit!(bone_mut.scale.y); },
             AnimElement::PivotX =>    { edit!(bone_mut.pivot.x); },
             AnimElement::PivotY =>    { edit!(bone_mut.pivot.y); },
-            AnimElement::Zindex =>    { edit!(bone_mut.zindex); },
+            AnimElement::IkMix =>     { let Some(ik) = ik else { return }; edit!(ik.mix); },
+            // not a single value, see mesh_editor, set_draw_order and set_attachment
+            AnimElement::Deform | AnimElement::DrawOrder | AnimElement::Attachment => return,
         };
 
         if !self.is_animating() {
@@ -963,22 +1050,17 @@ impl Shared {
         }
 
         if self.ui.anim.selected_frame != 0 {
-            let added = self.check_if_in_keyframe(self.selected_bone().unwrap().id, 0, element.clone());
+            let added = self.check_if_in_keyframe(id, 0, element.clone());
             if added {
                 self.last_keyframe_mut().unwrap().value = og_value;
             }
         }
 
-        self.check_if_in_keyframe(
-            self.selected_bone().unwrap().id,
-            self.ui.anim.selected_frame,
-            element.clone(),
-        );
+        self.check_if_in_keyframe(id, self.ui.anim.selected_frame, element.clone());
 
         let selected_frame = self.ui.anim.selected_frame;
-        let selected_id = self.selected_bone().unwrap().id;
         for kf in &mut self.selected_animation_mut().unwrap().keyframes {
-            if kf.frame != selected_frame || kf.bone_id != selected_id || kf.element != *element {
+            if kf.frame != selected_frame || kf.bone_id != id || kf.element != *element {
                 continue;
             }
 
@@ -1022,18 +1104,221 @@ impl Shared {
         self.armature.textures.remove(tex_idx as usize);
         self.bind_groups.remove(tex_idx as usize);
         let _ = self.ui.texture_images.remove(tex_idx as usize);
-        for bone in &mut self.armature.bones {
-            if bone.tex_idx == tex_idx {
-                bone.tex_idx = -1;
+        for (tex, region) in self.shown_textures_mut() {
+            if *tex == tex_idx {
+                *tex = -1;
+                *region = -1;
+            }
+            if *tex > tex_idx {
+                *tex -= 1;
+            }
+        }
+    }
+
+    /// Replace a texture's regions, unassigning slots from ones that no longer exist.
+    pub fn set_regions(&mut self, tex_idx: i32, regions: Vec<Region>) {
+        let len = regions.len() as i32;
+        self.armature.textures[tex_idx as usize].regions = regions;
+        for (tex, region) in self.shown_textures_mut() {
+            if *tex == tex_idx && *region >= len {
+                *region = -1;
+            }
+        }
+    }
+
+    pub fn remove_region(&mut self, tex_idx: i32, region_idx: i32) {
+        self.armature.textures[tex_idx as usize]
+            .regions
+            .remove(region_idx as usize);
+        for (tex, region) in self.shown_textures_mut() {
+            if *tex != tex_idx {
+                continue;
             }
-            if bone.tex_idx > tex_idx {
-                bone.tex_idx -= 1;
+            if *region == region_idx {
+                *region = -1;
             }
+            if *region > region_idx {
+                *region -= 1;
+            }
+        }
+    }
+
+    /// Texture and region indices of every slot, and of everything attached to them
+    /// in animations.
+    fn shown_textures_mut(&mut self) -> Vec<(&mut i32, &mut i32)> {
+        let slots = self
+            .armature
+            .slots
+            .iter_mut()
+            .map(|s| (&mut s.tex_idx, &mut s.region_idx));
+        let attachments = self
+            .armature
+            .animations
+            .iter_mut()
+            .flat_map(|a| &mut a.keyframes)
+            .filter_map(|kf| kf.attachment.as_mut())
+            .map(|a| (&mut a.tex_idx, &mut a.region_idx));
+        slots.chain(attachments).collect()
+    }
+
+    /// An armature of the project, whether or not it's the one being edited.
+    pub fn project_armature(&self, idx: usize) -> &Armature {
+        if idx == self.armature_idx {
+            &self.armature
+        } else {
+            &self.armatures[idx].armature
+        }
+    }
+
+    pub fn project_armature_mut(&mut self, idx: usize) -> &mut Armature {
+        if idx == self.armature_idx {
+            &mut self.armature
+        } else {
+            &mut self.armatures[idx].armature
+        }
+    }
+
+    /// Every armature of the project, in order.
+    pub fn project_armatures(&self) -> Vec<&Armature> {
+        (0..self.armatures.len().max(1))
+            .map(|i| self.project_armature(i))
+            .collect()
+    }
+
+    // swap what's being edited with what's held in a slot
+    fn swap_slot(&mut self, idx: usize) {
+        let slot = &mut self.armatures[idx];
+        std::mem::swap(&mut self.armature, &mut slot.armature);
+        std::mem::swap(&mut self.bind_groups, &mut slot.bind_groups);
+        std::mem::swap(&mut self.ui.texture_images, &mut slot.texture_images);
+        std::mem::swap(&mut self.history, &mut slot.history);
+    }
+
+    /// Start editing another armature of the project.
+    pub fn switch_armature(&mut self, idx: usize) {
+        if idx == self.armature_idx || idx >= self.armatures.len() {
+            return;
+        }
+        self.swap_slot(self.armature_idx);
+        self.swap_slot(idx);
+        self.armature_idx = idx;
+
+        self.reset_selections();
+    }
+
+    /// Replace the whole project, and start editing its first armature.
+    pub fn load_project(&mut self, slots: Vec<ArmatureSlot>) {
+        if slots.is_empty() {
+            return;
+        }
+        self.armatures = slots;
+        self.armature_idx = usize::MAX;
+        self.swap_slot(0);
+        // the slot now has the old project's armature
+        self.armatures[0] = ArmatureSlot::default();
+        self.armature_idx = 0;
+        self.reset_selections();
+    }
+
+    // selections only make sense for the armature they were made in
+    fn reset_selections(&mut self) {
+        self.unselect_everything();
+        self.editing_bone = false;
+        self.ui.anim.selected = usize::MAX;
+        self.ui.anim.selected_frame = -1;
+        self.ui.anim.playing = false;
+    }
+
+    /// Add an empty armature to the project, and start editing it.
+    pub fn new_armature(&mut self) {
+        let names: Vec<String> = self
+            .project_armatures()
+            .iter()
+            .map(|a| a.name.clone())
+            .collect();
+        let mut num = self.armatures.len() + 1;
+        while names.contains(&format!("Armature {}", num)) {
+            num += 1;
+        }
+
+        self.armatures.push(ArmatureSlot {
+            armature: Armature {
+                name: format!("Armature {}", num),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        self.switch_armature(self.armatures.len() - 1);
+    }
+
+    /// Remove an armature from the project. There's always at least one left.
+    pub fn remove_armature(&mut self, idx: usize) {
+        if self.armatures.len() <= 1 || idx >= self.armatures.len() {
+            return;
+        }
+        if idx == self.armature_idx {
+            self.switch_armature(if idx == 0 { 1 } else { idx - 1 });
         }
+        self.armatures.remove(idx);
+        if self.armature_idx > idx {
+            self.armature_idx -= 1;
+        }
+    }
+
+    /// Add whatever was edited since the last call to the history. Done at the end of
+    /// any frame the armature might have been edited in (see `armature_dirty`), so that
+    /// edits don't need to record themselves.
+    pub fn record_edits(&mut self) {
+        self.history.record(&self.armature);
     }
 
-    pub fn sort_bone_zindex(&mut self, bone_idx: i32) {
-        self.armature.bones[bone_idx as usize].zindex = bone_idx as f32 + 1.;
+    pub fn undo(&mut self) {
+        let active = self.armature.bones.get(self.selected_bone_idx).map(|b| b.id);
+        let Some(command) = self.history.undo(&mut self.armature) else {
+            return;
+        };
+        self.reload_textures |= command.changes_textures();
+        self.fix_selections(active);
+    }
+
+    pub fn redo(&mut self) {
+        let active = self.armature.bones.get(self.selected_bone_idx).map(|b| b.id);
+        let Some(command) = self.history.redo(&mut self.armature) else {
+            return;
+        };
+        self.reload_textures |= command.changes_textures();
+        self.fix_selections(active);
+    }
+
+    /// Undo or redo until `steps` commands are done, as if jumping to that point of the
+    /// history.
+    pub fn jump_history(&mut self, steps: usize) {
+        while self.history.undo_steps().len() > steps {
+            self.undo();
+        }
+        while self.history.undo_steps().len() < steps && !self.history.redo_steps().is_empty() {
+            self.redo();
+        }
+    }
+
+    // unselect what an undo or redo removed, and find the active bone (`active` being its
+    // id from before) wherever the rest of the bones left it
+    fn fix_selections(&mut self, active: Option<i32>) {
+        let bones = &self.armature.bones;
+        self.selected_bones.retain(|id| bones.iter().any(|b| b.id == *id));
+        let active = active.and_then(|id| bones.iter().position(|b| b.id == id));
+        self.selected_bone_idx = active.unwrap_or(usize::MAX);
+        if active.is_none() {
+            self.editing_bone = false;
+            if let Some(last) = self.selected_bone_idxs().last() {
+                self.add_to_selection(*last);
+            }
+        }
+        if self.ui.anim.selected >= self.armature.animations.len() {
+            self.ui.anim.selected = usize::MAX;
+            self.ui.anim.selected_frame = -1;
+        }
+        self.ui.selected_vertex = None;
     }
 
     /// place child bone underneath its parent
@@ -1136,7 +1421,3 @@ impl Ui {
         value
     }
 }
-
-fn default_neg_one() -> i32 {
-    -1
-}
@@ -0,0 +1,1520 @@
+//! Converting between armatures and Spine skeleton JSON, with its texture atlases.
+//!
+//! Spine units are pixels (`HARD_SCALE` of a world unit) and its rotations are in
+//! degrees. Attachments can be offset from their bone, which slots can't, so every
+//! imported slot gets a bone of its own to carry the offset, parented to the slot's
+//! bone.
+//!
+//! Both directions return warnings for anything that couldn't be converted, instead
+//! of leaving it out silently.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::runtime::*;
+
+/// Version of the JSON written by `export`.
+pub const SPINE_VERSION: &str = "4.1.00";
+
+// Spine only stores the fps of a skeleton as editor data, which isn't always exported
+const DEFAULT_FPS: i32 = 30;
+
+/// A region of a Spine texture atlas.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AtlasRegion {
+    pub name: String,
+    /// Index of the page (image) this is in.
+    pub page: usize,
+    /// Where the region is in its page, and its size before rotation.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// How far it was rotated when packed, counter-clockwise: 0, 90, 180 or 270 degrees.
+    pub degrees: u32,
+    /// Size of the image before its transparent edges were stripped.
+    pub orig_width: u32,
+    pub orig_height: u32,
+    /// Where the stripped image goes in the original, from its bottom left.
+    pub offset_x: u32,
+    pub offset_y: u32,
+}
+
+/// Parse a Spine (libGDX) texture atlas, returning the file names of its pages and
+/// its regions.
+///
+/// Both the format of Spine 4 (`bounds`, `offsets`) and older ones (`xy`, `size`,
+/// `orig`, `offset`) are read.
+pub fn parse_atlas(text: &str) -> Result<(Vec<String>, Vec<AtlasRegion>), String> {
+    let mut pages: Vec<String> = vec![];
+    let mut regions: Vec<AtlasRegion> = vec![];
+
+    // whether the next name starts a page, rather than a region
+    let mut in_page = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            in_page = false;
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            if !in_page {
+                pages.push(line.to_string());
+                in_page = true;
+            } else {
+                regions.push(AtlasRegion {
+                    name: line.to_string(),
+                    page: pages.len() - 1,
+                    ..Default::default()
+                });
+            }
+            continue;
+        };
+
+        // page fields (size, filter...) aren't needed
+        let Some(region) = regions.last_mut().filter(|r| r.page + 1 == pages.len()) else {
+            continue;
+        };
+        let numbers: Vec<u32> = value
+            .split(',')
+            .filter_map(|n| n.trim().parse().ok())
+            .collect();
+        let invalid = || format!("Invalid '{}' of atlas region '{}'", key, region.name);
+        match (key.trim(), numbers.as_slice()) {
+            ("bounds", [x, y, w, h]) => {
+                (region.x, region.y, region.width, region.height) = (*x, *y, *w, *h)
+            }
+            ("offsets", [x, y, w, h]) => {
+                (region.offset_x, region.offset_y) = (*x, *y);
+                (region.orig_width, region.orig_height) = (*w, *h);
+            }
+            ("xy", [x, y]) => (region.x, region.y) = (*x, *y),
+            ("size", [w, h]) => (region.width, region.height) = (*w, *h),
+            ("orig", [w, h]) => (region.orig_width, region.orig_height) = (*w, *h),
+            ("offset", [x, y]) => (region.offset_x, region.offset_y) = (*x, *y),
+            ("rotate", _) => match value.trim() {
+                "true" => region.degrees = 90,
+                "false" => region.degrees = 0,
+                _ => match numbers.as_slice() {
+                    [degrees @ (0 | 90 | 180 | 270)] => region.degrees = *degrees,
+                    _ => return Err(invalid()),
+                },
+            },
+            ("bounds" | "offsets" | "xy" | "size" | "orig" | "offset", _) => return Err(invalid()),
+            _ => {}
+        }
+    }
+
+    // without whitespace stripping, the original is the region itself
+    for region in &mut regions {
+        if region.orig_width == 0 && region.orig_height == 0 {
+            region.orig_width = region.width;
+            region.orig_height = region.height;
+        }
+    }
+
+    if pages.is_empty() {
+        return Err("The atlas doesn't have any pages.".to_string());
+    }
+    Ok((pages, regions))
+}
+
+/// Cut an atlas region out of its page, undoing its rotation and whitespace stripping.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn extract_region(page: &image::RgbaImage, region: &AtlasRegion) -> Option<Texture> {
+    let (packed_w, packed_h) = match region.degrees {
+        90 | 270 => (region.height, region.width),
+        _ => (region.width, region.height),
+    };
+    if region.x + packed_w > page.width() || region.y + packed_h > page.height() {
+        return None;
+    }
+
+    let mut packed =
+        image::imageops::crop_imm(page, region.x, region.y, packed_w, packed_h).to_image();
+    packed = match region.degrees {
+        90 => image::imageops::rotate90(&packed),
+        180 => image::imageops::rotate180(&packed),
+        270 => image::imageops::rotate270(&packed),
+        _ => packed,
+    };
+
+    let width = region.orig_width.max(region.width);
+    let height = region.orig_height.max(region.height);
+    let mut img = image::RgbaImage::new(width, height);
+    let top = height.saturating_sub(region.height + region.offset_y);
+    image::imageops::replace(&mut img, &packed, region.offset_x as i64, top as i64);
+
+    Some(Texture {
+        name: region.name.clone(),
+        size: Vec2::new(width as f32, height as f32),
+        pixels: img.into_raw().into(),
+        ..Default::default()
+    })
+}
+
+/// Convert Spine skeleton JSON to an armature, returning it with warnings about
+/// anything that was left out.
+///
+/// `textures` are the images that attachments can use, named after their paths
+/// (see `extract_region`). Spine 3.x and 4.x JSON are both read.
+pub fn import(json: &str, textures: &[Texture]) -> Result<(Armature, Vec<String>), String> {
+    let root: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let Some(spine_bones) = root["bones"].as_array() else {
+        return Err("That's not a Spine skeleton (it has no bones).".to_string());
+    };
+
+    let mut warnings: Vec<String> = vec![];
+    let mut armature = Armature {
+        textures: textures.to_vec(),
+        ..Default::default()
+    };
+
+    let version = root["skeleton"]["spine"].as_str().unwrap_or("");
+    let major: u32 = version
+        .split('.')
+        .next()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4);
+    let fps = root["skeleton"]["fps"]
+        .as_f64()
+        .map(|fps| fps.round() as i32)
+        .filter(|fps| *fps > 0)
+        .unwrap_or(DEFAULT_FPS);
+
+    // bones
+    let mut bone_ids: HashMap<String, i32> = HashMap::new();
+    for (i, spine_bone) in spine_bones.iter().enumerate() {
+        let name = str_of(spine_bone, "name");
+        let parent_id = match spine_bone["parent"].as_str() {
+            Some(parent) => match bone_ids.get(parent) {
+                Some(id) => *id,
+                None => return Err(format!("Bone '{}' has an unknown parent.", name)),
+            },
+            None => -1,
+        };
+        bone_ids.insert(name.clone(), i as i32);
+
+        let shear = num(spine_bone, "shearX", 0.) != 0. || num(spine_bone, "shearY", 0.) != 0.;
+        if shear {
+            warnings.push(format!("Bone '{}': shearing isn't supported.", name));
+        }
+        let inherit = spine_bone
+            .get("inherit")
+            .or(spine_bone.get("transform"))
+            .and_then(|mode| mode.as_str())
+            .unwrap_or("normal");
+        if inherit != "normal" {
+            warnings.push(format!(
+                "Bone '{}': only normal transform inheritance is supported, not '{}'.",
+                name, inherit
+            ));
+        }
+
+        armature.bones.push(Bone {
+            id: i as i32,
+            name,
+            parent_id,
+            pos: Vec2::new(num(spine_bone, "x", 0.), num(spine_bone, "y", 0.)) * HARD_SCALE,
+            rot: num(spine_bone, "rotation", 0.).to_radians(),
+            scale: Vec2::new(num(spine_bone, "scaleX", 1.), num(spine_bone, "scaleY", 1.)),
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        });
+    }
+
+    // attachments of the default skin, by slot name
+    let mut skin = &Value::Null;
+    let mut other_skins = vec![];
+    match &root["skins"] {
+        // 3.8 and later
+        Value::Array(skins) => {
+            for s in skins {
+                match str_of(s, "name").as_str() {
+                    "default" => skin = &s["attachments"],
+                    name => other_skins.push(name.to_string()),
+                }
+            }
+        }
+        // 3.7 and earlier
+        Value::Object(skins) => {
+            for (name, s) in skins {
+                match name.as_str() {
+                    "default" => skin = s,
+                    _ => other_skins.push(name.clone()),
+                }
+            }
+        }
+        _ => {}
+    }
+    if !other_skins.is_empty() {
+        warnings.push(format!(
+            "Only the default skin is imported, so these were left out: {}",
+            other_skins.join(", ")
+        ));
+    }
+
+    // slots, each with a bone carrying its attachment's offset
+    let spine_slots = root["slots"].as_array().map_or(&[][..], |s| &s[..]);
+    // index of each Spine slot in the armature's, if it was kept
+    let mut slot_idx: Vec<Option<usize>> = vec![];
+    for slot in spine_slots {
+        let name = str_of(slot, "name");
+        let Some(parent_id) = slot["bone"].as_str().and_then(|b| bone_ids.get(b)) else {
+            warnings.push(format!(
+                "Slot '{}' is on an unknown bone, so it was left out.",
+                name
+            ));
+            slot_idx.push(None);
+            continue;
+        };
+
+        let mut color = color_of(slot).unwrap_or_default();
+        if slot.get("dark").is_some() {
+            warnings.push(format!(
+                "Slot '{}': two color tinting isn't supported.",
+                name
+            ));
+        }
+        let blend = match slot["blend"].as_str().unwrap_or("normal") {
+            "additive" => BlendMode::Additive,
+            "multiply" => BlendMode::Multiply,
+            "screen" => BlendMode::Screen,
+            _ => BlendMode::Normal,
+        };
+
+        let mut bone = Bone {
+            id: armature.bones.len() as i32,
+            name: name.clone(),
+            parent_id: *parent_id,
+            scale: Vec2::new(1., 1.),
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        };
+        let mut tex_idx = -1;
+
+        let attachments = skin[&name].as_object();
+        let setup = slot["attachment"].as_str();
+        let others: Vec<&String> = attachments
+            .iter()
+            .flat_map(|a| a.keys())
+            .filter(|a| Some(a.as_str()) != setup)
+            .collect();
+        if !others.is_empty() {
+            let others: Vec<&str> = others.iter().map(|a| a.as_str()).collect();
+            warnings.push(format!(
+                "Slot '{}': only its setup attachment is imported, so these were left out: {}",
+                name,
+                others.join(", ")
+            ));
+        }
+
+        let attachment = setup.and_then(|a| Some((a, attachments?.get(a)?)));
+        if let Some((attachment_name, attachment)) = attachment {
+            let kind = attachment["type"].as_str().unwrap_or("region");
+            if kind != "region" {
+                warnings.push(format!(
+                    "Slot '{}': {} attachments aren't supported.",
+                    name, kind
+                ));
+            } else {
+                let path = attachment["path"].as_str().unwrap_or(attachment_name);
+                bone.pos =
+                    Vec2::new(num(attachment, "x", 0.), num(attachment, "y", 0.)) * HARD_SCALE;
+                bone.rot = num(attachment, "rotation", 0.).to_radians();
+                bone.scale =
+                    Vec2::new(num(attachment, "scaleX", 1.), num(attachment, "scaleY", 1.));
+                if let Some(tint) = color_of(attachment) {
+                    color = Color::new(
+                        color.r * tint.r,
+                        color.g * tint.g,
+                        color.b * tint.b,
+                        color.a * tint.a,
+                    );
+                }
+
+                match armature.textures.iter().position(|t| t.name == path) {
+                    Some(idx) => {
+                        // the attachment might be shown at a different size than its image
+                        let size = armature.textures[idx].size;
+                        if size.x > 0. && size.y > 0. {
+                            bone.scale.x *= num(attachment, "width", size.x) / size.x;
+                            bone.scale.y *= num(attachment, "height", size.y) / size.y;
+                        }
+                        tex_idx = idx as i32;
+                    }
+                    None => warnings.push(format!(
+                        "Slot '{}': the image '{}' wasn't found.",
+                        name, path
+                    )),
+                }
+            }
+        }
+
+        slot_idx.push(Some(armature.slots.len()));
+        armature.slots.push(Slot {
+            name,
+            bone_id: bone.id,
+            tex_idx,
+            color,
+            blend,
+            ..Default::default()
+        });
+        armature.bones.push(bone);
+    }
+
+    for (kind, what) in [
+        ("ik", "IK constraints"),
+        ("transform", "transform constraints"),
+        ("path", "path constraints"),
+        ("physics", "physics constraints"),
+        ("events", "events"),
+    ] {
+        let count = match &root[kind] {
+            Value::Array(items) => items.len(),
+            Value::Object(items) => items.len(),
+            _ => 0,
+        };
+        if count > 0 {
+            warnings.push(format!("{} {} were left out.", count, what));
+        }
+    }
+
+    // animations
+    let animations = root["animations"].as_object().into_iter().flatten();
+    for (anim_name, spine_anim) in animations {
+        let mut anim = Animation {
+            name: anim_name.clone(),
+            fps,
+            ..Default::default()
+        };
+        let mut timing = Timing::default();
+
+        let bone_timelines = spine_anim["bones"].as_object().into_iter().flatten();
+        for (bone_name, timelines) in bone_timelines {
+            let Some(&bone_id) = bone_ids.get(bone_name) else {
+                continue;
+            };
+            for (kind, keys) in timelines.as_object().into_iter().flatten() {
+                let Some(keys) = keys.as_array() else {
+                    continue;
+                };
+                // which elements the keys' values are for, and what values are missing as
+                let elements: &[(&str, AnimElement, f32)] = match kind.as_str() {
+                    "rotate" => &[("value", AnimElement::Rotation, 0.)],
+                    "translate" => &[
+                        ("x", AnimElement::PositionX, 0.),
+                        ("y", AnimElement::PositionY, 0.),
+                    ],
+                    "translatex" => &[("value", AnimElement::PositionX, 0.)],
+                    "translatey" => &[("value", AnimElement::PositionY, 0.)],
+                    "scale" => &[
+                        ("x", AnimElement::ScaleX, 1.),
+                        ("y", AnimElement::ScaleY, 1.),
+                    ],
+                    "scalex" => &[("value", AnimElement::ScaleX, 1.)],
+                    "scaley" => &[("value", AnimElement::ScaleY, 1.)],
+                    _ => {
+                        warnings.push(format!(
+                            "Animation '{}': {} keys of bone '{}' were left out.",
+                            anim_name, kind, bone_name
+                        ));
+                        continue;
+                    }
+                };
+                for (component, (field, element, default)) in elements.iter().enumerate() {
+                    let mut values: Vec<f32> = keys
+                        .iter()
+                        .map(|key| match *field {
+                            // 3.x called rotations angles
+                            "value" => key
+                                .get("value")
+                                .or(key.get("angle"))
+                                .and_then(|v| v.as_f64())
+                                .map_or(*default, |v| v as f32),
+                            _ => num(key, field, *default),
+                        })
+                        .collect();
+                    // 3.x takes the shortest way around between rotations
+                    if *element == AnimElement::Rotation && major < 4 {
+                        for i in 1..values.len() {
+                            let diff = values[i] - values[i - 1];
+                            values[i] = values[i - 1] + diff - 360. * (diff / 360.).round();
+                        }
+                    }
+                    let unit = match element {
+                        AnimElement::Rotation => 1_f32.to_radians(),
+                        AnimElement::PositionX | AnimElement::PositionY => HARD_SCALE,
+                        _ => 1.,
+                    };
+                    add_keys(
+                        &mut anim,
+                        &mut timing,
+                        bone_id,
+                        element.clone(),
+                        keys,
+                        &values,
+                        unit,
+                        CurveFormat::of(major, component),
+                    );
+                }
+            }
+        }
+
+        // 3.x spelled it in lowercase
+        let draw_order = spine_anim.get("drawOrder").or(spine_anim.get("draworder"));
+        for key in draw_order.and_then(|keys| keys.as_array()).into_iter().flatten() {
+            let order = draw_order_of(key, spine_slots, &slot_idx);
+            let exact = num(key, "time", 0.) * fps as f32;
+            let frame = exact.round() as i32;
+            if (exact - frame as f32).abs() > 0.01 {
+                timing.between_frames = true;
+            }
+            anim.keyframes.retain(|kf| {
+                let merged = kf.frame == frame && kf.element == AnimElement::DrawOrder;
+                timing.merged |= merged;
+                !merged
+            });
+            anim.keyframes.push(Keyframe {
+                frame,
+                bone_id: -1,
+                element: AnimElement::DrawOrder,
+                element_id: AnimElement::DrawOrder as i32,
+                draw_order: order,
+                ..Default::default()
+            });
+        }
+
+        for (kind, what) in [
+            ("slots", "slot keys (attachments and colors)"),
+            ("ik", "IK keys"),
+            ("transform", "transform constraint keys"),
+            ("path", "path constraint keys"),
+            ("physics", "physics keys"),
+            ("deform", "deform keys"),
+            ("attachments", "deform keys"),
+            ("events", "events"),
+        ] {
+            if spine_anim.get(kind).is_some_and(|v| !v.is_null()) {
+                warnings.push(format!(
+                    "Animation '{}': {} were left out.",
+                    anim_name, what
+                ));
+            }
+        }
+        if timing.between_frames {
+            warnings.push(format!(
+                "Animation '{}': keys between frames (at {} fps) were moved to the nearest one.",
+                anim_name, fps
+            ));
+        }
+        if timing.merged {
+            warnings.push(format!(
+                "Animation '{}': keys less than a frame apart were merged.",
+                anim_name
+            ));
+        }
+
+        anim.keyframes.sort_by_key(|kf| kf.frame);
+        armature.animations.push(anim);
+    }
+
+    Ok((armature, warnings))
+}
+
+/// Color of a slot or attachment, from its "rrggbbaa" hex.
+fn color_of(value: &Value) -> Option<Color> {
+    let hex = value["color"].as_str()?;
+    let channel = |i: usize| {
+        let byte = hex.get(i * 2..i * 2 + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        byte.map(|b| b as f32 / 255.)
+    };
+    Some(Color::new(
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        channel(3).unwrap_or(1.),
+    ))
+}
+
+/// Hex of a color, as "rrggbbaa".
+fn hex_of(color: Color) -> String {
+    let byte = |c: f32| (c.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "{:02x}{:02x}{:02x}{:02x}",
+        byte(color.r),
+        byte(color.g),
+        byte(color.b),
+        byte(color.a)
+    )
+}
+
+/// The draw order of a key of a draw order timeline, as indices of the armature's
+/// slots. `slot_idx` has the armature's index of each Spine slot, if it was kept.
+fn draw_order_of(key: &Value, spine_slots: &[Value], slot_idx: &[Option<usize>]) -> Vec<usize> {
+    let offsets: Vec<(usize, i64)> = key["offsets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|offset| {
+            let name = offset["slot"].as_str().unwrap_or("");
+            let slot = spine_slots.iter().position(|s| s["name"] == name)?;
+            Some((slot, offset["offset"].as_i64().unwrap_or(0)))
+        })
+        .collect();
+    apply_offsets(spine_slots.len(), &offsets)
+        .into_iter()
+        .filter_map(|spine_idx| slot_idx[spine_idx])
+        .collect()
+}
+
+/// A draw order from how far slots moved from the setup order, as (slot, offset)
+/// pairs. This is how both Spine and DragonBones key it. Slots that didn't move
+/// fill in the gaps, in their setup order.
+pub(crate) fn apply_offsets(count: usize, offsets: &[(usize, i64)]) -> Vec<usize> {
+    let mut offsets = offsets.to_vec();
+    offsets.sort_by_key(|(slot, _)| *slot);
+
+    let mut order: Vec<Option<usize>> = vec![None; count];
+    let mut unchanged = vec![];
+    let mut original = 0;
+    for (slot, offset) in offsets {
+        if slot >= count {
+            continue;
+        }
+        while original < slot {
+            unchanged.push(original);
+            original += 1;
+        }
+        let target = original as i64 + offset;
+        match order.get_mut(target.max(0) as usize) {
+            Some(pos) if pos.is_none() => *pos = Some(original),
+            _ => unchanged.push(original),
+        }
+        original += 1;
+    }
+    unchanged.extend(original..count);
+
+    // gaps are filled from the back, so the setup order is kept between them
+    for pos in order.iter_mut().rev() {
+        if pos.is_none() {
+            *pos = unchanged.pop();
+        }
+    }
+    order.into_iter().flatten().collect()
+}
+
+/// How far each slot moved in a draw order (see `apply_offsets`). Slots that stayed
+/// in place are left out.
+pub(crate) fn offsets_of(order: &[usize]) -> Vec<(usize, i64)> {
+    let mut offsets = vec![];
+    for original in 0..order.len() {
+        let Some(pos) = order.iter().position(|s| *s == original) else {
+            continue;
+        };
+        if pos != original {
+            offsets.push((original, pos as i64 - original as i64));
+        }
+    }
+    offsets
+}
+
+/// How curves of a key are laid out.
+#[derive(Clone, Copy)]
+enum CurveFormat {
+    /// Spine 3: from 0 to 1, shared by every value of the key.
+    Relative,
+    /// Spine 4: in seconds and values, 4 numbers for each value of the key.
+    Absolute { component: usize },
+}
+
+impl CurveFormat {
+    fn of(major: u32, component: usize) -> CurveFormat {
+        match major {
+            0..=3 => CurveFormat::Relative,
+            _ => CurveFormat::Absolute { component },
+        }
+    }
+}
+
+/// Rounding that happened while converting key times to frames.
+#[derive(Default)]
+struct Timing {
+    between_frames: bool,
+    merged: bool,
+}
+
+/// Add the keys of one element of a timeline to an animation.
+///
+/// `values` are in Spine's units, which `unit` converts from.
+#[allow(clippy::too_many_arguments)]
+fn add_keys(
+    anim: &mut Animation,
+    timing: &mut Timing,
+    bone_id: i32,
+    element: AnimElement,
+    keys: &[Value],
+    values: &[f32],
+    unit: f32,
+    format: CurveFormat,
+) {
+    let times: Vec<f32> = keys.iter().map(|key| num(key, "time", 0.)).collect();
+    let mut transition = Transition::Linear;
+    let mut handles = BezierHandles::default();
+
+    for (i, key) in keys.iter().enumerate() {
+        let exact = times[i] * anim.fps as f32;
+        let frame = exact.round() as i32;
+        if (exact - frame as f32).abs() > 0.01 {
+            timing.between_frames = true;
+        }
+
+        let kf = Keyframe {
+            frame,
+            bone_id,
+            element: element.clone(),
+            element_id: element.clone() as i32,
+            value: values[i] * unit,
+            transition: transition.clone(),
+            handles,
+            ..Default::default()
+        };
+        let existing = anim
+            .keyframes
+            .iter()
+            .position(|k| k.frame == frame && k.bone_id == bone_id && k.element == element);
+        match existing {
+            Some(idx) => {
+                anim.keyframes[idx] = kf;
+                timing.merged = true;
+            }
+            None => anim.keyframes.push(kf),
+        }
+
+        // a key's curve is how it goes to the next, which is where keyframes keep it
+        if i + 1 == keys.len() {
+            break;
+        }
+        let span = (times[i + 1] - times[i], values[i + 1] - values[i]);
+        (transition, handles) = curve_of(key, format, (times[i], values[i]), span);
+    }
+}
+
+/// Get the transition for the curve of a key.
+///
+/// `start` and `span` are the time and value of the key, and how far the next one is.
+fn curve_of(
+    key: &Value,
+    format: CurveFormat,
+    start: (f32, f32),
+    span: (f32, f32),
+) -> (Transition, BezierHandles) {
+    let linear = (Transition::Linear, BezierHandles::default());
+    let points: Vec<f32> = match &key["curve"] {
+        Value::String(curve) if curve == "stepped" => {
+            return (Transition::Stepped, BezierHandles::default())
+        }
+        // 3.8 has the first number as the curve, and the others as c2, c3 and c4
+        Value::Number(cx1) => vec![
+            cx1.as_f64().unwrap_or(0.) as f32,
+            num(key, "c2", 0.),
+            num(key, "c3", 1.),
+            num(key, "c4", 1.),
+        ],
+        Value::Array(curve) => curve
+            .iter()
+            .map(|v| v.as_f64().unwrap_or(0.) as f32)
+            .collect(),
+        _ => return linear,
+    };
+
+    let (x1, y1, x2, y2) = match format {
+        CurveFormat::Relative => match points[..] {
+            [x1, y1, x2, y2, ..] => (x1, y1, x2, y2),
+            _ => return linear,
+        },
+        CurveFormat::Absolute { component } => {
+            let Some(&[cx1, cy1, cx2, cy2]) = points.get(component * 4..component * 4 + 4) else {
+                return linear;
+            };
+            // without any change in value, the curve's shape doesn't matter
+            if span.0 <= 0. || span.1 == 0. {
+                return linear;
+            }
+            (
+                (cx1 - start.0) / span.0,
+                (cy1 - start.1) / span.1,
+                (cx2 - start.0) / span.0,
+                (cy2 - start.1) / span.1,
+            )
+        }
+    };
+    (
+        Transition::Bezier,
+        BezierHandles {
+            start: Vec2::new(x1, y1),
+            end: Vec2::new(x2, y2),
+        },
+    )
+}
+
+/// Name of an atlas region for a texture, or one of its regions.
+pub fn region_name(tex: &Texture, region_idx: i32) -> String {
+    match tex.regions.get(region_idx as usize) {
+        Some(region) if region_idx >= 0 => format!("{}/{}", tex.name, region.name),
+        _ => tex.name.clone(),
+    }
+}
+
+/// Write the atlas for textures that have been packed (see `atlas::build`).
+///
+/// `pages` are the file names and sizes of the pages. Every texture is a region,
+/// and so is every region of one (see `region_name`).
+pub fn write_atlas(textures: &[Texture], pages: &[(String, (u32, u32))]) -> String {
+    let mut text = String::new();
+    for (p, (file, (width, height))) in pages.iter().enumerate() {
+        text += &format!(
+            "{}\nsize: {}, {}\nfilter: Linear, Linear\n",
+            file, width, height
+        );
+        for tex in textures.iter().filter(|tex| tex.page == p) {
+            let mut entries = vec![(tex.name.clone(), tex.offset, tex.size)];
+            for (i, region) in tex.regions.iter().enumerate() {
+                let name = region_name(tex, i as i32);
+                entries.push((name, tex.offset + region.offset, region.size));
+            }
+            for (name, offset, size) in entries {
+                text += &format!(
+                    "{}\nbounds: {}, {}, {}, {}\n",
+                    name, offset.x, offset.y, size.x, size.y
+                );
+            }
+        }
+        text += "\n";
+    }
+    text
+}
+
+/// Convert an armature to Spine JSON, returning it with warnings about anything
+/// that was left out.
+///
+/// Attachments use the atlas region names of `region_name`.
+pub fn export(armature: &Armature) -> (Value, Vec<String>) {
+    let mut warnings: Vec<String> = vec![];
+
+    // Spine needs unique names, and parents to come before their children
+    let mut names: Vec<String> = vec![];
+    let mut unique = |name: &str| {
+        let mut unique = if name.is_empty() { "bone" } else { name }.to_string();
+        let mut n = 1;
+        while names.contains(&unique) {
+            n += 1;
+            unique = format!("{}_{}", name, n);
+        }
+        names.push(unique.clone());
+        unique
+    };
+    let root_name = unique("root");
+    let mut bone_names: Vec<(i32, String)> = vec![];
+    let mut spine_bones = vec![json!({
+        "name": root_name,
+        "x": armature.pos.x / HARD_SCALE,
+        "y": armature.pos.y / HARD_SCALE,
+    })];
+    let mut added = vec![false; armature.bones.len()];
+    loop {
+        let mut progress = false;
+        for (i, bone) in armature.bones.iter().enumerate() {
+            let parent = bone_names.iter().find(|(id, _)| *id == bone.parent_id);
+            let has_parent = armature.bones.iter().any(|b| b.id == bone.parent_id);
+            if added[i] || (has_parent && parent.is_none()) {
+                continue;
+            }
+            let parent = parent.map_or(root_name.clone(), |(_, name)| name.clone());
+            let name = unique(&bone.name);
+            spine_bones.push(json!({
+                "name": name,
+                "parent": parent,
+                "x": bone.pos.x / HARD_SCALE,
+                "y": bone.pos.y / HARD_SCALE,
+                "rotation": bone.rot.to_degrees(),
+                "scaleX": bone.scale.x,
+                "scaleY": bone.scale.y,
+            }));
+            bone_names.push((bone.id, name));
+            added[i] = true;
+            progress = true;
+        }
+        if !progress {
+            break;
+        }
+    }
+    for (i, bone) in armature.bones.iter().enumerate() {
+        if !added[i] {
+            warnings.push(format!(
+                "Bone '{}' has cyclic parents, so it was left out.",
+                bone.name
+            ));
+        }
+    }
+    let name_of = |id: i32| bone_names.iter().find(|(i, _)| *i == id).map(|(_, n)| n);
+
+    // slots, in draw order
+    let mut slot_names: Vec<String> = vec![];
+    let mut slots = vec![];
+    let mut attachments = Map::new();
+    for slot in &armature.slots {
+        let bone = armature.bones.iter().find(|b| b.id == slot.bone_id);
+        let (Some(bone), Some(bone_name)) = (bone, name_of(slot.bone_id)) else {
+            warnings.push(format!(
+                "Slot '{}' is on a bone that was left out, so it was too.",
+                slot.name
+            ));
+            continue;
+        };
+        let base = if slot.name.is_empty() { "slot" } else { &slot.name };
+        let mut name = base.to_string();
+        let mut n = 1;
+        while slot_names.contains(&name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        slot_names.push(name.clone());
+
+        let mut spine_slot = json!({ "name": name, "bone": bone_name });
+        if slot.color != Color::WHITE {
+            spine_slot["color"] = json!(hex_of(slot.color));
+        }
+        if slot.blend != BlendMode::Normal {
+            spine_slot["blend"] = json!(slot.blend.to_string().to_lowercase());
+        }
+
+        if let Some(tex) = armature.textures.get(slot.tex_idx as usize) {
+            let warning = format!(
+                "Bone '{}': meshes aren't exported, so its whole texture is shown instead.",
+                bone.name
+            );
+            if bone.is_mesh && !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+
+            // attachments are centered on their offset, while textures sit on their pivot
+            let size = tex.region_size(slot.region_idx);
+            let region = region_name(tex, slot.region_idx);
+            spine_slot["attachment"] = json!(region);
+            attachments.insert(
+                name.clone(),
+                json!({
+                    region.clone(): {
+                        "x": (0.5 - bone.pivot.x) * size.x,
+                        "y": (bone.pivot.y - 0.5) * size.y,
+                        "width": size.x,
+                        "height": size.y,
+                    }
+                }),
+            );
+        }
+        slots.push(spine_slot);
+    }
+    // armature slot index of each Spine slot
+    let spine_slot_idx: Vec<usize> = armature
+        .slots
+        .iter()
+        .enumerate()
+        .filter(|(_, slot)| name_of(slot.bone_id).is_some())
+        .map(|(i, _)| i)
+        .collect();
+
+    if !armature.ik_constraints.is_empty() {
+        warnings.push(format!(
+            "{} IK constraints were left out.",
+            armature.ik_constraints.len()
+        ));
+    }
+
+    // animations
+    let mut approximated: Vec<Transition> = vec![];
+    let mut animations = Map::new();
+    for anim in &armature.animations {
+        let fps = anim.fps.max(1) as f32;
+        let mut bones = Map::new();
+        let mut left_out: Vec<String> = vec![];
+
+        for bone in &armature.bones {
+            let Some(name) = name_of(bone.id) else {
+                continue;
+            };
+            let mut timelines = Map::new();
+            for element in [
+                AnimElement::PositionX,
+                AnimElement::PositionY,
+                AnimElement::Rotation,
+                AnimElement::ScaleX,
+                AnimElement::ScaleY,
+            ] {
+                let keyframes: Vec<&Keyframe> = anim
+                    .keyframes
+                    .iter()
+                    .filter(|kf| kf.bone_id == bone.id && kf.element == element)
+                    .collect();
+                if keyframes.is_empty() {
+                    continue;
+                }
+                let (kind, unit) = match element {
+                    AnimElement::PositionX => ("translatex", HARD_SCALE),
+                    AnimElement::PositionY => ("translatey", HARD_SCALE),
+                    AnimElement::Rotation => ("rotate", 1_f32.to_radians()),
+                    AnimElement::ScaleX => ("scalex", 1.),
+                    _ => ("scaley", 1.),
+                };
+
+                let mut keys = vec![];
+                for (i, kf) in keyframes.iter().enumerate() {
+                    let time = kf.frame as f32 / fps;
+                    let value = kf.value / unit;
+                    let mut key = json!({ "time": time, "value": value });
+                    if let Some(next) = keyframes.get(i + 1) {
+                        let handles = match next.transition {
+                            Transition::Linear => None,
+                            Transition::Stepped => {
+                                key["curve"] = json!("stepped");
+                                None
+                            }
+                            Transition::Bezier => Some(next.handles),
+                            _ => match next.transition.approximate_bezier() {
+                                Some(handles) => {
+                                    if !approximated.contains(&next.transition) {
+                                        approximated.push(next.transition.clone());
+                                    }
+                                    Some(handles)
+                                }
+                                None => {
+                                    let warning = format!(
+                                        "Animation '{}': {} transitions aren't supported, \
+                                         so they were made linear.",
+                                        anim.name, next.transition
+                                    );
+                                    if !warnings.contains(&warning) {
+                                        warnings.push(warning);
+                                    }
+                                    None
+                                }
+                            },
+                        };
+                        // Spine's curves are in seconds and values, rather than from 0 to 1
+                        if let Some(h) = handles {
+                            let dt = next.frame as f32 / fps - time;
+                            let dv = next.value / unit - value;
+                            key["curve"] = json!([
+                                time + h.start.x * dt,
+                                value + h.start.y * dv,
+                                time + h.end.x * dt,
+                                value + h.end.y * dv,
+                            ]);
+                        }
+                    }
+                    keys.push(key);
+                }
+                timelines.insert(kind.to_string(), Value::Array(keys));
+            }
+            if !timelines.is_empty() {
+                bones.insert(name.clone(), Value::Object(timelines));
+            }
+        }
+
+        // Spine keys how far each slot moved from its setup order
+        let mut draw_order = vec![];
+        for kf in &anim.keyframes {
+            if kf.element != AnimElement::DrawOrder {
+                continue;
+            }
+            let order: Vec<usize> = kf
+                .draw_order
+                .iter()
+                .filter_map(|i| spine_slot_idx.iter().position(|s| s == i))
+                .collect();
+            let offsets: Vec<Value> = offsets_of(&order)
+                .into_iter()
+                .map(|(slot, offset)| json!({ "slot": slots[slot]["name"], "offset": offset }))
+                .collect();
+            draw_order.push(json!({ "time": kf.frame as f32 / fps, "offsets": offsets }));
+        }
+
+        for kf in &anim.keyframes {
+            let what = match kf.element {
+                AnimElement::PivotX | AnimElement::PivotY => "pivot",
+                AnimElement::IkMix => "IK",
+                AnimElement::Deform => "deform",
+                AnimElement::Attachment => "attachment",
+                _ => continue,
+            };
+            if !left_out.iter().any(|w| w == what) {
+                left_out.push(what.to_string());
+            }
+        }
+        if !left_out.is_empty() {
+            warnings.push(format!(
+                "Animation '{}': {} keyframes were left out.",
+                anim.name,
+                left_out.join(", ")
+            ));
+        }
+
+        let mut spine_anim = json!({ "bones": bones });
+        if !draw_order.is_empty() {
+            spine_anim["drawOrder"] = Value::Array(draw_order);
+        }
+        animations.insert(anim.name.clone(), spine_anim);
+    }
+    for transition in approximated {
+        warnings.push(format!(
+            "{} transitions were approximated with bezier curves.",
+            transition
+        ));
+    }
+
+    // Spine has the same fps for every animation, which is only used when editing
+    let fps = armature
+        .animations
+        .first()
+        .map_or(DEFAULT_FPS, |anim| anim.fps);
+    if armature.animations.iter().any(|anim| anim.fps != fps) {
+        warnings.push(format!(
+            "Animations have different fps, which Spine doesn't have, so it was set to {}.",
+            fps
+        ));
+    }
+
+    let skeleton = json!({
+        "skeleton": { "spine": SPINE_VERSION, "fps": fps, "images": "./" },
+        "bones": spine_bones,
+        "slots": slots,
+        "skins": [{ "name": "default", "attachments": attachments }],
+        "animations": animations,
+    });
+    (skeleton, warnings)
+}
+
+/// Read a Spine skeleton's JSON file, with its images.
+///
+/// Images are taken from the atlas next to it (`name.atlas` or `name.atlas.txt`) if
+/// there is one, or otherwise from separate files named after attachment paths.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_files(path: &str) -> Result<(Armature, Vec<String>), crate::error::FileError> {
+    use crate::error::FileError;
+    use std::path::Path;
+
+    let json = std::fs::read_to_string(path)?;
+    let path = Path::new(path);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let mut textures: Vec<Texture> = vec![];
+    let mut warnings: Vec<String> = vec![];
+    let atlas_path = [".atlas", ".atlas.txt"]
+        .iter()
+        .map(|ext| dir.join(format!("{}{}", stem, ext)))
+        .find(|p| p.exists());
+    if let Some(atlas_path) = atlas_path {
+        let (pages, regions) =
+            parse_atlas(&std::fs::read_to_string(atlas_path)?).map_err(FileError::Schema)?;
+        for (p, page) in pages.iter().enumerate() {
+            let img = image::open(dir.join(page))?.into_rgba8();
+            for region in regions.iter().filter(|r| r.page == p) {
+                match extract_region(&img, region) {
+                    Some(tex) => textures.push(tex),
+                    None => warnings.push(format!(
+                        "Atlas region '{}' lies outside of its page.",
+                        region.name
+                    )),
+                }
+            }
+        }
+    } else {
+        let root: Value = serde_json::from_str(&json)?;
+        let images = dir.join(root["skeleton"]["images"].as_str().unwrap_or(""));
+        for name in attachment_paths(&root) {
+            let Ok(img) = image::open(images.join(format!("{}.png", name))) else {
+                continue;
+            };
+            let img = img.into_rgba8();
+            textures.push(Texture {
+                name,
+                size: Vec2::new(img.width() as f32, img.height() as f32),
+                pixels: img.into_raw().into(),
+                ..Default::default()
+            });
+        }
+    }
+
+    let (mut armature, import_warnings) = import(&json, &textures).map_err(FileError::Schema)?;
+    armature.name = stem.to_string();
+    warnings.extend(import_warnings);
+    Ok((armature, warnings))
+}
+
+/// Paths of every region attachment, in every skin.
+#[cfg(not(target_arch = "wasm32"))]
+fn attachment_paths(root: &Value) -> Vec<String> {
+    let skins: Vec<&Value> = match &root["skins"] {
+        Value::Array(skins) => skins.iter().map(|s| &s["attachments"]).collect(),
+        Value::Object(skins) => skins.values().collect(),
+        _ => vec![],
+    };
+    let mut paths: Vec<String> = vec![];
+    for slot in skins
+        .iter()
+        .filter_map(|s| s.as_object())
+        .flat_map(|s| s.values())
+    {
+        for (name, attachment) in slot.as_object().into_iter().flatten() {
+            if attachment["type"].as_str().unwrap_or("region") != "region" {
+                continue;
+            }
+            let path = attachment["path"].as_str().unwrap_or(name).to_string();
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+/// Write an armature as Spine JSON to `path`, with its textures packed into an atlas
+/// next to it (`name.atlas`, and `name.png`, `name_2.png`... for its pages).
+///
+/// Returns warnings about anything that was left out.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_files(
+    path: &str,
+    armature: &Armature,
+    atlas_config: &crate::atlas::AtlasConfig,
+) -> Result<Vec<String>, crate::error::FileError> {
+    use std::path::Path;
+
+    let mut armature = armature.clone();
+    let pages = crate::atlas::build(&mut armature.textures, atlas_config);
+
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let page_files: Vec<(String, (u32, u32))> = pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| {
+            let file = match i {
+                0 => format!("{}.png", stem),
+                _ => format!("{}_{}.png", stem, i + 1),
+            };
+            (file, (page.width(), page.height()))
+        })
+        .collect();
+
+    let (skeleton, warnings) = export(&armature);
+    std::fs::write(path, serde_json::to_string_pretty(&skeleton)?)?;
+    std::fs::write(
+        path.with_extension("atlas"),
+        write_atlas(&armature.textures, &page_files),
+    )?;
+    for (page, (file, _)) in pages.iter().zip(&page_files) {
+        page.save_with_format(path.with_file_name(file), image::ImageFormat::Png)?;
+    }
+    Ok(warnings)
+}
+
+/// A number of a JSON object, or `default` if it's missing.
+pub(crate) fn num(value: &Value, key: &str, default: f32) -> f32 {
+    value[key].as_f64().map_or(default, |v| v as f32)
+}
+
+/// A string of a JSON object, or an empty one if it's missing.
+pub(crate) fn str_of(value: &Value, key: &str) -> String {
+    value[key].as_str().unwrap_or("").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::testing::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn offsets_give_back_their_draw_order() {
+        // every permutation of up to 5 slots
+        for count in 0..=5u32 {
+            for n in 0..count.pow(count) {
+                let order: Vec<usize> = (0..count)
+                    .map(|i| (n / count.pow(i) % count) as usize)
+                    .collect();
+                if (0..count as usize).any(|slot| !order.contains(&slot)) {
+                    continue;
+                }
+                assert_eq!(apply_offsets(order.len(), &offsets_of(&order)), order);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_atlas_regions_are_turned_back() {
+        let atlas = "page.png\nsize: 8, 8\nfilter: Linear, Linear\n\
+            a\nbounds: 0, 0, 3, 2\nrotate: true\n\
+            b\nbounds: 0, 0, 3, 2\nrotate: 180\n\
+            c\nbounds: 0, 0, 3, 2\nrotate: 270\n\
+            d\nbounds: 0, 0, 3, 2\nrotate: false\n";
+        let (pages, regions) = parse_atlas(atlas).unwrap();
+        assert_eq!(pages, ["page.png"]);
+        let degrees: Vec<u32> = regions.iter().map(|r| r.degrees).collect();
+        assert_eq!(degrees, [90, 180, 270, 0]);
+        assert!(parse_atlas(&atlas.replace("270", "45")).is_err());
+
+        let image = image::RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        for region in &regions {
+            // packed counter-clockwise
+            let page = match region.degrees {
+                90 => image::imageops::rotate270(&image),
+                180 => image::imageops::rotate180(&image),
+                270 => image::imageops::rotate90(&image),
+                _ => image.clone(),
+            };
+            let texture = extract_region(&page, region).unwrap();
+            assert_eq!(texture.size, Vec2::new(3., 2.));
+            assert!(texture.pixels[..] == image.as_raw()[..], "{}", region.name);
+        }
+    }
+
+    #[test]
+    fn spine_3_8_is_imported() {
+        let json = r#"{
+            "skeleton": { "spine": "3.8.99", "fps": 30 },
+            "bones": [
+                { "name": "root" },
+                { "name": "arm", "parent": "root", "x": 100, "y": 50, "rotation": 90, "shearX": 10 }
+            ],
+            "slots": [{ "name": "arm", "bone": "arm", "attachment": "arm" }],
+            "skins": [
+                { "name": "default", "attachments": {
+                    "arm": { "arm": { "x": 10, "width": 40, "height": 10 } }
+                } },
+                { "name": "alt", "attachments": {} }
+            ],
+            "animations": { "wave": { "bones": { "arm": {
+                "rotate": [
+                    { "angle": 170 },
+                    { "time": 1, "angle": -170, "curve": 0.25, "c3": 0.75 },
+                    { "time": 2, "angle": 0 }
+                ],
+                "translate": [
+                    { "time": 0.5, "x": 10, "y": -20, "curve": "stepped" },
+                    { "time": 1, "x": 20 }
+                ],
+                "shear": [{ "time": 0 }]
+            } } } }
+        }"#;
+        let texture = Texture {
+            name: "arm".to_string(),
+            size: Vec2::new(20., 10.),
+            ..Default::default()
+        };
+        let (armature, warnings) = import(json, &[texture]).unwrap();
+        assert_eq!(
+            warnings,
+            [
+                "Bone 'arm': shearing isn't supported.",
+                "Only the default skin is imported, so these were left out: alt",
+                "Animation 'wave': shear keys of bone 'arm' were left out.",
+            ]
+        );
+
+        let arm = &armature.bones[1];
+        assert_near(arm.pos, Vec2::new(0.5, 0.25));
+        assert!((arm.rot - FRAC_PI_2).abs() < 0.0001);
+        // the slot's bone carries the attachment's offset, and its stretch
+        let slot_bone = &armature.bones[2];
+        assert_eq!((slot_bone.parent_id, armature.slots[0].tex_idx), (1, 0));
+        assert_near(slot_bone.pos, Vec2::new(10. * HARD_SCALE, 0.));
+        assert_near(slot_bone.scale, Vec2::new(2., 1.));
+
+        let anim = &armature.animations[0];
+        assert_eq!(anim.fps, 30);
+        let deg = 1_f32.to_radians();
+        let bezier = Transition::Bezier;
+        assert_keys(
+            anim,
+            &[
+                (0, 1, AnimElement::Rotation, 170. * deg, Transition::Linear),
+                (
+                    15,
+                    1,
+                    AnimElement::PositionX,
+                    10. * HARD_SCALE,
+                    Transition::Linear,
+                ),
+                (
+                    15,
+                    1,
+                    AnimElement::PositionY,
+                    -20. * HARD_SCALE,
+                    Transition::Linear,
+                ),
+                (
+                    30,
+                    1,
+                    AnimElement::PositionX,
+                    20. * HARD_SCALE,
+                    Transition::Stepped,
+                ),
+                (30, 1, AnimElement::PositionY, 0., Transition::Stepped),
+                // the shortest way around, as 3.x turns
+                (30, 1, AnimElement::Rotation, 190. * deg, Transition::Linear),
+                (60, 1, AnimElement::Rotation, 360. * deg, bezier),
+            ],
+        );
+        let handles = anim
+            .keyframes
+            .iter()
+            .find(|kf| kf.frame == 60)
+            .unwrap()
+            .handles;
+        assert_near(handles.start, Vec2::new(0.25, 0.));
+        assert_near(handles.end, Vec2::new(0.75, 1.));
+    }
+
+    #[test]
+    fn spine_4_1_is_imported() {
+        let json = r#"{
+            "skeleton": { "spine": "4.1.24" },
+            "bones": [
+                { "name": "root" },
+                { "name": "leg", "parent": "root", "transform": "noRotationOrReflection" }
+            ],
+            "ik": [{ "name": "foot", "bones": ["leg"], "target": "root" }],
+            "animations": { "walk": { "bones": { "leg": {
+                "rotate": [
+                    { "curve": [0.333, 0, 0.667, 90] },
+                    { "time": 1, "value": 90 },
+                    { "time": 2, "value": -180 }
+                ],
+                "translatex": [{ "time": 0.51, "value": 40 }],
+                "scale": [{ "x": 2, "curve": "stepped" }, { "time": 1, "y": 3 }]
+            } } } }
+        }"#;
+        let (armature, warnings) = import(json, &[]).unwrap();
+        assert_eq!(
+            warnings,
+            [
+                "Bone 'leg': only normal transform inheritance is supported, not \
+                 'noRotationOrReflection'.",
+                "1 IK constraints were left out.",
+                "Animation 'walk': keys between frames (at 30 fps) were moved to the nearest one.",
+            ]
+        );
+
+        let anim = &armature.animations[0];
+        // without the skeleton's fps, Spine's default
+        assert_eq!(anim.fps, 30);
+        let deg = 1_f32.to_radians();
+        assert_keys(
+            anim,
+            &[
+                (0, 1, AnimElement::Rotation, 0., Transition::Linear),
+                (0, 1, AnimElement::ScaleX, 2., Transition::Linear),
+                (0, 1, AnimElement::ScaleY, 1., Transition::Linear),
+                (
+                    15,
+                    1,
+                    AnimElement::PositionX,
+                    40. * HARD_SCALE,
+                    Transition::Linear,
+                ),
+                (30, 1, AnimElement::Rotation, 90. * deg, Transition::Bezier),
+                (30, 1, AnimElement::ScaleX, 1., Transition::Stepped),
+                (30, 1, AnimElement::ScaleY, 3., Transition::Stepped),
+                // 4.x keeps turning the way it's keyed
+                (
+                    60,
+                    1,
+                    AnimElement::Rotation,
+                    -180. * deg,
+                    Transition::Linear,
+                ),
+            ],
+        );
+        let rotation = |kf: &&Keyframe| kf.frame == 30 && kf.element == AnimElement::Rotation;
+        let handles = anim.keyframes.iter().find(rotation).unwrap().handles;
+        assert_near(handles.start, Vec2::new(0.333, 0.));
+        assert_near(handles.end, Vec2::new(0.667, 1.));
+    }
+
+    #[test]
+    fn exported_armatures_import_the_same() {
+        let bone = |id, name: &str, parent_id, pos, rot, scale| Bone {
+            id,
+            name: name.to_string(),
+            parent_id,
+            pos,
+            rot,
+            scale,
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        };
+        let mut stepped = key(0, AnimElement::Rotation, 15, 1.);
+        stepped.transition = Transition::Stepped;
+        let mut curved = key(0, AnimElement::Rotation, 30, 0.5);
+        curved.transition = Transition::Bezier;
+        curved.handles = BezierHandles {
+            start: Vec2::new(0.25, 0.1),
+            end: Vec2::new(0.75, 0.9),
+        };
+        let armature = Armature {
+            bones: vec![
+                bone(0, "hip", -1, Vec2::new(0.5, 0.25), 0.3, Vec2::new(1., 2.)),
+                bone(1, "knee", 0, Vec2::new(0., -0.5), -1., Vec2::new(1., 1.)),
+            ],
+            animations: vec![Animation {
+                name: "walk".to_string(),
+                fps: 30,
+                keyframes: vec![
+                    key(0, AnimElement::Rotation, 0, 0.),
+                    key(1, AnimElement::PositionX, 0, 0.1),
+                    key(1, AnimElement::ScaleY, 10, 2.),
+                    stepped,
+                    curved.clone(),
+                    key(1, AnimElement::PositionX, 30, -0.1),
+                ],
+            }],
+            ..Default::default()
+        };
+
+        let (json, warnings) = export(&armature);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let (imported, warnings) = import(&json.to_string(), &[]).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        // under a root bone of their own
+        assert_eq!(imported.bones.len(), 3);
+        for (bone, og) in imported.bones[1..].iter().zip(&armature.bones) {
+            assert_eq!((&bone.name, bone.parent_id), (&og.name, og.parent_id + 1));
+            assert_near(bone.pos, og.pos);
+            assert!((bone.rot - og.rot).abs() < 0.0001);
+            assert_near(bone.scale, og.scale);
+        }
+
+        let mut expected: Vec<Key> = armature.animations[0]
+            .keyframes
+            .iter()
+            .map(|kf| {
+                let key = (kf.frame, kf.bone_id + 1, kf.element.clone());
+                (key.0, key.1, key.2, kf.value, kf.transition.clone())
+            })
+            .collect();
+        expected.sort_by(|a, b| (a.0, a.1, &a.2).cmp(&(b.0, b.1, &b.2)));
+        let anim = &imported.animations[0];
+        assert_eq!((&anim.name, anim.fps), (&"walk".to_string(), 30));
+        assert_keys(anim, &expected);
+        let rotation = |kf: &&Keyframe| kf.frame == 30 && kf.element == AnimElement::Rotation;
+        let handles = anim.keyframes.iter().find(rotation).unwrap().handles;
+        assert_near(handles.start, curved.handles.start);
+        assert_near(handles.end, curved.handles.end);
+    }
+}
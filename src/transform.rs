@@ -0,0 +1,161 @@
+//! 2D affine transforms, used to bring bones from local to world space.
+
+use std::ops::{Add, Mul};
+
+use crate::runtime::Vec2;
+
+/// A 3x2 affine matrix.
+///
+/// Laid out as:
+/// ```text
+/// | a  c  tx |
+/// | b  d  ty |
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        a: 1.,
+        b: 0.,
+        c: 0.,
+        d: 1.,
+        tx: 0.,
+        ty: 0.,
+    };
+
+    /// Build a transform that scales, then rotates, then translates.
+    pub fn from_trs(pos: Vec2, rot: f32, scale: Vec2) -> Transform {
+        let (sin, cos) = rot.sin_cos();
+        Transform {
+            a: cos * scale.x,
+            b: sin * scale.x,
+            c: -sin * scale.y,
+            d: cos * scale.y,
+            tx: pos.x,
+            ty: pos.y,
+        }
+    }
+
+    /// Transform a point (affected by translation).
+    pub fn apply(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            self.a * point.x + self.c * point.y + self.tx,
+            self.b * point.x + self.d * point.y + self.ty,
+        )
+    }
+
+    /// Transform a direction (unaffected by translation).
+    pub fn apply_vector(&self, vector: Vec2) -> Vec2 {
+        Vec2::new(
+            self.a * vector.x + self.c * vector.y,
+            self.b * vector.x + self.d * vector.y,
+        )
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// Returns `None` if the transform can't be inverted (eg. a scale of 0).
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+
+        let a = self.d / det;
+        let b = -self.b / det;
+        let c = -self.c / det;
+        let d = self.a / det;
+        Some(Transform {
+            a,
+            b,
+            c,
+            d,
+            tx: -(a * self.tx + c * self.ty),
+            ty: -(b * self.tx + d * self.ty),
+        })
+    }
+
+    pub fn translation(&self) -> Vec2 {
+        Vec2::new(self.tx, self.ty)
+    }
+
+    /// Rotation of the transform's X axis.
+    pub fn rotation(&self) -> f32 {
+        self.b.atan2(self.a)
+    }
+
+    /// Scale along the transform's axes.
+    ///
+    /// Y is negative if the transform is mirrored. Any shear is lost.
+    pub fn scale(&self) -> Vec2 {
+        let x = (self.a * self.a + self.b * self.b).sqrt();
+        if x == 0. {
+            return Vec2::new(0., (self.c * self.c + self.d * self.d).sqrt());
+        }
+        Vec2::new(x, self.determinant() / x)
+    }
+}
+
+/// `parent * child` applies `child` first, then `parent`.
+impl Mul for Transform {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            a: self.a * rhs.a + self.c * rhs.b,
+            b: self.b * rhs.a + self.d * rhs.b,
+            c: self.a * rhs.c + self.c * rhs.d,
+            d: self.b * rhs.c + self.d * rhs.d,
+            tx: self.a * rhs.tx + self.c * rhs.ty + self.tx,
+            ty: self.b * rhs.tx + self.d * rhs.ty + self.ty,
+        }
+    }
+}
+
+/// Component-wise, for blending transforms together.
+impl Mul<f32> for Transform {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: f32) -> Self {
+        Self {
+            a: self.a * rhs,
+            b: self.b * rhs,
+            c: self.c * rhs,
+            d: self.d * rhs,
+            tx: self.tx * rhs,
+            ty: self.ty * rhs,
+        }
+    }
+}
+
+/// Component-wise, for blending transforms together.
+impl Add for Transform {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            a: self.a + rhs.a,
+            b: self.b + rhs.b,
+            c: self.c + rhs.c,
+            d: self.d + rhs.d,
+            tx: self.tx + rhs.tx,
+            ty: self.ty + rhs.ty,
+        }
+    }
+}
@@ -17,6 +17,7 @@ macro_rules! ui_color {
 #[rustfmt::skip] ui_color!(COLOR_MAIN,               32, 25, 46);
 #[rustfmt::skip] ui_color!(COLOR_TEXT,               180, 180, 180);
 #[rustfmt::skip] ui_color!(COLOR_TEXT_SELECTED,      210, 210, 210);
+#[rustfmt::skip] ui_color!(COLOR_TEXT_FADED,         110, 110, 110);
 #[rustfmt::skip] ui_color!(COLOR_FRAMELINE,          80, 60, 130);
 #[rustfmt::skip] ui_color!(COLOR_FRAMELINE_HOVERED,  108, 80, 179);
 #[rustfmt::skip] ui_color!(COLOR_FRAMELINE_PASTLAST, 50, 41, 74);
@@ -63,10 +64,30 @@ pub fn draw(context: &Context, shared: &mut Shared) {
     if shared.ui.image_modal {
         modal_image(shared, context);
     }
+    if shared.ui.atlas_modal {
+        modal_atlas(shared, context);
+    }
+    if shared.ui.export_modal {
+        modal_export(shared, context);
+    }
+    if shared.ui.keymap_modal {
+        modal_keymap(shared, context);
+    }
 
     // close modals on pressing escape
     if shared.input.is_pressing(winit::keyboard::KeyCode::Escape) {
         shared.ui.image_modal = false;
+        shared.ui.atlas_modal = false;
+        shared.ui.export_modal = false;
+        shared.ui.keymap_modal = false;
+        shared.ui.rebinding = None;
+    }
+
+    // not while typing into a field, or picking a key to bind
+    if !context.wants_keyboard_input() && shared.ui.rebinding.is_none() {
+        for action in shared.keymap.pressed(context) {
+            action.run(shared);
+        }
     }
 
     //visualize_vertices(context, shared);
@@ -85,6 +106,8 @@ pub fn draw(context: &Context, shared: &mut Shared) {
             shared.input.initial_points = vec![];
         }
         shared.input.scroll = Vec2::new(i.raw_scroll_delta.x, i.raw_scroll_delta.y);
+        shared.input.modifiers = i.modifiers;
+        shared.input.right_clicked = i.pointer.secondary_clicked();
     });
 
     context.set_cursor_icon(shared.cursor_icon);
@@ -125,12 +148,20 @@ pub fn draw(context: &Context, shared: &mut Shared) {
         edit_mode_bar(context, shared);
     }
 
+    if shared.editing_mesh() {
+        mesh_editor::draw(context, shared);
+    }
+
     if shared.armature.bones.len() > 0 {
         animate_bar(context, shared);
     }
 
     camera_bar(context, shared);
 
+    if shared.ui.history_open {
+        history_panel::draw(context, shared);
+    }
+
     // check if mouse is on ui
     //
     // this check always returns false on mouse click, so it's only checked when the mouse isn't clicked
@@ -148,79 +179,28 @@ fn top_panel(egui_ctx: &Context, shared: &mut Shared) {
         })
         .show(egui_ctx, |ui| {
             egui::menu::bar(ui, |ui| {
-                let mut offset = 0.;
-                macro_rules! str {
-                    ($string:expr) => {
-                        $string.to_string()
-                    };
-                }
-                ui.menu_button("File", |ui| {
-                    if top_bar_button(ui, str!("Import"), str!("I"), &mut offset).clicked() {
-                        #[cfg(not(target_arch = "wasm32"))]
-                        utils::open_import_dialog();
-                        ui.close_menu();
-                    }
-                    if top_bar_button(ui, str!("Save"), str!("S"), &mut offset).clicked() {
-                        #[cfg(not(target_arch = "wasm32"))]
-                        utils::open_save_dialog();
-                        ui.close_menu();
-                    }
-                    if top_bar_button(ui, str!("Export Video"), str!("E"), &mut offset).clicked() {
-                        // check if ffmpeg exists and complain if it doesn't
-                        let mut ffmpeg = false;
-                        match std::process::Command::new("ffmpeg").arg("-version").output() {
-                            Ok(output) => {
-                                if output.status.success() {
-                                    ffmpeg = true;
-                                } else {
-                                    println!("ffmpeg command ran but returned an error:");
-                                }
+                use keymap::Action;
+
+                // every entry is an action, so it can also be run by its shortcut
+                let mut menu = |name: &str, actions: &[keymap::Action], ui: &mut egui::Ui| {
+                    ui.menu_button(name, |ui| {
+                        let mut offset = 0.;
+                        for action in actions {
+                            let shortcut = shared.keymap.text(*action);
+                            let label = action.label().to_string();
+                            if top_bar_button(ui, label, shortcut, &mut offset).clicked() {
+                                action.run(shared);
+                                ui.close_menu();
                             }
-                            Err(e) => {
-                                println!("Failed to run ffmpeg: {}", e);
-                                println!("Make sure ffmpeg is installed and in your $PATH.");
-                            }
-                        }
-                        if !ffmpeg {
-                            let headline = "ffmpeg is not available.\n\nPlease ensure it is installed and in your $PATH.";
-                            shared.ui.modal_headline = headline.to_string();
-                            return
                         }
-
-                        // complain if there's no proper animation to export
-                        if shared.ui.anim.selected == usize::MAX {
-                            if shared.armature.animations.len() == 0
-                                || shared.armature.animations[0].keyframes.len() == 0
-                            {
-                                shared.ui.modal_headline = "No animation available.".to_string();
-                                return;
-                            } else {
-                                shared.ui.anim.selected = 0;
-                            }
-                        } else if shared.last_keyframe() == None {
-                            shared.ui.modal_headline = "No animation available.".to_string();
-                            return;
-                        }
-                        shared.recording = true;
-                        shared.done_recording = true;
-                        shared.ui.anim.playing = true;
-                        shared.select_frame(0);
-                        shared.ui.anim.loops = 1;
-                        shared.ui.anim.elapsed = Some(std::time::Instant::now());
-                        ui.close_menu();
-                    }
-                });
-                offset = 0.;
-                ui.menu_button("View", |ui| {
-                    if top_bar_button(ui, str!("Zoom In"), str!("="), &mut offset).clicked() {
-                        set_zoom(shared.camera.zoom - 0.1, shared);
-                        ui.close_menu();
-                    }
-                    if top_bar_button(ui, str!("Zoom Out"), str!("-"), &mut offset).clicked() {
-                        set_zoom(shared.camera.zoom + 0.1, shared);
-                        ui.close_menu();
-                    }
-                });
+                    });
+                };
+                menu("File", &[
+                    Action::Import, Action::Save, Action::SaveAs, Action::ExportSpine,
+                    Action::ExportDragonBones, Action::AtlasSettings, Action::Export,
+                ], ui);
+                menu("Edit", &[Action::Undo, Action::Redo, Action::Shortcuts], ui);
+                menu("View", &[Action::ZoomIn, Action::ZoomOut, Action::History], ui);
                 shared.ui.edit_bar_pos.y = ui.min_rect().bottom();
                 shared.ui.animate_mode_bar_pos.y = ui.min_rect().bottom();
             });
@@ -250,6 +230,24 @@ fn edit_mode_bar(egui_ctx: &Context, shared: &mut Shared) {
                 edit_mode_button!("Move", EditMode::Move);
                 edit_mode_button!("Rotate", EditMode::Rotate);
                 edit_mode_button!("Scale", EditMode::Scale);
+                if shared.selected_bone().unwrap().is_mesh {
+                    edit_mode_button!("Mesh", EditMode::Mesh);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Pivot:");
+                egui::ComboBox::new("pivot", "")
+                    .selected_text(shared.pivot.to_string())
+                    .show_ui(ui, |ui| {
+                        for pivot in Pivot::ALL {
+                            ui.selectable_value(&mut shared.pivot, pivot, pivot.to_string());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "What the selected bones are rotated and scaled around. \
+                        Right click to place the 2D cursor",
+                    );
             });
         });
 }
@@ -427,7 +425,9 @@ pub fn polar_dialog(shared: &mut Shared, ctx: &egui::Context) {
                                 }
                             }
                         }
-                        shared.selected_bone_idx = usize::MAX;
+                        shared.unselect_everything();
+                    } else if shared.ui.polar_id == "delete_armature" {
+                        shared.remove_armature(shared.armature_idx);
                     } else if shared.ui.polar_id == "exiting" {
                         shared.ui.exiting = true;
                     }
@@ -547,7 +547,7 @@ pub fn modal_image(shared: &mut Shared, ctx: &egui::Context) {
                         // stop the loop to prevent index errors
                         break;
                     } else {
-                        shared.selected_bone_mut().unwrap().tex_idx = i as i32;
+                        shared.set_attachment(i as i32, -1);
                         shared.ui.image_modal = false;
                     }
                 }
@@ -563,6 +563,243 @@ pub fn modal_image(shared: &mut Shared, ctx: &egui::Context) {
         });
 }
 
+/// Settings for how textures are packed on save.
+pub fn modal_atlas(shared: &mut Shared, ctx: &egui::Context) {
+    egui::Modal::new("atlas".into())
+        .frame(egui::Frame {
+            corner_radius: 0.into(),
+            fill: COLOR_MAIN,
+            inner_margin: egui::Margin::same(5),
+            stroke: egui::Stroke::new(1., COLOR_ACCENT),
+            ..Default::default()
+        })
+        .show(ctx, |ui| {
+            ui.set_width(250.);
+            ui.heading("Atlas Settings");
+
+            modal_x(ui, || {
+                shared.ui.atlas_modal = false;
+            });
+
+            let atlas = &mut shared.atlas;
+            egui::Grid::new("atlas_settings").show(ui, |ui| {
+                ui.label("Max Page Size");
+                ui.add(egui::DragValue::new(&mut atlas.max_size).range(64..=16384));
+                ui.end_row();
+                ui.label("Padding");
+                ui.add(egui::DragValue::new(&mut atlas.padding).range(0..=64));
+                ui.end_row();
+                ui.label("Extrusion");
+                ui.add(egui::DragValue::new(&mut atlas.extrude).range(0..=16));
+                ui.end_row();
+            });
+        });
+}
+
+/// Settings for exporting the selected animation, and the button to start it.
+pub fn modal_export(shared: &mut Shared, ctx: &egui::Context) {
+    egui::Modal::new("export".into())
+        .frame(egui::Frame {
+            corner_radius: 0.into(),
+            fill: COLOR_MAIN,
+            inner_margin: egui::Margin::same(5),
+            stroke: egui::Stroke::new(1., COLOR_ACCENT),
+            ..Default::default()
+        })
+        .show(ctx, |ui| {
+            ui.set_width(250.);
+            ui.heading("Export");
+
+            modal_x(ui, || {
+                shared.ui.export_modal = false;
+            });
+
+            let settings = &mut shared.export;
+            egui::Grid::new("export_settings").show(ui, |ui| {
+                ui.label("Format");
+                let format = settings.format;
+                egui::ComboBox::new("export_format", "")
+                    .selected_text(format.name())
+                    .show_ui(ui, |ui| {
+                        for f in export::ExportFormat::ALL {
+                            ui.selectable_value(&mut settings.format, f, f.name());
+                        }
+                    });
+                // keep the extension in line with the format
+                if settings.format != format {
+                    settings.path = std::path::Path::new(&settings.path)
+                        .with_extension(settings.format.extension())
+                        .to_string_lossy()
+                        .to_string();
+                }
+                ui.end_row();
+                ui.label("Path");
+                ui.text_edit_singleline(&mut settings.path);
+                ui.end_row();
+                ui.label("FPS");
+                ui.add(egui::DragValue::new(&mut settings.fps).range(1..=240));
+                ui.end_row();
+                ui.label("Start Frame");
+                ui.add(egui::DragValue::new(&mut settings.start).range(0..=i32::MAX));
+                ui.end_row();
+                ui.label("End Frame");
+                ui.add(egui::DragValue::new(&mut settings.end).range(-1..=i32::MAX))
+                    .on_hover_text("-1 exports up to the last keyframe");
+                ui.end_row();
+                ui.label("Resolution");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut settings.width).range(1..=8192));
+                    ui.label("x");
+                    ui.add(egui::DragValue::new(&mut settings.height).range(1..=8192));
+                });
+                ui.end_row();
+                ui.label("Background");
+                ui.checkbox(&mut settings.transparent, "Transparent");
+                ui.end_row();
+            });
+
+            ui.add_space(10.);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                if button("Export", ui).clicked() {
+                    shared.ui.export_modal = false;
+                    start_export(shared);
+                }
+            });
+        });
+}
+
+pub fn modal_keymap(shared: &mut Shared, ctx: &egui::Context) {
+    // the next key pressed (other than escape) is bound
+    if let Some(action) = shared.ui.rebinding {
+        let pressed = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if *key != egui::Key::Escape => Some(egui::KeyboardShortcut::new(
+                    keymap::shortcut_modifiers(*modifiers),
+                    *key,
+                )),
+                _ => None,
+            })
+        });
+        if let Some(shortcut) = pressed {
+            // the other action is left without a shortcut, so say which one it was
+            shared.ui.keymap_note = match shared.keymap.set(action, Some(shortcut)) {
+                Some(other) => format!(
+                    "{} was unbound from {}.",
+                    keymap::format_shortcut(shortcut),
+                    other.label()
+                ),
+                None => "".to_string(),
+            };
+            shared.ui.rebinding = None;
+            save_keymap(shared);
+        }
+    }
+
+    egui::Modal::new("keymap".into())
+        .frame(egui::Frame {
+            corner_radius: 0.into(),
+            fill: COLOR_MAIN,
+            inner_margin: egui::Margin::same(5),
+            stroke: egui::Stroke::new(1., COLOR_ACCENT),
+            ..Default::default()
+        })
+        .show(ctx, |ui| {
+            ui.set_width(300.);
+            ui.heading("Keyboard Shortcuts");
+
+            modal_x(ui, || {
+                shared.ui.keymap_modal = false;
+                shared.ui.rebinding = None;
+                shared.ui.keymap_note = "".to_string();
+            });
+            if !shared.ui.keymap_note.is_empty() {
+                ui.label(&shared.ui.keymap_note);
+            }
+
+            let mut edited = false;
+            egui::Grid::new("keymap").show(ui, |ui| {
+                for action in keymap::Action::ALL {
+                    ui.label(action.label());
+                    let text = if shared.ui.rebinding == Some(action) {
+                        "Press a key...".to_string()
+                    } else if shared.keymap.get(action).is_none() {
+                        "None".to_string()
+                    } else {
+                        shared.keymap.text(action)
+                    };
+                    if button(&text, ui).clicked() {
+                        shared.ui.rebinding = Some(action);
+                        shared.ui.keymap_note = "".to_string();
+                    }
+                    if shared.keymap.get(action).is_some() && button("Clear", ui).clicked() {
+                        shared.keymap.set(action, None);
+                        shared.ui.keymap_note = "".to_string();
+                        edited = true;
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.add_space(10.);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                if button("Reset to Defaults", ui).clicked() {
+                    shared.keymap = keymap::Keymap::default();
+                    shared.ui.rebinding = None;
+                    shared.ui.keymap_note = "".to_string();
+                    edited = true;
+                }
+            });
+            if edited {
+                save_keymap(shared);
+            }
+        });
+}
+
+// keep rebound shortcuts for next time
+fn save_keymap(shared: &mut Shared) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = shared.keymap.save() {
+        shared.ui.modal_headline = err;
+    }
+}
+
+/// Check that the selected animation can be exported, and have it exported on the next render.
+fn start_export(shared: &mut Shared) {
+    // check if ffmpeg exists and complain if it doesn't
+    if shared.export.format == export::ExportFormat::Ffmpeg {
+        let version = std::process::Command::new("ffmpeg")
+            .arg("-version")
+            .output();
+        if !version.is_ok_and(|output| output.status.success()) {
+            let headline =
+                "ffmpeg is not available.\n\nPlease ensure it is installed and in your $PATH.";
+            shared.ui.modal_headline = headline.to_string();
+            return;
+        }
+    }
+
+    // complain if there's no proper animation to export
+    if shared.ui.anim.selected == usize::MAX {
+        if shared.armature.animations.is_empty()
+            || shared.armature.animations[0].keyframes.is_empty()
+        {
+            shared.ui.modal_headline = "No animation available.".to_string();
+            return;
+        } else {
+            shared.ui.anim.selected = 0;
+        }
+    } else if shared.last_keyframe().is_none() {
+        shared.ui.modal_headline = "No animation available.".to_string();
+        return;
+    }
+    shared.recording = true;
+}
+
 pub fn top_bar_button(
     ui: &mut egui::Ui,
     text: String,
@@ -572,7 +809,7 @@ pub fn top_bar_button(
     let height = 20.;
     let rect = egui::Rect::from_min_size(
         egui::Pos2::new(ui.min_rect().left(), ui.min_rect().top() + *offset),
-        egui::Vec2::new(100., height),
+        egui::Vec2::new(200., height),
     );
     let response: egui::Response = ui.allocate_rect(rect, egui::Sense::click());
     let painter = ui.painter_at(ui.min_rect());
@@ -2,6 +2,9 @@
 
 use crate::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::FileError;
+
 #[cfg(not(target_arch = "wasm32"))]
 use std::io::Read;
 
@@ -29,71 +32,53 @@ pub fn world_to_screen_space(pos: Vec2, window: Vec2, zoom: f32, use_aspect_rati
     vec2
 }
 
-/// Rotate a point via rotation matrix.
-pub fn rotate(point: &Vec2, rot: f32) -> Vec2 {
-    Vec2 {
-        x: point.x * rot.cos() - point.y * rot.sin(),
-        y: point.x * rot.sin() + point.y * rot.cos(),
-    }
-}
+pub use crate::runtime::rotate;
 
 /// Return the angle that the source would need to look at target.
 pub fn look_at(source: &Vec2, target: &Vec2) -> f32 {
     f32::atan2(-(target.x - source.x), target.y - source.y)
 }
 
-/// Check if a point is in a rectangle (formed by vertices).
-pub fn in_bounding_box(
-    point: &Vec2,
-    verts: &Vec<Vertex>,
-    window_size: &Vec2,
-) -> (Vec<Vertex>, bool) {
-    // get the bound based on infinitely-long lines
-    let mut top = -f32::INFINITY;
-    let mut bot = f32::INFINITY;
-    let mut left = f32::INFINITY;
-    let mut right = -f32::INFINITY;
-    for v in verts {
-        left = f32::min(left, v.pos.x);
-        right = f32::max(right, v.pos.x);
-        bot = f32::min(bot, v.pos.y);
-        top = f32::max(top, v.pos.y);
-    }
-
-    let vertices: Vec<Vertex> = vec![
-        Vertex {
-            pos: Vec2::new(right, top),
-            uv: Vec2::new(1., 0.),
-        },
-        Vertex {
-            pos: Vec2::new(left, top),
-            uv: Vec2::new(0., 1.),
-        },
-        Vertex {
-            pos: Vec2::new(left, bot),
-            uv: Vec2::new(0., 0.),
-        },
-        Vertex {
-            pos: Vec2::new(right, bot),
-            uv: Vec2::new(1., 1.),
-        },
-    ];
-
-    // convert bound positions to screen space
-    let half = Vec2 {
-        x: window_size.x / 2.,
-        y: window_size.y / 2.,
+/// Check if a world-space point lands on a bone's texture, using the bone's world transform.
+pub fn in_bone(point: Vec2, bone: &Bone, tex_size: Vec2, hard_scale: f32) -> bool {
+    // bring the point into the bone's space, where the texture is axis-aligned
+    let local = match bone.world.inverse() {
+        Some(inverse) => inverse.apply(point),
+        None => return false,
     };
-    top = half.y - (half.y * top);
-    bot = half.y - (half.y * bot);
-    left = half.x + (half.x * left);
-    right = half.x + (half.x * right);
-
-    // finally, check if point is inside
-    (
-        vertices,
-        point.y > top && point.y < bot && point.x > left && point.x < right,
-    )
+
+    let size = tex_size * hard_scale;
+    let left = -size.x * bone.pivot.x;
+    let top = size.y * bone.pivot.y;
+
+    local.x > left && local.x < left + size.x && local.y < top && local.y > top - size.y
+}
+
+/// Check if a point lands on any triangle of a mesh.
+pub fn in_mesh(point: Vec2, verts: &[Vec2], indices: &[u32]) -> bool {
+    indices.chunks_exact(3).any(|tri| {
+        in_triangle(
+            point,
+            verts[tri[0] as usize],
+            verts[tri[1] as usize],
+            verts[tri[2] as usize],
+        )
+    })
+}
+
+/// Check if a point is in a triangle, regardless of its winding.
+pub fn in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let d1 = cross(b - a, point - a);
+    let d2 = cross(c - b, point - b);
+    let d3 = cross(a - c, point - c);
+    let has_neg = d1 < 0. || d2 < 0. || d3 < 0.;
+    let has_pos = d1 > 0. || d2 > 0. || d3 > 0.;
+    !(has_neg && has_pos)
+}
+
+/// 2D cross product (Z of the 3D one).
+pub fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
 }
 
 pub fn to_vec2(f: f32) -> Vec2 {
@@ -124,105 +109,262 @@ pub fn open_import_dialog() {
     });
 }
 
+/// Save dialog for exporting to another animator's format, with only its files shown.
+///
+/// The name picked gets the format's suffix if it doesn't have it.
 #[cfg(not(target_arch = "wasm32"))]
-pub fn save(path: String, shared: &mut Shared) {
-    // get the image size in advance
-    let mut size = Vec2::default();
-    for tex in &shared.armature.textures {
-        size.x += tex.size.x;
-        if tex.size.y > size.y {
-            size.y = tex.size.y;
+pub fn open_export_dialog(format: Format) {
+    std::thread::spawn(move || {
+        let task = rfd::FileDialog::new()
+            .add_filter(format.name(), &["json"])
+            .save_file();
+        let Some(path) = task else {
+            return;
+        };
+        let mut path = path.to_str().unwrap().to_string();
+        if format_of(&path) != format {
+            path = path.trim_end_matches(".json").to_string() + format.suffix();
         }
-    }
+        file_reader::create_temp_file(TEMP_SAVE_PATH, &path);
+    });
+}
+
+/// File formats that armatures can be saved as and imported from.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Format {
+    Skf,
+    /// Spine skeleton JSON, with its atlas (see `spine`).
+    Spine,
+    /// DragonBones JSON, with its atlases (see `dragonbones`).
+    DragonBones,
+}
 
-    if size != Vec2::ZERO {
-        create_temp_tex_sheet(shared, &size);
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Skf => "SkelForm",
+            Format::Spine => "Spine JSON",
+            Format::DragonBones => "DragonBones JSON",
+        }
     }
 
-    let img_data = std::fs::read("./temp.png");
+    /// End of the names of files in this format.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Format::Skf => ".skf",
+            Format::Spine => ".json",
+            Format::DragonBones => "_ske.json",
+        }
+    }
+}
 
-    // clone armature and make some edits, then serialize it
-    let mut armature_copy = shared.armature.clone();
+/// Format of a file, going by its name.
+pub fn format_of(path: &str) -> Format {
+    let path = path.to_lowercase();
+    if path.ends_with(Format::DragonBones.suffix()) {
+        Format::DragonBones
+    } else if path.ends_with(Format::Spine.suffix()) {
+        Format::Spine
+    } else {
+        Format::Skf
+    }
+}
 
-    // if bone isn't a mesh (ie is a simple rect), then empty the vertices
-    for bone in &mut armature_copy.bones {
-        if !bone.is_mesh {
-            bone.vertices = vec![];
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(path: String, shared: &mut Shared) {
+    let armatures: Vec<Armature> = shared.project_armatures().into_iter().cloned().collect();
+    let result = match format_of(&path) {
+        Format::Skf => write_skf(&path, &armatures, &shared.atlas).map(|_| vec![]),
+        // Spine only has room for the armature being edited
+        Format::Spine => {
+            spine::write_files(&path, &shared.armature, &shared.atlas).map(|mut warnings| {
+                if armatures.len() > 1 {
+                    let only = format!("Only '{}' was exported.", shared.armature.name);
+                    warnings.insert(0, only);
+                }
+                warnings
+            })
         }
+        Format::DragonBones => dragonbones::write_files(&path, &armatures, &shared.atlas),
+    };
+    match result {
+        Ok(warnings) if !warnings.is_empty() => {
+            shared.ui.modal_headline = format!(
+                "Saved {}, but some of it was left out:\n\n{}",
+                path,
+                warnings.join("\n")
+            );
+        }
+        Ok(_) => {}
+        Err(err) => shared.ui.modal_headline = format!("Couldn't save {}\n\n{}", path, err),
+    }
+}
+
+/// Write armatures (and their textures, all packed into the same atlas pages)
+/// to a `.skf` file.
+///
+/// Returns how many texture pages were written. On failure, an existing file at `path`
+/// is left as it was.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_skf(
+    path: &str,
+    armatures: &[Armature],
+    atlas_config: &atlas::AtlasConfig,
+) -> Result<usize, FileError> {
+    // clone armatures and make some edits, then serialize them
+    let mut armatures_copy = armatures.to_vec();
+
+    // pack textures first, so that the saved armatures have their atlas offsets
+    let mut textures: Vec<Texture> = armatures_copy
+        .iter_mut()
+        .flat_map(|armature| std::mem::take(&mut armature.textures))
+        .collect();
+    let pages = atlas::build(&mut textures, atlas_config);
+    let mut textures = textures.into_iter();
+    for (armature, og) in armatures_copy.iter_mut().zip(armatures) {
+        armature.textures = textures.by_ref().take(og.textures.len()).collect();
     }
 
-    // assign element_id to armature
-    for anim in &mut armature_copy.animations {
-        for kf in &mut anim.keyframes {
-            kf.element_id = kf.element.clone() as i32;
+    for armature in &mut armatures_copy {
+        // if bone isn't a mesh (ie is a simple rect), then empty the vertices
+        for bone in &mut armature.bones {
+            if !bone.is_mesh {
+                bone.vertices = vec![];
+            }
+        }
+
+        // assign element_id to armature
+        for anim in &mut armature.animations {
+            for kf in &mut anim.keyframes {
+                kf.element_id = kf.element.clone() as i32;
+            }
         }
     }
 
+    let texture_size = match pages.first() {
+        Some(page) => Vec2::new(page.width() as f32, page.height() as f32),
+        None => Vec2::ZERO,
+    };
     let root = Root {
-        armatures: vec![armature_copy],
-        texture_size: size,
+        version: migrate::FORMAT_VERSION,
+        armatures: armatures_copy,
+        texture_size,
+        atlas: *atlas_config,
     };
 
-    let armatures_json = serde_json::to_string(&root).unwrap();
+    let armatures_json = serde_json::to_string(&root)?;
 
-    // create zip file
-    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    // create zip in memory, so nothing is written unless all of it succeeded
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
     let options =
         zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
 
-    // save armature json and texture image
-    zip.start_file("armature.json", options).unwrap();
-    zip.write(armatures_json.as_bytes()).unwrap();
-    if size != Vec2::ZERO {
-        zip.start_file("textures.png", options).unwrap();
-        if let Ok(ref img) = img_data {
-            zip.write(&img.to_vec()).unwrap();
-        }
+    // save armature json and texture pages
+    zip.start_file("armature.json", options)?;
+    zip.write_all(armatures_json.as_bytes())?;
+    for (i, page) in pages.iter().enumerate() {
+        let mut png = std::io::Cursor::new(vec![]);
+        page.write_to(&mut png, image::ImageFormat::Png)?;
+        zip.start_file(atlas::page_file(i), options)?;
+        zip.write_all(png.get_ref())?;
     }
 
-    zip.finish().unwrap();
+    let bytes = zip.finish()?.into_inner();
 
-    if let Ok(_) = img_data {
-        std::fs::remove_file("temp.png").unwrap();
-    }
+    // write next to the old file and swap it in, as writing over it would truncate it first
+    let temp = format!("{}.tmp", path);
+    std::fs::write(&temp, bytes)
+        .and_then(|_| std::fs::rename(&temp, path))
+        .inspect_err(|_| _ = std::fs::remove_file(&temp))?;
+    Ok(pages.len())
 }
 
-fn create_temp_tex_sheet(shared: &mut Shared, size: &Vec2) {
-    // this is the buffer that will be saved as an image
-    let mut final_buf = <image::ImageBuffer<image::Rgba<u8>, _>>::new(size.x as u32, size.y as u32);
-
-    let mut offset: u32 = 0;
-    for tex in &mut shared.armature.textures {
-        // get current texture as a buffer
-        let img_buf = <image::ImageBuffer<image::Rgba<u8>, _>>::from_raw(
-            tex.size.x as u32,
-            tex.size.y as u32,
-            tex.pixels.clone(),
-        )
-        .unwrap();
+/// Read a `.skf` file, with the pixels of every texture loaded.
+///
+/// Files from older versions of the format are upgraded (see `migrate`). The armature isn't validated (see `runtime::validate`).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_skf(path: &str) -> Result<Root, FileError> {
+    let file = std::fs::File::open(path)?;
+    let mut zip = zip::ZipArchive::new(file)?;
 
-        // add it to the final buffer
-        for x in 0..img_buf.width() {
-            for y in 0..img_buf.height() {
-                final_buf.put_pixel(x + offset, y, *img_buf.get_pixel(x, y));
-            }
+    // load armature
+    let armature_file = zip.by_name("armature.json").map_err(|err| match err {
+        zip::result::ZipError::FileNotFound => {
+            FileError::Schema("That's not a SkelForm armature! (armature.json is missing)".into())
+        }
+        _ => err.into(),
+    })?;
+    let mut json: serde_json::Value = serde_json::from_reader(armature_file)?;
+    migrate::migrate(&mut json).map_err(FileError::Schema)?;
+    let mut root: crate::Root = serde_json::from_value(json)?;
+
+    if root.armatures.is_empty() {
+        return Err(FileError::Schema("The file doesn't have an armature.".into()));
+    }
+    // load textures, from whichever page they were packed into
+    let mut pages: Vec<Option<image::DynamicImage>> = vec![];
+    for texture in root.armatures.iter_mut().flat_map(|a| &mut a.textures) {
+        if zip.index_for_name(&atlas::page_file(texture.page)).is_none() {
+            return Err(FileError::Schema(format!(
+                "Texture '{}' is on page {}, which isn't in the file.",
+                texture.name, texture.page
+            )));
+        }
+        if pages.len() <= texture.page {
+            pages.resize(texture.page + 1, None);
+        }
+        if pages[texture.page].is_none() {
+            let mut bytes = vec![];
+            zip.by_name(&atlas::page_file(texture.page))?
+                .read_to_end(&mut bytes)?;
+            pages[texture.page] = Some(image::load_from_memory(&bytes)?);
         }
 
-        tex.offset.x = offset as f32;
-
-        // make sure the next texture will be added beside this one, instead of overwriting
-        offset += img_buf.width();
+        let page = pages[texture.page].as_ref().unwrap();
+        let (x, y) = (texture.offset.x as u32, texture.offset.y as u32);
+        let (w, h) = (texture.size.x as u32, texture.size.y as u32);
+        // offsets and sizes come from the file, so they might not even add up
+        let outside = |start: u32, len: u32, max: u32| {
+            start.checked_add(len).is_none_or(|end| end > max)
+        };
+        if outside(x, w, page.width()) || outside(y, h, page.height()) {
+            return Err(FileError::Schema(format!(
+                "Texture '{}' lies outside of its texture page.",
+                texture.name
+            )));
+        }
+        texture.pixels = page.crop_imm(x, y, w, h).into_rgba8().into_raw().into();
     }
 
-    // finally, save the final buffer as an image
-    image::save_buffer(
-        "temp.png",
-        &final_buf.to_vec(),
-        final_buf.width() as u32,
-        final_buf.height() as u32,
-        image::ExtendedColorType::Rgba8,
-    )
-    .unwrap();
+    Ok(root)
+}
+
+/// Upload textures to be drawn, as bind groups for the renderer and images for egui.
+pub fn load_textures(
+    textures: &[Texture],
+    queue: &wgpu::Queue,
+    device: &wgpu::Device,
+    bind_group_layout: &BindGroupLayout,
+    context: &egui::Context,
+) -> (Vec<wgpu::BindGroup>, Vec<egui::TextureHandle>) {
+    let mut bind_groups = vec![];
+    let mut images = vec![];
+    for texture in textures {
+        bind_groups.push(renderer::create_texture_bind_group(
+            texture.pixels.to_vec(),
+            texture.size,
+            queue,
+            device,
+            bind_group_layout,
+        ));
+
+        let color_image = egui::ColorImage::from_rgba_unmultiplied(
+            [texture.size.x as usize, texture.size.y as usize],
+            &texture.pixels,
+        );
+        images.push(context.load_texture("anim_icons", color_image, Default::default()));
+    }
+    (bind_groups, images)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -234,56 +376,82 @@ pub fn import(
     bind_group_layout: &BindGroupLayout,
     context: &egui::Context,
 ) {
-    let file = std::fs::File::open(path);
-    let mut zip = zip::ZipArchive::new(file.unwrap());
-
-    if let Ok(_) = zip {
-    } else {
-        shared.ui.modal_headline = "That's not a SkelForm armature!".to_string();
-        return;
-    }
-
-    // load armature
-    let armature_file = zip.as_mut().unwrap().by_name("armature.json").unwrap();
-    let mut root: crate::Root = serde_json::from_reader(armature_file).unwrap();
-
-    // load texture
-    if root.armatures[0].textures.len() > 0 {
-        let texture_file = zip.as_mut().unwrap().by_name("textures.png").unwrap();
-
-        let mut bytes = vec![];
-        for byte in texture_file.bytes() {
-            bytes.push(byte.unwrap());
+    // nothing is applied unless the whole file could be read
+    let mut warnings: Vec<String> = vec![];
+    let format = format_of(&path);
+    let root = match format {
+        Format::Skf => read_skf(&path),
+        Format::Spine => spine::read_files(&path).map(|(armature, spine_warnings)| {
+            warnings = spine_warnings;
+            Root {
+                version: migrate::FORMAT_VERSION,
+                armatures: vec![armature],
+                ..Default::default()
+            }
+        }),
+        Format::DragonBones => dragonbones::read_files(&path).map(|(root, db_warnings)| {
+            warnings = db_warnings;
+            root
+        }),
+    };
+    let root = root.and_then(|root| {
+        for armature in &root.armatures {
+            let problems = runtime::validate(armature);
+            if !problems.is_empty() {
+                let problems = problems.join("\n");
+                return Err(FileError::Schema(format!(
+                    "The armature '{}' is broken:\n{}",
+                    armature.name, problems
+                )));
+            }
         }
-        let mut img = image::load_from_memory(&bytes).unwrap();
-
-        shared.bind_groups = vec![];
-        shared.ui.texture_images = vec![];
-
-        for texture in &mut root.armatures[0].textures {
-            texture.pixels = img
-                .crop(texture.offset.x as u32, 0, texture.size.x as u32, texture.size.y as u32)
-                .into_rgba8()
-                .to_vec();
-
-            shared.bind_groups.push(renderer::create_texture_bind_group(
-                texture.pixels.to_vec(),
-                texture.size,
-                queue,
-                device,
-                bind_group_layout,
-            ));
-
-            let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                [texture.size.x as usize, texture.size.y as usize],
-                &texture.pixels,
-            );
-            let tex = context.load_texture("anim_icons", color_image, Default::default());
-            shared.ui.texture_images.push(tex);
+        Ok(root)
+    });
+    let root = match root {
+        Ok(root) => root,
+        Err(err) => {
+            shared.ui.modal_headline = format!("Couldn't import {}\n\n{}", path, err);
+            return;
+        }
+    };
+
+    let mut slots = vec![];
+    for (i, mut armature) in root.armatures.into_iter().enumerate() {
+        if armature.name.is_empty() {
+            armature.name = format!("Armature {}", i + 1);
         }
+
+        let (bind_groups, texture_images) = load_textures(
+            &armature.textures,
+            queue,
+            device,
+            bind_group_layout,
+            context,
+        );
+        slots.push(ArmatureSlot {
+            armature,
+            bind_groups,
+            texture_images,
+            ..Default::default()
+        });
     }
 
-    shared.armature = root.armatures[0].clone();
+    shared.load_project(slots);
+    // other formats don't have atlas settings, so the current ones are kept
+    if format == Format::Skf {
+        shared.atlas = root.atlas;
+    }
+    // saving goes back to the imported file, unless it'd have to be converted
+    shared.save_path = match format {
+        Format::Skf => path.clone(),
+        _ => "".to_string(),
+    };
 
-    shared.unselect_everything();
+    if !warnings.is_empty() {
+        shared.ui.modal_headline = format!(
+            "Imported {}, but some of it was left out:\n\n{}",
+            path,
+            warnings.join("\n")
+        );
+    }
 }
@@ -0,0 +1,207 @@
+//! Textures are packed into pages without touching each other, and come back out of a
+//! saved file exactly as they went in.
+
+use skelform_lib::atlas::{self, AtlasConfig};
+use skelform_lib::runtime::*;
+
+// every pixel different, and different between textures
+fn texture(seed: u8, w: u32, h: u32) -> Texture {
+    let pixels: Vec<u8> = (0..w * h)
+        .flat_map(|i| [seed, (i % w) as u8, (i / w) as u8, 255])
+        .collect();
+    Texture {
+        name: format!("tex_{}", seed),
+        size: Vec2::new(w as f32, h as f32),
+        pixels: pixels.into(),
+        ..Default::default()
+    }
+}
+
+fn textures() -> Vec<Texture> {
+    let sizes = [
+        (20, 12),
+        (5, 30),
+        (16, 16),
+        (3, 3),
+        (24, 8),
+        (9, 14),
+        (1, 1),
+        (12, 20),
+    ];
+    let textures = sizes.iter().enumerate();
+    textures
+        .map(|(i, (w, h))| texture(i as u8, *w, *h))
+        .collect()
+}
+
+fn pixel(tex: &Texture, x: u32, y: u32) -> [u8; 4] {
+    let idx = ((y * tex.size.x as u32 + x) * 4) as usize;
+    tex.pixels[idx..idx + 4].try_into().unwrap()
+}
+
+#[test]
+fn packed_textures_never_overlap() {
+    let config = AtlasConfig {
+        max_size: 48,
+        padding: 2,
+        extrude: 1,
+    };
+    let mut textures = textures();
+    let pages = atlas::build(&mut textures, &config);
+    assert!(pages.len() > 1);
+
+    // what each texture takes up, with its extrusion and the padding after it
+    let footprint = |tex: &Texture| {
+        let (x, y) = (tex.offset.x as u32, tex.offset.y as u32);
+        let right = x + tex.size.x as u32 + config.extrude + config.padding;
+        let bottom = y + tex.size.y as u32 + config.extrude + config.padding;
+        (x - config.extrude, y - config.extrude, right, bottom)
+    };
+    for (i, a) in textures.iter().enumerate() {
+        let (x, y, right, bottom) = footprint(a);
+        let page = &pages[a.page];
+        assert!(right <= page.width() && bottom <= page.height());
+        assert!(page.width() <= config.max_size && page.height() <= config.max_size);
+
+        for b in textures.iter().skip(i + 1).filter(|b| b.page == a.page) {
+            let (bx, by, b_right, b_bottom) = footprint(b);
+            let overlap = x < b_right && bx < right && y < b_bottom && by < bottom;
+            assert!(!overlap, "'{}' overlaps '{}'", a.name, b.name);
+        }
+    }
+}
+
+#[test]
+fn edges_are_extruded() {
+    let config = AtlasConfig {
+        max_size: 64,
+        padding: 3,
+        extrude: 2,
+    };
+    let mut textures = vec![texture(7, 4, 3)];
+    let pages = atlas::build(&mut textures, &config);
+    let tex = &textures[0];
+    let page = &pages[0];
+    assert_eq!(tex.offset, Vec2::new(2., 2.));
+    assert_eq!(page.dimensions(), (4 + 2 * 2 + 3, 3 + 2 * 2 + 3));
+
+    let at = |x: u32, y: u32| page.get_pixel(x, y).0;
+    // sides repeat the pixel next to them, and corners the corner
+    assert_eq!(at(0, 3), pixel(tex, 0, 1));
+    assert_eq!(at(1, 3), pixel(tex, 0, 1));
+    assert_eq!(at(7, 2), pixel(tex, 3, 0));
+    assert_eq!(at(4, 6), pixel(tex, 2, 2));
+    assert_eq!(at(0, 0), pixel(tex, 0, 0));
+    assert_eq!(at(7, 6), pixel(tex, 3, 2));
+    // and the texture itself is untouched
+    assert_eq!(at(3, 3), pixel(tex, 1, 1));
+    // the padding is left empty
+    assert_eq!(at(8, 3), [0, 0, 0, 0]);
+    assert_eq!(at(3, 9), [0, 0, 0, 0]);
+}
+
+#[test]
+#[cfg(feature = "editor")]
+fn saved_textures_are_read_back_exactly() {
+    use skelform_lib::utils;
+
+    let config = AtlasConfig {
+        max_size: 40,
+        ..Default::default()
+    };
+    let mut armatures = vec![Armature::default(), Armature::default()];
+    let mut textures = textures();
+    armatures[1].textures = textures.split_off(3);
+    armatures[0].textures = textures;
+
+    let path = std::env::temp_dir().join(format!("skelform_atlas_{}.skf", std::process::id()));
+    let path = path.to_str().unwrap();
+    let page_count = utils::write_skf(path, &armatures, &config).unwrap();
+    let root = utils::read_skf(path);
+    std::fs::remove_file(path).unwrap();
+    let root = root.unwrap();
+    assert!(page_count > 1);
+
+    for (read, written) in root.armatures.iter().zip(&armatures) {
+        assert_eq!(read.textures.len(), written.textures.len());
+        for (read, written) in read.textures.iter().zip(&written.textures) {
+            assert_eq!(read.size, written.size);
+            assert!(read.pixels[..] == written.pixels[..], "'{}' changed", written.name);
+        }
+    }
+    let pages = root
+        .armatures
+        .iter()
+        .flat_map(|a| &a.textures)
+        .map(|t| t.page);
+    assert_eq!(pages.max(), Some(page_count - 1));
+}
+
+#[test]
+#[cfg(feature = "editor")]
+fn textures_outside_of_their_pages_are_rejected() {
+    use skelform_lib::utils;
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join(format!("skelform_bad_{}.skf", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut png = std::io::Cursor::new(vec![]);
+    let page = image::RgbaImage::new(8, 8);
+    page.write_to(&mut png, image::ImageFormat::Png).unwrap();
+
+    let read = |texture: serde_json::Value| {
+        let root = serde_json::json!({
+            "version": skelform_lib::migrate::FORMAT_VERSION,
+            "armatures": [{ "textures": [texture] }],
+            "texture_size": { "x": 8, "y": 8 },
+        });
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("armature.json", options).unwrap();
+        zip.write_all(root.to_string().as_bytes()).unwrap();
+        zip.start_file(atlas::page_file(0), options).unwrap();
+        zip.write_all(png.get_ref()).unwrap();
+        zip.finish().unwrap();
+        utils::read_skf(path)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    };
+    let fits = serde_json::json!({ "offset": { "x": 4, "y": 4 }, "size": { "x": 4, "y": 4 } });
+    let result = read(fits.clone());
+
+    let mut wrapping = fits.clone();
+    wrapping["offset"]["x"] = u32::MAX.into();
+    let wrapping = read(wrapping);
+
+    let mut missing = fits;
+    missing["page"] = 100000000000u64.into();
+    let missing = read(missing);
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(result, Ok(()));
+    assert!(wrapping
+        .unwrap_err()
+        .contains("outside of its texture page"));
+    assert!(missing.unwrap_err().contains("isn't in the file"));
+}
+
+#[test]
+#[cfg(feature = "editor")]
+fn saving_replaces_the_old_file() {
+    use skelform_lib::utils;
+
+    let path = std::env::temp_dir().join(format!("skelform_resave_{}.skf", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut armature = Armature::default();
+    armature.textures.push(texture(1, 4, 4));
+    let config = AtlasConfig::default();
+    utils::write_skf(path, &[armature.clone()], &config).unwrap();
+    armature.textures.push(texture(2, 3, 3));
+    utils::write_skf(path, &[armature], &config).unwrap();
+
+    let root = utils::read_skf(path);
+    let leftover = std::path::Path::new(&format!("{}.tmp", path)).exists();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(root.unwrap().armatures[0].textures.len(), 2);
+    assert!(!leftover);
+}
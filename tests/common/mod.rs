@@ -0,0 +1,55 @@
+//! Helpers shared by the tests. Ones using the editor need its feature.
+
+#![allow(dead_code)]
+
+#[cfg(feature = "editor")]
+use skelform_lib::gizmo::Axis;
+use skelform_lib::runtime::Vec2;
+#[cfg(feature = "editor")]
+use skelform_lib::shared::*;
+
+/// An empty editor, with the history's snapshot taken.
+#[cfg(feature = "editor")]
+pub fn editor() -> Shared {
+    editor_with(|_| {})
+}
+
+/// An editor set up by `setup`, with the history's snapshot taken after it (so the
+/// setup can't be undone).
+#[cfg(feature = "editor")]
+pub fn editor_with(setup: impl FnOnce(&mut Shared)) -> Shared {
+    let mut shared = Shared::default();
+    setup(&mut shared);
+    shared.record_edits();
+    shared
+}
+
+/// Drag the selection from `from` to `to` as one step of the history, as the canvas
+/// does.
+#[cfg(feature = "editor")]
+pub fn drag(shared: &mut Shared, from: Vec2, to: Vec2) {
+    transform(shared, None, from, to, false);
+}
+
+/// Drag the selection from the origin to `to` along `axis` (if given), snapping to
+/// increments if `snap` is true.
+#[cfg(feature = "editor")]
+pub fn drag_axis(shared: &mut Shared, axis: Option<Axis>, to: Vec2, snap: bool) {
+    transform(shared, axis, Vec2::new(0., 0.), to, snap);
+}
+
+#[cfg(feature = "editor")]
+fn transform(shared: &mut Shared, axis: Option<Axis>, from: Vec2, to: Vec2, snap: bool) {
+    shared.history.seal();
+    shared.start_transform(from, axis);
+    shared.transform_selection(to, snap);
+    shared.record_edits();
+}
+
+pub fn assert_near(a: Vec2, b: Vec2) {
+    assert_near_within(a, b, 0.001);
+}
+
+pub fn assert_near_within(a: Vec2, b: Vec2, tolerance: f32) {
+    assert!((a - b).length() < tolerance, "{} isn't {}", a, b);
+}
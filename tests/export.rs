@@ -0,0 +1,92 @@
+//! Animated exports decode back into the frames that went in, each shown for as long
+//! as the fps says.
+
+use skelform_lib::export::{self, ExportFormat, ExportSettings};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+
+// a different color for each frame, with a transparent corner
+fn frames() -> Vec<image::RgbaImage> {
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+    colors
+        .iter()
+        .map(|color| {
+            image::RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| match x < 2 && y < 2 {
+                true => image::Rgba([0, 0, 0, 0]),
+                false => image::Rgba(*color),
+            })
+        })
+        .collect()
+}
+
+fn write(format: ExportFormat, name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("skelform_{}_{}", std::process::id(), name));
+    let settings = ExportSettings {
+        format,
+        path: path.to_string_lossy().to_string(),
+        // 33.3ms a frame, which doesn't divide evenly
+        fps: 30,
+        ..Default::default()
+    };
+    let mut done = vec![];
+    export::export(&settings, frames(), |frame| done.push(frame)).unwrap();
+    assert_eq!(done, [1, 2, 3]);
+    path
+}
+
+#[test]
+fn gifs_decode_into_every_frame() {
+    let path = write(ExportFormat::Gif, "anim.gif");
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options
+        .read_info(std::fs::File::open(&path).unwrap())
+        .unwrap();
+    assert_eq!(decoder.width() as u32, WIDTH);
+    assert_eq!(decoder.height() as u32, HEIGHT);
+
+    let mut delays = vec![];
+    let mut decoded = vec![];
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!((frame.width, frame.height), (WIDTH as u16, HEIGHT as u16));
+        delays.push(frame.delay);
+        decoded.push(frame.buffer.to_vec());
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    // in hundredths of a second, adding up to a tenth of a second
+    assert_eq!(delays, [3, 4, 3]);
+    assert_eq!(decoded.len(), 3);
+    for (decoded, frame) in decoded.iter().zip(frames()) {
+        assert!(decoded[..] == frame.as_raw()[..]);
+    }
+}
+
+#[test]
+fn webps_decode_into_every_frame() {
+    let path = write(ExportFormat::WebP, "anim.webp");
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the RIFF header's length covers the rest of the file
+    assert_eq!(&bytes[..4], b"RIFF");
+    let riff_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    assert_eq!(riff_len, bytes.len() - 8);
+
+    let mut decoder = image_webp::WebPDecoder::new(std::io::Cursor::new(&bytes)).unwrap();
+    assert!(decoder.is_animated() && decoder.has_alpha());
+    assert_eq!(decoder.dimensions(), (WIDTH, HEIGHT));
+    assert_eq!(decoder.num_frames(), 3);
+    assert_eq!(decoder.loop_count(), image_webp::LoopCount::Forever);
+    assert_eq!(decoder.loop_duration(), 100);
+
+    let mut delays = vec![];
+    let mut buf = vec![0; decoder.output_buffer_size().unwrap()];
+    for frame in frames() {
+        delays.push(decoder.read_frame(&mut buf).unwrap());
+        // lossless, so exactly as they went in
+        assert!(buf[..] == frame.as_raw()[..]);
+    }
+    assert_eq!(delays, [33, 34, 33]);
+}
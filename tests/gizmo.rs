@@ -0,0 +1,106 @@
+//! The gizmo sits where the renderer places the selected bones, and its handles keep
+//! dragging to an axis, with snapping to increments.
+
+#![cfg(feature = "editor")]
+
+mod common;
+
+use common::{assert_near, drag_axis, editor_with};
+use skelform_lib::armature_window::new_bone;
+use skelform_lib::gizmo::{self, Axis, Gizmo, Handle};
+use skelform_lib::shared::*;
+
+use std::f32::consts::FRAC_PI_2;
+
+// a bone at the origin, selected
+fn one_bone() -> Shared {
+    editor_with(|shared| {
+        shared.camera.zoom = 5.;
+        new_bone(shared, -1);
+        shared.select_bone(0);
+    })
+}
+
+#[test]
+fn handles_are_where_theyre_drawn() {
+    let mut shared = one_bone();
+    let gizmo = Gizmo::new(&shared).unwrap();
+    let handle = |mode, x, y| gizmo.handle_at(&mode, Vec2::new(x, y));
+    assert_eq!(handle(EditMode::Move, 0., 0.), Some(Handle::Center));
+    assert_eq!(handle(EditMode::Move, 0.6, 0.), Some(Handle::Axis(Axis::X)));
+    assert_eq!(handle(EditMode::Scale, 0., 1.), Some(Handle::Axis(Axis::Y)));
+    assert_eq!(handle(EditMode::Move, 0.6, 0.6), None);
+    assert_eq!(handle(EditMode::Rotate, 0., -1.), Some(Handle::Ring));
+    assert_eq!(handle(EditMode::Rotate, 0., 0.), None);
+
+    // the same size on screen, however far the camera is
+    shared.camera.zoom = 10.;
+    let far = Gizmo::new(&shared).unwrap();
+    assert_eq!(far.size, gizmo.size * 2.);
+
+    shared.edit_mode = EditMode::Mesh;
+    assert!(Gizmo::new(&shared).is_none());
+    shared.unselect_everything();
+    shared.edit_mode = EditMode::Move;
+    assert!(Gizmo::new(&shared).is_none());
+}
+
+#[test]
+fn gizmo_follows_the_bone_as_its_shown() {
+    let mut shared = one_bone();
+    shared.armature.bones[0].rot = FRAC_PI_2;
+    let (_, child) = new_bone(&mut shared, -1);
+    shared.armature.bones[child].parent_id = shared.armature.bones[0].id;
+    shared.armature.bones[child].pos = Vec2::new(1., 0.);
+    shared.select_bone(child);
+
+    let gizmo = Gizmo::new(&shared).unwrap();
+    assert_near(gizmo.origin, Vec2::new(0., 1.));
+    assert_near(gizmo.x_axis, Vec2::new(0., 1.));
+    assert_near(gizmo.y_axis, Vec2::new(-1., 0.));
+
+    // the 2D cursor moves it elsewhere
+    shared.pivot = Pivot::Cursor;
+    shared.cursor = Vec2::new(3., 3.);
+    assert_near(Gizmo::new(&shared).unwrap().origin, shared.cursor);
+}
+
+#[test]
+fn arrows_move_along_their_axis() {
+    let mut shared = one_bone();
+    drag_axis(&mut shared, Some(Axis::X), Vec2::new(0.5, 0.3), false);
+    assert_near(shared.armature.bones[0].pos, Vec2::new(0.5, 0.));
+
+    // along the bone's own axis
+    shared.armature.bones[0].rot = FRAC_PI_2;
+    drag_axis(&mut shared, Some(Axis::X), Vec2::new(0.3, 0.5), false);
+    assert_near(shared.armature.bones[0].pos, Vec2::new(0.5, 0.5));
+}
+
+#[test]
+fn scale_handles_scale_one_axis() {
+    let mut shared = one_bone();
+    shared.edit_mode = EditMode::Scale;
+    shared.start_transform(Vec2::new(0., 1.), Some(Axis::Y));
+    shared.transform_selection(Vec2::new(0.4, 2.), false);
+    assert_near(shared.armature.bones[0].scale, Vec2::new(1., 2.));
+}
+
+#[test]
+fn dragging_snaps_to_increments() {
+    let mut shared = one_bone();
+    drag_axis(&mut shared, None, Vec2::new(0.234, -0.17), true);
+    assert_near(shared.armature.bones[0].pos, Vec2::new(0.2, -0.2));
+
+    shared.edit_mode = EditMode::Rotate;
+    // 20 degrees
+    shared.start_transform(Vec2::new(1., 0.), None);
+    shared.transform_selection(Vec2::new(0.94, 0.342), true);
+    let rot = shared.armature.bones[0].rot;
+    assert!((rot - gizmo::SNAP_ROTATE).abs() < 0.001, "{}", rot);
+
+    shared.edit_mode = EditMode::Scale;
+    shared.start_transform(Vec2::new(1., 0.), None);
+    shared.transform_selection(Vec2::new(1.26, 0.), true);
+    assert_near(shared.armature.bones[0].scale, Vec2::new(1.3, 1.3));
+}
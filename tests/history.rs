@@ -0,0 +1,308 @@
+//! Every kind of edit has to be undoable and redoable, back to exactly how the armature
+//! was. Edits are made like the editor makes them, then recorded as it does at the end
+//! of a frame.
+
+#![cfg(feature = "editor")]
+
+mod common;
+
+use common::editor;
+use skelform_lib::armature_window::new_bone;
+use skelform_lib::keyframe_editor::new_animation;
+use skelform_lib::shared::*;
+
+type Edit = (&'static str, fn(&mut Shared));
+
+fn texture(name: &str, size: u32) -> Texture {
+    Texture {
+        name: name.to_string(),
+        size: Vec2::new(size as f32, size as f32),
+        pixels: vec![255; (size * size * 4) as usize].into(),
+        ..Default::default()
+    }
+}
+
+const EDITS: [Edit; 24] = [
+    ("Add bone 'New Bone'", |s| {
+        new_bone(s, -1);
+    }),
+    ("Add bone 'New Bone'", |s| {
+        new_bone(s, -1);
+    }),
+    ("Rename bone 'New Bone' to 'Hip'", |s| {
+        s.armature.bones[0].name = "Hip".to_string();
+    }),
+    ("Reparent bone 'New Bone'", |s| {
+        s.armature.bones[1].parent_id = s.armature.bones[0].id;
+    }),
+    ("Move 'Hip'", |s| {
+        s.select_bone(0);
+        s.edit_bone(&AnimElement::PositionX, 40., true);
+    }),
+    ("Rotate 'Hip'", |s| {
+        s.edit_bone(&AnimElement::Rotation, 1., true)
+    }),
+    ("Import texture 'Body'", |s| {
+        s.armature.textures.push(texture("Body", 4))
+    }),
+    ("Import texture 'Head'", |s| {
+        s.armature.textures.push(texture("Head", 2))
+    }),
+    ("Add slot 'Hip'", |s| s.set_attachment(0, -1)),
+    ("Change texture of 'Hip'", |s| s.set_attachment(1, -1)),
+    ("Edit regions of 'Body'", |s| {
+        let region = Region {
+            name: "Arm".to_string(),
+            size: Vec2::new(2., 2.),
+            ..Default::default()
+        };
+        s.set_regions(0, vec![region]);
+    }),
+    ("Change color of 'Hip'", |s| {
+        s.armature.slots[0].color = Color::new(1., 0., 0., 0.5);
+    }),
+    ("Add slot 'New Bone'", |s| {
+        s.select_bone(1);
+        s.set_attachment(0, 0);
+    }),
+    ("Change draw order", |s| s.set_draw_order(vec![1, 0])),
+    ("Add animation", |s| new_animation(s)),
+    ("Rename animation '' to 'Walk'", |s| {
+        s.armature.animations[0].name = "Walk".to_string();
+    }),
+    // the bone's first key is also at the start
+    ("Add keyframes to 'Walk'", |s| {
+        s.ui.anim.open = true;
+        s.ui.anim.selected = 0;
+        s.ui.anim.selected_frame = 5;
+        s.edit_bone(&AnimElement::PositionY, 10., true);
+    }),
+    ("Edit keyframe @0", |s| {
+        s.armature.animations[0].keyframes[0].transition = Transition::SineIn;
+    }),
+    ("Add keyframe @5", |s| s.set_attachment(1, -1)),
+    ("Delete slot 'New Bone'", |s| {
+        s.ui.anim.open = false;
+        s.remove_slot(0);
+    }),
+    ("Edit regions of 'Body'", |s| s.remove_region(0, 0)),
+    ("Delete texture 'Head'", |s| {
+        s.armature.textures.pop();
+        s.armature.slots[0].tex_idx = -1;
+    }),
+    ("Delete bone 'Hip'", |s| {
+        s.delete_bone(s.armature.bones[0].id)
+    }),
+    ("Rename armature to 'Hero'", |s| {
+        s.armature.name = "Hero".to_string()
+    }),
+];
+
+#[test]
+fn every_edit_undoes_and_redoes() {
+    let mut shared = editor();
+    let mut states = vec![shared.armature.clone()];
+    for (label, edit) in EDITS {
+        edit(&mut shared);
+        assert!(
+            shared.history.record(&shared.armature),
+            "{} did nothing",
+            label
+        );
+        shared.history.seal();
+        states.push(shared.armature.clone());
+    }
+    assert_eq!(shared.history.undo_steps().len(), EDITS.len());
+
+    for state in states.iter().rev().skip(1) {
+        shared.undo();
+        assert!(shared.armature == *state);
+    }
+    assert!(shared.history.undo_steps().is_empty());
+
+    for state in states.iter().skip(1) {
+        shared.redo();
+        assert!(shared.armature == *state);
+    }
+    assert!(shared.history.redo_steps().is_empty());
+}
+
+#[test]
+fn edits_are_labelled() {
+    let mut shared = editor();
+    for (label, edit) in EDITS {
+        edit(&mut shared);
+        shared.record_edits();
+        shared.history.seal();
+        let step = shared.history.undo_steps().last().unwrap();
+        assert_eq!(step.label(), label);
+    }
+}
+
+#[test]
+fn jumping_undoes_and_redoes_up_to_a_step() {
+    let mut shared = editor();
+    let mut states = vec![shared.armature.clone()];
+    for (_, edit) in EDITS {
+        edit(&mut shared);
+        shared.record_edits();
+        shared.history.seal();
+        states.push(shared.armature.clone());
+    }
+
+    for steps in [3, 20, 0, EDITS.len(), 12] {
+        shared.jump_history(steps);
+        assert_eq!(shared.history.undo_steps().len(), steps);
+        assert!(shared.armature == states[steps]);
+    }
+    assert_eq!(shared.history.redo_steps().len(), EDITS.len() - 12);
+}
+
+#[test]
+fn keyframes_are_labelled_by_frame() {
+    let mut shared = editor();
+    new_bone(&mut shared, -1);
+    new_animation(&mut shared);
+    shared.select_bone(0);
+    shared.ui.anim.open = true;
+    shared.ui.anim.selected = 0;
+    shared.record_edits();
+
+    for frame in [0, 12] {
+        shared.history.seal();
+        shared.ui.anim.selected_frame = frame;
+        shared.edit_bone(&AnimElement::Rotation, 1., true);
+        shared.record_edits();
+    }
+    let labels: Vec<&str> = shared
+        .history
+        .undo_steps()
+        .iter()
+        .map(|s| s.label())
+        .collect();
+    assert_eq!(labels[1..], ["Add keyframe @0", "Add keyframe @12"]);
+
+    // moving a keyframe edits two frames
+    shared.history.seal();
+    shared.armature.animations[0].keyframes[0].frame = 8;
+    shared.sort_keyframes();
+    shared.record_edits();
+    let last = shared.history.undo_steps().last().unwrap();
+    assert_eq!(last.label(), "Edit keyframes of ''");
+}
+
+#[test]
+fn drags_are_one_step() {
+    let mut shared = editor();
+    new_bone(&mut shared, -1);
+    shared.select_bone(0);
+    shared.record_edits();
+
+    // a new click, then a frame for every step of the drag
+    for drag in [0., 100.] {
+        shared.history.seal();
+        for x in 1..=10 {
+            shared.edit_bone(&AnimElement::PositionX, drag + x as f32, true);
+            shared.record_edits();
+        }
+    }
+    assert_eq!(shared.history.undo_steps().len(), 3);
+
+    shared.undo();
+    assert_eq!(shared.armature.bones[0].pos.x, 10.);
+    shared.undo();
+    assert_eq!(shared.armature.bones[0].pos.x, 0.);
+}
+
+#[test]
+fn dragging_back_leaves_no_step() {
+    let mut shared = editor();
+    new_bone(&mut shared, -1);
+    shared.select_bone(0);
+    shared.record_edits();
+
+    shared.history.seal();
+    for x in [5., 10., 0.] {
+        shared.edit_bone(&AnimElement::PositionX, x, true);
+        shared.record_edits();
+    }
+    assert_eq!(shared.history.undo_steps().len(), 1);
+}
+
+#[test]
+fn redone_bones_keep_their_ids() {
+    let mut shared = editor();
+    for _ in 0..3 {
+        new_bone(&mut shared, -1);
+        shared.record_edits();
+        shared.history.seal();
+    }
+    let ids: Vec<i32> = shared.armature.bones.iter().map(|b| b.id).collect();
+
+    shared.undo();
+    shared.undo();
+    shared.redo();
+    shared.redo();
+    let redone: Vec<i32> = shared.armature.bones.iter().map(|b| b.id).collect();
+    assert_eq!(ids, redone);
+}
+
+#[test]
+fn new_edits_drop_the_redo_steps() {
+    let mut shared = editor();
+    new_bone(&mut shared, -1);
+    shared.record_edits();
+    shared.undo();
+    assert_eq!(shared.history.redo_steps().len(), 1);
+
+    new_animation(&mut shared);
+    shared.record_edits();
+    assert!(shared.history.redo_steps().is_empty());
+    assert_eq!(shared.history.undo_steps().len(), 1);
+}
+
+#[test]
+fn undo_unselects_what_it_removes() {
+    let mut shared = editor();
+    new_bone(&mut shared, -1);
+    shared.select_bone(0);
+    shared.record_edits();
+
+    shared.undo();
+    assert!(shared.selected_bone().is_none());
+}
+
+#[test]
+fn oldest_steps_are_forgotten_over_budget() {
+    let mut shared = editor();
+    // each import weighs a little over 64 KB
+    shared.history.budget = 200 * 1024;
+    for i in 0..10 {
+        shared.armature.textures.push(texture(&i.to_string(), 128));
+        shared.record_edits();
+        shared.history.seal();
+        assert!(shared.history.weight() <= shared.history.budget);
+    }
+    assert_eq!(shared.history.undo_steps().len(), 3);
+
+    // what's left still undoes to how it was then
+    while !shared.history.undo_steps().is_empty() {
+        shared.undo();
+    }
+    assert_eq!(shared.armature.textures.len(), 7);
+    assert!(shared.reload_textures);
+}
+
+#[test]
+fn pixels_are_weighed_once() {
+    let mut shared = editor();
+    assert!(shared.history.has_snapshot());
+    assert!(shared.history.weight() > 0);
+
+    // the snapshot shares them with the armature, and the import step
+    shared.armature.textures.push(texture("Big", 128));
+    shared.record_edits();
+    let bytes = 128 * 128 * 4;
+    let weight = shared.history.weight();
+    assert!(weight > bytes && weight < bytes * 2, "{}", weight);
+}
@@ -0,0 +1,127 @@
+//! IK constraints rotate their chain so that its end lands on the target, or points
+//! at it when it's out of reach, and are mixed with the pose as it was animated.
+
+mod common;
+
+use common::{assert_near, assert_near_within};
+use skelform_lib::ik;
+use skelform_lib::runtime::*;
+
+// a chain of `joints` unit-long bones along X from the origin, ending in one more bone,
+// then a root bone at `target` for the chain to reach
+fn chain(joints: i32, target: Vec2) -> Vec<Bone> {
+    let bone = |id, parent_id, pos| Bone {
+        id,
+        parent_id,
+        pos,
+        scale: Vec2::new(1., 1.),
+        ..Default::default()
+    };
+    let mut bones = vec![bone(0, -1, Vec2::new(0., 0.))];
+    for id in 1..=joints {
+        bones.push(bone(id, id - 1, Vec2::new(1., 0.)));
+    }
+    bones.push(bone(joints + 1, -1, target));
+    bones
+}
+
+fn constraint(joints: i32) -> IkConstraint {
+    IkConstraint {
+        bone_id: joints,
+        target_id: joints + 1,
+        chain_length: joints,
+        ..Default::default()
+    }
+}
+
+fn solved(bones: &[Bone], constraint: IkConstraint, anim: Option<&Animation>) -> Vec<Vec2> {
+    let mut bones = bones.to_vec();
+    ik::solve(&[constraint], anim, 0., &mut bones);
+    world_transforms(&bones)
+        .iter()
+        .map(|t| t.translation())
+        .collect()
+}
+
+#[test]
+fn two_bones_reach_the_target() {
+    let bones = chain(2, Vec2::new(1., 1.));
+    let pos = solved(&bones, constraint(2), None);
+    assert_near(pos[2], Vec2::new(1., 1.));
+    // neither bone stretches
+    assert_near(pos[1], Vec2::new(0., 1.));
+}
+
+#[test]
+fn two_bones_bend_to_either_side() {
+    let bones = chain(2, Vec2::new(1., 1.));
+    let bend_negative = IkConstraint {
+        bend_positive: false,
+        ..constraint(2)
+    };
+    let pos = solved(&bones, bend_negative, None);
+    assert_near(pos[2], Vec2::new(1., 1.));
+    assert_near(pos[1], Vec2::new(1., 0.));
+}
+
+#[test]
+fn two_bones_stretch_towards_unreachable_targets() {
+    let bones = chain(2, Vec2::new(0., 5.));
+    let pos = solved(&bones, constraint(2), None);
+    assert_near(pos[1], Vec2::new(0., 1.));
+    assert_near(pos[2], Vec2::new(0., 2.));
+}
+
+#[test]
+fn longer_chains_reach_the_target() {
+    let bones = chain(3, Vec2::new(1., 2.));
+    let pos = solved(&bones, constraint(3), None);
+    assert_near_within(pos[3], Vec2::new(1., 2.), 0.01);
+    for (joint, next) in pos[..3].iter().zip(&pos[1..4]) {
+        assert!(((*next - *joint).length() - 1.).abs() < 0.001);
+    }
+}
+
+#[test]
+fn longer_chains_stretch_towards_unreachable_targets() {
+    let bones = chain(3, Vec2::new(-10., 0.));
+    let pos = solved(&bones, constraint(3), None);
+    assert_near_within(pos[1], Vec2::new(-1., 0.), 0.01);
+    assert_near_within(pos[2], Vec2::new(-2., 0.), 0.01);
+    assert_near_within(pos[3], Vec2::new(-3., 0.), 0.01);
+}
+
+#[test]
+fn mix_blends_with_the_animated_pose() {
+    // pointing just short of -X, with the target just past it
+    let mut bones = chain(1, Vec2::new(3_f32.cos(), -3_f32.sin()));
+    bones[0].rot = 3.;
+    let mix = |mix| {
+        let constraint = IkConstraint {
+            mix,
+            ..constraint(1)
+        };
+        solved(&bones, constraint, None)[1]
+    };
+
+    assert_near(mix(0.), Vec2::new(3_f32.cos(), 3_f32.sin()));
+    assert_near(mix(1.), Vec2::new(3_f32.cos(), -3_f32.sin()));
+    // halfway the short way round, across -X, rather than back past +X
+    assert_near(mix(0.5), Vec2::new(-1., 0.));
+}
+
+#[test]
+fn mix_can_be_keyed() {
+    let bones = chain(2, Vec2::new(1., 1.));
+    let anim = Animation {
+        keyframes: vec![Keyframe {
+            bone_id: 2,
+            element: AnimElement::IkMix,
+            value: 0.,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let pos = solved(&bones, constraint(2), Some(&anim));
+    assert_near(pos[2], Vec2::new(2., 0.));
+}
@@ -0,0 +1,152 @@
+//! Shortcuts have to survive being written to the keymap file and read back, and every
+//! platform's defaults have to leave no two actions on the same keys.
+
+#![cfg(feature = "editor")]
+
+use egui::os::OperatingSystem;
+use egui::{Key, KeyboardShortcut, Modifiers};
+use skelform_lib::keymap::{self, Action, Keymap};
+
+const PLATFORMS: [OperatingSystem; 3] = [
+    OperatingSystem::Windows,
+    OperatingSystem::Nix,
+    OperatingSystem::Mac,
+];
+
+#[test]
+fn defaults_dont_clash() {
+    for os in PLATFORMS {
+        let keymap = Keymap::defaults(os);
+        let bound: Vec<KeyboardShortcut> =
+            Action::ALL.iter().filter_map(|a| keymap.get(*a)).collect();
+        for (i, shortcut) in bound.iter().enumerate() {
+            assert!(
+                !bound[i + 1..].contains(shortcut),
+                "{:?} on {:?}",
+                shortcut,
+                os
+            );
+        }
+    }
+}
+
+#[test]
+fn defaults_follow_the_platform() {
+    let text = |os, action| Keymap::defaults(os).text(action);
+    assert_eq!(text(OperatingSystem::Nix, Action::Undo), "Ctrl+Z");
+    assert_eq!(text(OperatingSystem::Windows, Action::Redo), "Ctrl+Y");
+    assert_eq!(text(OperatingSystem::Mac, Action::Undo), "Cmd+Z");
+    assert_eq!(text(OperatingSystem::Mac, Action::Redo), "Cmd+Shift+Z");
+    assert_eq!(text(OperatingSystem::Nix, Action::ZoomIn), "Equals");
+    assert_eq!(text(OperatingSystem::Nix, Action::ExportSpine), "");
+}
+
+#[test]
+fn shortcuts_read_back_as_written() {
+    for os in PLATFORMS {
+        for action in Action::ALL {
+            let Some(shortcut) = Keymap::defaults(os).get(action) else {
+                continue;
+            };
+            let text = keymap::format_shortcut(shortcut);
+            assert_eq!(keymap::parse_shortcut(&text), Ok(shortcut), "{}", text);
+        }
+    }
+
+    let plus = KeyboardShortcut::new(Modifiers::CTRL, Key::Plus);
+    assert_eq!(keymap::format_shortcut(plus), "Ctrl+Plus");
+    // keys can be written as symbols too
+    assert_eq!(keymap::parse_shortcut("Ctrl++"), Ok(plus));
+    assert_eq!(keymap::parse_shortcut("="), keymap::parse_shortcut("Equals"));
+    assert!(keymap::parse_shortcut("Hyper+S").is_err());
+    assert!(keymap::parse_shortcut("Ctrl+Nope").is_err());
+}
+
+#[test]
+fn keymap_file_round_trips() {
+    let mut keymap = Keymap::defaults(OperatingSystem::Nix);
+    keymap.set(Action::ExportSpine, keymap::parse_shortcut("Alt+1").ok());
+    keymap.set(Action::History, None);
+
+    let path = std::env::temp_dir().join(format!("skelform_keymap_{}.json", std::process::id()));
+    keymap.save_to(&path).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(json["export_spine"], "Alt+1");
+    assert_eq!(json["history"], serde_json::Value::Null);
+    let read = Keymap::from_json(&json, OperatingSystem::Nix).unwrap();
+    assert_eq!(read, keymap);
+}
+
+#[test]
+fn keymap_file_can_leave_actions_out() {
+    let json = serde_json::json!({ "undo": "Ctrl+U", "zoom_in": null });
+    let keymap = Keymap::from_json(&json, OperatingSystem::Nix).unwrap();
+    assert_eq!(keymap.text(Action::Undo), "Ctrl+U");
+    assert_eq!(keymap.get(Action::ZoomIn), None);
+    assert_eq!(keymap.text(Action::Save), "Ctrl+S");
+
+    let unknown = serde_json::json!({ "fly": "Ctrl+F" });
+    assert!(Keymap::from_json(&unknown, OperatingSystem::Nix).is_err());
+    let not_text = serde_json::json!({ "undo": 5 });
+    assert!(Keymap::from_json(&not_text, OperatingSystem::Nix).is_err());
+}
+
+#[test]
+fn rebinding_takes_the_shortcut_from_other_actions() {
+    let mut keymap = Keymap::defaults(OperatingSystem::Nix);
+    let undo = keymap.get(Action::Undo);
+    assert_eq!(keymap.set(Action::History, undo), Some(Action::Undo));
+    assert_eq!(keymap.get(Action::Undo), None);
+    assert_eq!(keymap.get(Action::History), undo);
+}
+
+#[test]
+fn pressed_shortcuts_run_their_actions() {
+    let keymap = Keymap::defaults(OperatingSystem::Nix);
+    let context = egui::Context::default();
+    let press = |key, modifiers| egui::Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers,
+    };
+
+    // as egui-winit reports Ctrl on Linux
+    let ctrl = Modifiers::CTRL | Modifiers::COMMAND;
+    let shift = ctrl | Modifiers::SHIFT;
+    let mut pressed = vec![];
+    for event in [
+        press(Key::S, shift),
+        press(Key::Z, ctrl),
+        press(Key::Q, ctrl),
+    ] {
+        let input = egui::RawInput {
+            events: vec![event],
+            ..Default::default()
+        };
+        context.begin_pass(input);
+        pressed.extend(keymap.pressed(&context));
+        let _ = context.end_pass();
+    }
+    assert_eq!(pressed, [Action::SaveAs, Action::Undo]);
+}
+
+#[test]
+fn keymap_file_cant_bind_a_shortcut_twice() {
+    // taken from a default, like rebinding would
+    let json = serde_json::json!({ "history": "Ctrl+Z" });
+    let keymap = Keymap::from_json(&json, OperatingSystem::Nix).unwrap();
+    assert_eq!(keymap.text(Action::History), "Ctrl+Z");
+    assert_eq!(keymap.get(Action::Undo), None);
+
+    // but not from another action in the file
+    let json = serde_json::json!({ "history": "Ctrl+Z", "undo": "Ctrl+Z" });
+    assert_eq!(
+        Keymap::from_json(&json, OperatingSystem::Nix),
+        Err("history and undo are both bound to Ctrl+Z.".to_string())
+    );
+}
@@ -0,0 +1,173 @@
+//! Loads every file of `tests/corpus`, which has samples of each version of the format
+//! in `v{version}/`. When the format changes, add a migration and samples of the new
+//! version; the old samples must keep loading.
+
+use std::path::Path;
+
+use serde_json::Value;
+use skelform_lib::{migrate, runtime};
+
+fn load(path: &Path) -> (u32, runtime::Root) {
+    let text = std::fs::read_to_string(path).unwrap();
+    let mut json: Value = serde_json::from_str(&text).unwrap();
+    let from =
+        migrate::migrate(&mut json).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+    let root =
+        serde_json::from_value(json).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+    (from, root)
+}
+
+fn corpus(version: u32) -> Vec<runtime::Root> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/corpus/v{}", version));
+    let mut paths: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|_| panic!("no samples of version {} in {}", version, dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no samples of version {}", version);
+
+    paths
+        .iter()
+        .map(|path| {
+            let (from, root) = load(path);
+            assert_eq!(from, version, "{} is in the wrong folder", path.display());
+            assert_eq!(root.version, migrate::FORMAT_VERSION);
+            for armature in &root.armatures {
+                let problems = runtime::validate(armature);
+                assert!(problems.is_empty(), "{}: {:?}", path.display(), problems);
+            }
+            root
+        })
+        .collect()
+}
+
+#[test]
+fn every_version_loads() {
+    for version in 0..=migrate::FORMAT_VERSION {
+        corpus(version);
+    }
+}
+
+#[test]
+fn v0_elements_from_ids() {
+    let root = corpus(0)
+        .into_iter()
+        .find(|root| {
+            root.armatures[0]
+                .animations
+                .first()
+                .is_some_and(|a| a.name == "Wave")
+        })
+        .unwrap();
+    let elements: Vec<runtime::AnimElement> = root.armatures[0].animations[0]
+        .keyframes
+        .iter()
+        .map(|kf| kf.element.clone())
+        .collect();
+    // the z-index key is dropped, as the bone has nothing to draw (see v1_to_v2)
+    assert_eq!(
+        elements,
+        [runtime::AnimElement::PositionX, runtime::AnimElement::ScaleY]
+    );
+}
+
+#[test]
+fn v0_meshes_become_rects() {
+    for root in corpus(0) {
+        for bone in &root.armatures[0].bones {
+            assert!(!bone.is_mesh && bone.vertices.is_empty(), "{}", bone.name);
+        }
+    }
+}
+
+#[test]
+fn v0_keeps_editor_data() {
+    let root = corpus(0)
+        .into_iter()
+        .find(|root| root.armatures[0].bones.len() == 2)
+        .unwrap();
+    let armature = &root.armatures[0];
+    assert_eq!(armature.bones[1].name, "Head");
+    assert_eq!(armature.bones[1].rot, 0.25);
+    assert_eq!(armature.textures[1].offset.x, 32.);
+    assert_eq!(
+        armature.animations[0].keyframes[1].transition,
+        runtime::Transition::SineOut
+    );
+}
+
+#[test]
+fn v1_textures_become_slots() {
+    for root in corpus(1) {
+        for armature in &root.armatures {
+            for slot in &armature.slots {
+                let bone = armature.bones.iter().find(|b| b.id == slot.bone_id).unwrap();
+                assert_eq!(slot.name, bone.name);
+            }
+        }
+    }
+
+    let root = corpus(1)
+        .into_iter()
+        .find(|root| root.armatures[0].bones.len() == 4)
+        .unwrap();
+    let slots = &root.armatures[0].slots;
+    let names: Vec<&str> = slots.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Upper", "Lower"]);
+    assert_eq!((slots[0].tex_idx, slots[0].region_idx), (0, 1));
+    assert_eq!(slots[0].color, runtime::Color::WHITE);
+}
+
+#[test]
+fn v1_zindex_keys_become_draw_order() {
+    let root = corpus(1)
+        .into_iter()
+        .find(|root| root.armatures[0].name == "Swap")
+        .unwrap();
+    let armature = &root.armatures[0];
+    let names: Vec<&str> = armature.slots.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Back", "Front"]);
+
+    let keys: Vec<(i32, Vec<usize>)> = armature.animations[0]
+        .keyframes
+        .iter()
+        .filter(|kf| kf.element == runtime::AnimElement::DrawOrder)
+        .map(|kf| (kf.frame, kf.draw_order.clone()))
+        .collect();
+    assert_eq!(keys, [(6, vec![1, 0]), (16, vec![0, 1])]);
+
+    // the other keys are kept, still in order
+    let frames: Vec<i32> = armature.animations[0]
+        .keyframes
+        .iter()
+        .map(|kf| kf.frame)
+        .collect();
+    assert_eq!(frames, [5, 6, 16]);
+
+    assert_eq!(runtime::draw_order(armature, 0, 5.), [0, 1]);
+    assert_eq!(runtime::draw_order(armature, 0, 9.5), [1, 0]);
+}
+
+#[test]
+fn current_is_unchanged() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(format!("tests/corpus/v{}", migrate::FORMAT_VERSION));
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let text = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        let before: Value = serde_json::from_str(&text).unwrap();
+        let mut after = before.clone();
+        migrate::migrate(&mut after).unwrap();
+        assert_eq!(before, after);
+    }
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    let mut json = serde_json::json!({
+        "version": migrate::FORMAT_VERSION + 1,
+        "texture_size": { "x": 0, "y": 0 },
+        "armatures": [],
+    });
+    assert!(migrate::migrate(&mut json).is_err());
+}
@@ -0,0 +1,154 @@
+//! Exported frames are rendered without a window. Skipped where there's no adapter
+//! to render with, not even a software one.
+
+#![cfg(feature = "editor")]
+
+use skelform_lib::export::ExportSettings;
+use skelform_lib::offscreen::Offscreen;
+use skelform_lib::runtime;
+use skelform_lib::shared::*;
+
+// a half transparent red square, centered on a bone at the origin
+fn armature() -> Armature {
+    let size = 64;
+    Armature {
+        bones: vec![Bone {
+            scale: Vec2::new(1., 1.),
+            pivot: Vec2::new(0.5, 0.5),
+            ..Default::default()
+        }],
+        slots: vec![Slot {
+            bone_id: 0,
+            tex_idx: 0,
+            ..Default::default()
+        }],
+        textures: vec![Texture {
+            size: Vec2::new(size as f32, size as f32),
+            pixels: [255, 0, 0, 128].repeat(size * size).into(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn render(offscreen: &Offscreen, transparent: bool) -> image::RgbaImage {
+    let armature = armature();
+    let settings = ExportSettings {
+        width: 32,
+        height: 32,
+        transparent,
+        ..Default::default()
+    };
+    let camera = Camera {
+        zoom: 1.,
+        ..Default::default()
+    };
+    offscreen
+        .render_frame(
+            &armature,
+            &runtime::pose(&armature, 0, 0.),
+            &armature.slots,
+            &[0],
+            &offscreen.bind_groups(&armature.textures),
+            &camera,
+            &settings,
+        )
+        .unwrap()
+}
+
+fn assert_color(pixel: &image::Rgba<u8>, expected: [u8; 4]) {
+    let close = pixel
+        .0
+        .iter()
+        .zip(expected)
+        .all(|(a, b)| a.abs_diff(b) <= 2);
+    assert!(close, "{:?} isn't {:?}", pixel.0, expected);
+}
+
+#[test]
+fn bones_are_drawn_over_the_background() {
+    let Ok(offscreen) = pollster::block_on(Offscreen::headless(true))
+        .or_else(|_| pollster::block_on(Offscreen::headless(false)))
+    else {
+        eprintln!("no graphics adapter, skipping");
+        return;
+    };
+
+    // colors are as they were in the texture, rather than multiplied by their alpha
+    let frame = render(&offscreen, true);
+    assert_color(frame.get_pixel(16, 16), [255, 0, 0, 128]);
+    assert_color(frame.get_pixel(1, 1), [0, 0, 0, 0]);
+
+    let frame = render(&offscreen, false);
+    assert_color(frame.get_pixel(16, 16), [230, 102, 102, 255]);
+    assert_color(frame.get_pixel(1, 1), [204, 204, 204, 255]);
+}
+
+#[test]
+fn animations_are_rendered_at_the_export_fps() {
+    let Ok(offscreen) = pollster::block_on(Offscreen::headless(true))
+        .or_else(|_| pollster::block_on(Offscreen::headless(false)))
+    else {
+        eprintln!("no graphics adapter, skipping");
+        return;
+    };
+
+    let mut armature = armature();
+    armature.animations = vec![Animation {
+        fps: 10,
+        keyframes: vec![Keyframe {
+            frame: 10,
+            ..Default::default()
+        }],
+        ..Default::default()
+    }];
+    let settings = ExportSettings {
+        width: 8,
+        height: 4,
+        fps: 20,
+        ..Default::default()
+    };
+    let camera = Camera::default();
+
+    // twice as many frames as the animation has, rendered one at a time
+    let mut frames = offscreen
+        .render_animation(&armature, 0, &camera, &settings)
+        .unwrap();
+    assert_eq!(frames.len(), 21);
+    let first = frames.next().unwrap();
+    assert_eq!(first.dimensions(), (8, 4));
+    assert_eq!(frames.len(), 20);
+}
+
+#[test]
+fn oversized_exports_are_refused() {
+    let Ok(offscreen) = pollster::block_on(Offscreen::headless(true))
+        .or_else(|_| pollster::block_on(Offscreen::headless(false)))
+    else {
+        eprintln!("no graphics adapter, skipping");
+        return;
+    };
+
+    let armature = armature();
+    let settings = ExportSettings {
+        width: offscreen.max_size() + 1,
+        height: 32,
+        ..Default::default()
+    };
+    let camera = Camera::default();
+    let err = offscreen
+        .render_animation(&armature, 0, &camera, &settings)
+        .err()
+        .unwrap();
+    assert!(err.contains("too big"), "{}", err);
+    let frame = offscreen.render_frame(
+        &armature,
+        &armature.bones,
+        &armature.slots,
+        &[0],
+        &offscreen.bind_groups(&armature.textures),
+        &camera,
+        &settings,
+    );
+    assert!(frame.is_err());
+}
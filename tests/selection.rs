@@ -0,0 +1,176 @@
+//! Selected bones are moved, rotated and scaled together around a shared pivot, as
+//! one step of the history.
+
+#![cfg(feature = "editor")]
+
+mod common;
+
+use common::{assert_near, drag, editor_with};
+use skelform_lib::armature_window::new_bone;
+use skelform_lib::keyframe_editor::new_animation;
+use skelform_lib::shared::*;
+
+// two bones either side of the origin, with a child under the first
+fn three_bones() -> Shared {
+    editor_with(|shared| {
+        for x in [-10., 10., 0.] {
+            let (_, idx) = new_bone(shared, -1);
+            shared.armature.bones[idx].pos = Vec2::new(x, 0.);
+        }
+        shared.armature.bones[2].parent_id = shared.armature.bones[0].id;
+    })
+}
+
+#[test]
+fn selecting_adds_toggles_and_hands_over_the_active_bone() {
+    let mut shared = three_bones();
+    shared.select_bone(0);
+    shared.add_to_selection(1);
+    assert_eq!(shared.selected_bone_idxs(), [0, 1]);
+    assert_eq!(shared.selected_bone_idx, 1);
+
+    shared.toggle_selection(1);
+    assert_eq!(shared.selected_bone_idxs(), [0]);
+    assert_eq!(shared.selected_bone_idx, 0);
+
+    shared.toggle_selection(0);
+    assert!(shared.selected_bone().is_none());
+
+    shared.add_to_selection(2);
+    shared.select_bone(1);
+    assert_eq!(shared.selected_bone_idxs(), [1]);
+}
+
+#[test]
+fn selected_bones_move_together_as_one_step() {
+    let mut shared = three_bones();
+    shared.select_bone(0);
+    shared.add_to_selection(1);
+    // the child follows its parent rather than moving twice
+    shared.add_to_selection(2);
+
+    drag(&mut shared, Vec2::new(0., 0.), Vec2::new(3., 4.));
+    assert_near(shared.armature.bones[0].pos, Vec2::new(-7., 4.));
+    assert_near(shared.armature.bones[1].pos, Vec2::new(13., 4.));
+    assert_near(shared.armature.bones[2].pos, Vec2::new(0., 0.));
+
+    let steps = shared.history.undo_steps();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].label(), "Move 2 bones");
+
+    shared.undo();
+    assert_near(shared.armature.bones[0].pos, Vec2::new(-10., 0.));
+    assert_near(shared.armature.bones[1].pos, Vec2::new(10., 0.));
+}
+
+#[test]
+fn bones_rotate_around_the_pivot() {
+    let mut shared = three_bones();
+    shared.select_bone(0);
+    shared.add_to_selection(1);
+    shared.edit_mode = EditMode::Rotate;
+
+    // a quarter turn around the selection's center
+    drag(&mut shared, Vec2::new(5., 0.), Vec2::new(0., 5.));
+    let quarter = std::f32::consts::FRAC_PI_2;
+    assert_near(shared.armature.bones[0].pos, Vec2::new(0., -10.));
+    assert_near(shared.armature.bones[1].pos, Vec2::new(0., 10.));
+    assert!((shared.armature.bones[1].rot - quarter).abs() < 0.001);
+    assert_eq!(shared.history.undo_steps()[0].label(), "Rotate 2 bones");
+
+    // in place
+    shared.undo();
+    shared.pivot = Pivot::IndividualOrigins;
+    drag(&mut shared, Vec2::new(5., 0.), Vec2::new(0., 5.));
+    assert_near(shared.armature.bones[0].pos, Vec2::new(-10., 0.));
+    assert!((shared.armature.bones[0].rot - quarter).abs() < 0.001);
+}
+
+#[test]
+fn bones_scale_away_from_the_cursor() {
+    let mut shared = three_bones();
+    shared.select_bone(1);
+    shared.edit_mode = EditMode::Scale;
+    shared.pivot = Pivot::Cursor;
+    shared.cursor = Vec2::new(20., 0.);
+
+    drag(&mut shared, Vec2::new(25., 0.), Vec2::new(30., 0.));
+    assert_near(shared.armature.bones[1].pos, Vec2::new(0., 0.));
+    assert_near(shared.armature.bones[1].scale, Vec2::new(2., 2.));
+}
+
+#[test]
+fn children_are_moved_in_their_parents_space() {
+    let mut shared = three_bones();
+    shared.armature.bones[0].rot = std::f32::consts::PI;
+    shared.select_bone(2);
+
+    drag(&mut shared, Vec2::new(0., 0.), Vec2::new(1., 2.));
+    assert_near(shared.armature.bones[2].pos, Vec2::new(-1., -2.));
+}
+
+#[test]
+fn animated_bones_are_all_keyed_in_one_step() {
+    let mut shared = three_bones();
+    new_animation(&mut shared);
+    shared.ui.anim.open = true;
+    shared.ui.anim.selected = 0;
+    shared.ui.anim.selected_frame = 0;
+    shared.record_edits();
+
+    shared.select_bone(0);
+    shared.add_to_selection(1);
+    shared.history.seal();
+    shared.start_transform(Vec2::new(0., 0.), None);
+    // a frame for every step of the drag
+    for x in 1..=5 {
+        shared.transform_selection(Vec2::new(x as f32, 0.), false);
+        shared.record_edits();
+    }
+
+    let keys = &shared.armature.animations[0].keyframes;
+    for bone in 0..2 {
+        let id = shared.armature.bones[bone].id;
+        assert!(keys
+            .iter()
+            .any(|kf| kf.bone_id == id && kf.element == AnimElement::PositionX && kf.value == 5.));
+    }
+    // the armature itself is left as it was
+    assert_eq!(shared.armature.bones[0].pos.x, -10.);
+
+    let steps = shared.history.undo_steps();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1].label(), "Add keyframes @0");
+}
+
+#[test]
+fn undo_forgets_removed_bones_in_the_selection() {
+    let mut shared = three_bones();
+    shared.select_bone(0);
+    new_bone(&mut shared, -1);
+    shared.record_edits();
+    shared.add_to_selection(3);
+    assert_eq!(shared.selected_bone_idxs(), [0, 3]);
+
+    shared.undo();
+    assert_eq!(shared.selected_bones, [shared.armature.bones[0].id]);
+}
+
+#[test]
+fn undo_keeps_the_active_bone_when_others_move() {
+    let mut shared = three_bones();
+    let (a, b) = (shared.armature.bones[0].id, shared.armature.bones[1].id);
+    shared.delete_bone(a);
+    shared.record_edits();
+    let idx = shared.armature.bones.iter().position(|bone| bone.id == b);
+    shared.select_bone(idx.unwrap());
+
+    // A comes back in front of B
+    shared.undo();
+    assert_eq!(shared.selected_bones, [b]);
+    assert_eq!(shared.selected_bone().unwrap().id, b);
+    assert_eq!(shared.selected_bone_idx, 1);
+
+    shared.redo();
+    assert_eq!(shared.selected_bone().unwrap().id, b);
+}